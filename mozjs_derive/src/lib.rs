@@ -0,0 +1,732 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Derives of `ToJSValConvertible` and `FromJSValConvertible`, re-exported
+//! by `mozjs` as `mozjs::conversions::{ToJSVal, FromJSVal}` when its
+//! `derive` feature is enabled.
+//!
+//! Structs with named fields convert to and from WebIDL dictionaries:
+//!
+//! ```ignore
+//! #[derive(ToJSVal, FromJSVal)]
+//! #[jsval(rename_all = "camelCase")]
+//! struct RequestInit {
+//!     method: String,
+//!     #[jsval(default)]
+//!     keep_alive: bool,
+//!     #[jsval(config = "ConversionBehavior::EnforceRange")]
+//!     priority: Option<u8>,
+//! }
+//! ```
+//!
+//! Members are read in lexicographical order of their JS names, as WebIDL
+//! specifies. A member that is `undefined` is taken from its default if it
+//! has one, is `None` if the field is an `Option`, and is a conversion
+//! failure otherwise. `None` fields are left out when converting to JS,
+//! unless they are `required`, in which case they are `null`.
+//!
+//! Enums whose variants have no fields convert to and from strings, like
+//! WebIDL enums. Enums with a `tag` convert to and from objects whose `tag`
+//! member names the variant; variants may have named fields, which are
+//! members of the same object, or a single unnamed field that converts from
+//! the whole object and whose members are copied to it, like `Object.assign`:
+//!
+//! ```ignore
+//! #[derive(ToJSVal, FromJSVal)]
+//! #[jsval(tag = "type", rename_all = "kebab-case")]
+//! enum Message {
+//!     Ping,
+//!     Resize { width: u32, height: u32 },
+//!     Open(RequestInit),
+//! }
+//! ```
+//!
+//! Attributes on the type are `rename_all = "..."`, with one of
+//! `lowercase`, `UPPERCASE`, `camelCase`, `PascalCase`, `snake_case`,
+//! `SCREAMING_SNAKE_CASE` and `kebab-case`, applied to the fields of a
+//! struct or the variants of an enum, and `tag = "..."` for enums.
+//! Variants take `rename = "..."`. Fields take `rename = "..."`, `default`,
+//! `default = "path::to::function"`, `required`, which makes an `Option`
+//! field required, and `config = "expression"`, the conversion configuration,
+//! which defaults to `Default::default()`.
+//!
+//! The generated code refers to the `mozjs` crate by name.
+
+#![deny(missing_docs)]
+
+extern crate proc_macro;
+extern crate proc_macro2;
+#[macro_use]
+extern crate quote;
+extern crate syn;
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use syn::spanned::Spanned;
+use syn::{Attribute, Data, DeriveInput, Error, Expr, ExprPath, Fields, GenericArgument};
+use syn::{Ident, Lit, LitStr, Meta, NestedMeta, PathArguments, Type, WherePredicate};
+
+/// Derives `ToJSValConvertible`. See the crate documentation.
+#[proc_macro_derive(ToJSVal, attributes(jsval))]
+pub fn derive_to_jsval(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as DeriveInput);
+    expand_to_jsval(&input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+/// Derives `FromJSValConvertible`, with `()` as the configuration. See the
+/// crate documentation.
+#[proc_macro_derive(FromJSVal, attributes(jsval))]
+pub fn derive_from_jsval(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as DeriveInput);
+    expand_from_jsval(&input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+/// The arguments of the `#[jsval(...)]` attributes on an item: flags and
+/// `name = "value"` pairs.
+fn parse_args(attrs: &[Attribute]) -> syn::Result<Vec<(Ident, Option<LitStr>)>> {
+    let mut args = vec![];
+    for attr in attrs {
+        if !attr.path.is_ident("jsval") {
+            continue;
+        }
+        let list = match attr.parse_meta()? {
+            Meta::List(list) => list,
+            meta => return Err(Error::new(meta.span(), "expected #[jsval(...)]")),
+        };
+        for nested in list.nested {
+            let (path, value) = match nested {
+                NestedMeta::Meta(Meta::Path(path)) => (path, None),
+                NestedMeta::Meta(Meta::NameValue(pair)) => match pair.lit {
+                    Lit::Str(value) => (pair.path, Some(value)),
+                    lit => return Err(Error::new(lit.span(), "expected a string literal")),
+                },
+                nested => return Err(Error::new(nested.span(), "unexpected attribute")),
+            };
+            match path.get_ident() {
+                Some(ident) => args.push((ident.clone(), value)),
+                None => return Err(Error::new(path.span(), "unexpected attribute")),
+            }
+        }
+    }
+    Ok(args)
+}
+
+fn expect_value(name: &Ident, value: Option<LitStr>) -> syn::Result<LitStr> {
+    value.ok_or_else(|| Error::new(name.span(), format!("expected `{} = \"...\"`", name)))
+}
+
+fn unknown_attribute(name: &Ident) -> Error {
+    Error::new(name.span(), format!("unknown jsval attribute `{}`", name))
+}
+
+#[derive(Clone, Copy)]
+enum RenameRule {
+    Lower,
+    Upper,
+    Camel,
+    Pascal,
+    Snake,
+    ScreamingSnake,
+    Kebab,
+}
+
+impl RenameRule {
+    fn parse(value: &LitStr) -> syn::Result<RenameRule> {
+        Ok(match &*value.value() {
+            "lowercase" => RenameRule::Lower,
+            "UPPERCASE" => RenameRule::Upper,
+            "camelCase" => RenameRule::Camel,
+            "PascalCase" => RenameRule::Pascal,
+            "snake_case" => RenameRule::Snake,
+            "SCREAMING_SNAKE_CASE" => RenameRule::ScreamingSnake,
+            "kebab-case" => RenameRule::Kebab,
+            _ => return Err(Error::new(value.span(), "unknown rename rule")),
+        })
+    }
+
+    /// Renames a Rust identifier in either snake case or Pascal case.
+    fn apply(self, ident: &Ident) -> String {
+        let ident = ident.to_string();
+        let ident = ident.trim_start_matches("r#");
+        let mut words = vec![];
+        let mut word = String::new();
+        let mut previous_lower = false;
+        for c in ident.chars() {
+            if (c == '_' || (c.is_uppercase() && previous_lower)) && !word.is_empty() {
+                words.push(word);
+                word = String::new();
+            }
+            previous_lower = c.is_lowercase() || c.is_numeric();
+            if c != '_' {
+                word.extend(c.to_lowercase());
+            }
+        }
+        if !word.is_empty() {
+            words.push(word);
+        }
+
+        let capitalize = |word: &String| -> String {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect())
+                .unwrap_or_default()
+        };
+        match self {
+            RenameRule::Lower => words.concat(),
+            RenameRule::Upper => words.concat().to_uppercase(),
+            RenameRule::Camel => {
+                let mut renamed = words.first().cloned().unwrap_or_default();
+                renamed.extend(words.iter().skip(1).map(capitalize));
+                renamed
+            }
+            RenameRule::Pascal => words.iter().map(capitalize).collect(),
+            RenameRule::Snake => words.join("_"),
+            RenameRule::ScreamingSnake => words.join("_").to_uppercase(),
+            RenameRule::Kebab => words.join("-"),
+        }
+    }
+}
+
+struct ContainerAttrs {
+    rename_all: Option<RenameRule>,
+    tag: Option<String>,
+}
+
+impl ContainerAttrs {
+    fn parse(attrs: &[Attribute]) -> syn::Result<ContainerAttrs> {
+        let mut container = ContainerAttrs {
+            rename_all: None,
+            tag: None,
+        };
+        for (name, value) in parse_args(attrs)? {
+            if name == "rename_all" {
+                container.rename_all = Some(RenameRule::parse(&expect_value(&name, value)?)?);
+            } else if name == "tag" {
+                container.tag = Some(expect_value(&name, value)?.value());
+            } else {
+                return Err(unknown_attribute(&name));
+            }
+        }
+        Ok(container)
+    }
+
+    fn js_name(&self, ident: &Ident, rename: Option<String>) -> String {
+        rename.unwrap_or_else(|| match self.rename_all {
+            Some(rule) => rule.apply(ident),
+            None => ident.to_string().trim_start_matches("r#").to_owned(),
+        })
+    }
+}
+
+enum DefaultValue {
+    Trait,
+    Function(ExprPath),
+}
+
+/// A struct field, or a field of an enum variant.
+struct Field<'a> {
+    ident: Option<&'a Ident>,
+    ty: &'a Type,
+    js_name: String,
+    default: Option<DefaultValue>,
+    required: bool,
+    config: Option<Expr>,
+}
+
+impl<'a> Field<'a> {
+    fn parse(field: &'a syn::Field, container: &ContainerAttrs) -> syn::Result<Field<'a>> {
+        let mut rename = None;
+        let mut default = None;
+        let mut required = false;
+        let mut config = None;
+        for (name, value) in parse_args(&field.attrs)? {
+            if name == "rename" {
+                rename = Some(expect_value(&name, value)?.value());
+            } else if name == "default" {
+                default = Some(match value {
+                    Some(path) => DefaultValue::Function(path.parse()?),
+                    None => DefaultValue::Trait,
+                });
+            } else if name == "required" {
+                required = true;
+            } else if name == "config" {
+                config = Some(expect_value(&name, value)?.parse()?);
+            } else {
+                return Err(unknown_attribute(&name));
+            }
+        }
+        if required && default.is_some() {
+            return Err(Error::new(
+                field.span(),
+                "a field cannot be both required and defaulted",
+            ));
+        }
+        let js_name = match field.ident {
+            Some(ref ident) => container.js_name(ident, rename),
+            None => String::new(),
+        };
+        Ok(Field {
+            ident: field.ident.as_ref(),
+            ty: &field.ty,
+            js_name,
+            default,
+            required,
+            config,
+        })
+    }
+
+    /// Whether the field is an `Option` that may be missing.
+    fn is_optional(&self) -> bool {
+        !self.required && self.default.is_none() && is_option(self.ty)
+    }
+
+    fn config(&self) -> TokenStream2 {
+        match self.config {
+            Some(ref config) => quote!(#config),
+            None => quote!(::std::default::Default::default()),
+        }
+    }
+}
+
+fn is_option(ty: &Type) -> bool {
+    let path = match *ty {
+        Type::Path(ref path) if path.qself.is_none() => &path.path,
+        _ => return false,
+    };
+    let last = match path.segments.last() {
+        Some(last) => last,
+        None => return false,
+    };
+    match last.arguments {
+        PathArguments::AngleBracketed(ref args) => {
+            last.ident == "Option"
+                && args.args.len() == 1
+                && matches!(args.args[0], GenericArgument::Type(_))
+        }
+        _ => false,
+    }
+}
+
+fn parse_fields<'a>(fields: &'a Fields, container: &ContainerAttrs) -> syn::Result<Vec<Field<'a>>> {
+    fields
+        .iter()
+        .map(|field| Field::parse(field, container))
+        .collect()
+}
+
+fn variant_name(variant: &syn::Variant, container: &ContainerAttrs) -> syn::Result<String> {
+    let mut rename = None;
+    for (name, value) in parse_args(&variant.attrs)? {
+        if name == "rename" {
+            rename = Some(expect_value(&name, value)?.value());
+        } else {
+            return Err(unknown_attribute(&name));
+        }
+    }
+    Ok(container.js_name(&variant.ident, rename))
+}
+
+fn binding(index: usize) -> Ident {
+    Ident::new(&format!("__field{}", index), Span::call_site())
+}
+
+/// Adds a bound on the type of every field to the where clause of a generic
+/// type, so that fields of parameter types convert.
+fn add_bounds(
+    input: &DeriveInput,
+    fields: &[&Field],
+    bound: &dyn Fn(&Field) -> Vec<WherePredicate>,
+) -> syn::Generics {
+    let mut generics = input.generics.clone();
+    if generics.type_params().next().is_some() {
+        let where_clause = generics.make_where_clause();
+        for field in fields {
+            where_clause.predicates.extend(bound(field));
+        }
+    }
+    generics
+}
+
+fn all_fields<'a, 'b>(
+    variants: &'b [(String, &'a syn::Variant, Vec<Field<'a>>)],
+) -> Vec<&'b Field<'a>> {
+    variants
+        .iter()
+        .flat_map(|(_, _, fields)| fields.iter())
+        .collect()
+}
+
+/// Statements that define the members of the dictionary in `rval` from the
+/// bindings of the fields.
+fn write_members(fields: &[Field]) -> TokenStream2 {
+    let mut sorted: Vec<_> = fields.iter().enumerate().collect();
+    sorted.sort_by(|a, b| a.1.js_name.cmp(&b.1.js_name));
+    let members = sorted.into_iter().map(|(index, field)| {
+        let binding = binding(index);
+        let js_name = &field.js_name;
+        if !field.required && is_option(field.ty) {
+            quote! {
+                if let ::std::option::Option::Some(ref member) = *#binding {
+                    ::mozjs::dictionary::set_member(cx, rval.handle(), #js_name, member);
+                }
+            }
+        } else {
+            quote! {
+                ::mozjs::dictionary::set_member(cx, rval.handle(), #js_name, #binding);
+            }
+        }
+    });
+    quote!(#(#members)*)
+}
+
+/// Statements that read the members of the dictionary in `value` into the
+/// bindings of the fields, returning early on failure.
+fn read_members(fields: &[Field]) -> TokenStream2 {
+    let mut sorted: Vec<_> = fields.iter().enumerate().collect();
+    sorted.sort_by(|a, b| a.1.js_name.cmp(&b.1.js_name));
+    let members = sorted.into_iter().map(|(index, field)| {
+        let binding = binding(index);
+        let js_name = &field.js_name;
+        let ty = field.ty;
+        let config = field.config();
+        let missing = match field.default {
+            Some(DefaultValue::Trait) => quote!(::std::default::Default::default()),
+            Some(DefaultValue::Function(ref function)) => quote!(#function()),
+            None if field.is_optional() => quote!(::std::option::Option::None),
+            None => {
+                let message = format!("missing required member '{}'", js_name);
+                quote! {
+                    return Ok(::mozjs::conversions::ConversionResult::Failure(#message.into()))
+                }
+            }
+        };
+        quote! {
+            let #binding = match ::mozjs::dictionary::get_member::<#ty>(
+                cx,
+                value,
+                #js_name,
+                #config,
+            )? {
+                ::mozjs::conversions::ConversionResult::Success(
+                    ::std::option::Option::Some(member),
+                ) => member,
+                ::mozjs::conversions::ConversionResult::Success(::std::option::Option::None) => {
+                    #missing
+                }
+                ::mozjs::conversions::ConversionResult::Failure(e) => {
+                    return Ok(::mozjs::conversions::ConversionResult::Failure(e));
+                }
+            };
+        }
+    });
+    quote!(#(#members)*)
+}
+
+/// The expression `path { field: __field0, ... }`, or the pattern
+/// `path { field: ref __field0, ... }` if `by_ref` is set.
+fn construct(path: TokenStream2, fields: &[Field], by_ref: bool) -> TokenStream2 {
+    let fields = fields.iter().enumerate().map(|(index, field)| {
+        let ident = field.ident;
+        let binding = binding(index);
+        if by_ref {
+            quote!(#ident: ref #binding)
+        } else {
+            quote!(#ident: #binding)
+        }
+    });
+    quote!(#path { #(#fields),* })
+}
+
+fn expand_to_jsval(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let container = ContainerAttrs::parse(&input.attrs)?;
+    let name = &input.ident;
+    let to_bound = |field: &Field| {
+        let ty = field.ty;
+        vec![syn::parse_quote!(#ty: ::mozjs::conversions::ToJSValConvertible)]
+    };
+
+    let (generics, body) = match input.data {
+        Data::Struct(ref data) => {
+            let fields = named_fields(&data.fields, &container)?;
+            if container.tag.is_some() {
+                return Err(Error::new(input.span(), "only enums can have a tag"));
+            }
+            let pattern = construct(quote!(#name), &fields, true);
+            let members = write_members(&fields);
+            let body = quote! {
+                let #pattern = *self;
+                ::mozjs::dictionary::new_dictionary(cx, rval);
+                #members
+            };
+            let fields: Vec<_> = fields.iter().collect();
+            (add_bounds(input, &fields, &to_bound), body)
+        }
+        Data::Enum(ref data) => {
+            let variants = parse_variants(data, &container)?;
+            let arms = variants.iter().map(|&(ref js_name, variant, ref fields)| {
+                let ident = &variant.ident;
+                match (container.tag.as_ref(), &variant.fields) {
+                    (None, _) => quote! {
+                        #name::#ident => #js_name.to_jsval(cx, rval),
+                    },
+                    (Some(tag), Fields::Unnamed(_)) => quote! {
+                        #name::#ident(ref __field0) => {
+                            ::mozjs::dictionary::new_dictionary(cx, rval);
+                            ::mozjs::dictionary::assign_members(cx, rval.handle(), __field0);
+                            ::mozjs::dictionary::set_member(cx, rval.handle(), #tag, #js_name);
+                        }
+                    },
+                    (Some(tag), _) => {
+                        let pattern = construct(quote!(#name::#ident), fields, true);
+                        let members = write_members(fields);
+                        quote! {
+                            #pattern => {
+                                ::mozjs::dictionary::new_dictionary(cx, rval);
+                                ::mozjs::dictionary::set_member(cx, rval.handle(), #tag, #js_name);
+                                #members
+                            }
+                        }
+                    }
+                }
+            });
+            let body = quote! {
+                match *self {
+                    #(#arms)*
+                }
+            };
+            (add_bounds(input, &all_fields(&variants), &to_bound), body)
+        }
+        Data::Union(_) => return Err(Error::new(input.span(), "unions cannot be converted")),
+    };
+
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::mozjs::conversions::ToJSValConvertible for #name #ty_generics
+            #where_clause
+        {
+            #[allow(unused_variables)]
+            unsafe fn to_jsval(
+                &self,
+                cx: *mut ::mozjs::jsapi::JSContext,
+                rval: ::mozjs::rust::MutableHandleValue,
+            ) {
+                use ::mozjs::conversions::ToJSValConvertible;
+                #body
+            }
+        }
+    })
+}
+
+fn expand_from_jsval(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let container = ContainerAttrs::parse(&input.attrs)?;
+    let name = &input.ident;
+    let from_bound = |field: &Field| {
+        let ty = field.ty;
+        let mut predicates: Vec<WherePredicate> =
+            vec![syn::parse_quote!(#ty: ::mozjs::conversions::FromJSValConvertible)];
+        if field.config.is_none() {
+            predicates.push(syn::parse_quote! {
+                <#ty as ::mozjs::conversions::FromJSValConvertible>::Config:
+                    ::std::default::Default
+            });
+        }
+        predicates
+    };
+    let not_an_object = quote! {
+        if !::mozjs::dictionary::is_dictionary(value) {
+            return Ok(::mozjs::conversions::ConversionResult::Failure(
+                "Value is not an object".into(),
+            ));
+        }
+    };
+
+    let (generics, body) = match input.data {
+        Data::Struct(ref data) => {
+            let fields = named_fields(&data.fields, &container)?;
+            if container.tag.is_some() {
+                return Err(Error::new(input.span(), "only enums can have a tag"));
+            }
+            let members = read_members(&fields);
+            let result = construct(quote!(#name), &fields, false);
+            let body = quote! {
+                #not_an_object
+                #members
+                Ok(::mozjs::conversions::ConversionResult::Success(#result))
+            };
+            let fields: Vec<_> = fields.iter().collect();
+            (add_bounds(input, &fields, &from_bound), body)
+        }
+        Data::Enum(ref data) => {
+            let variants = parse_variants(data, &container)?;
+            let body = match container.tag {
+                None => {
+                    let arms = variants.iter().map(|&(ref js_name, variant, _)| {
+                        let ident = &variant.ident;
+                        quote!(#js_name => #name::#ident,)
+                    });
+                    let enumeration = name.to_string();
+                    quote! {
+                        let string = match <::std::string::String as
+                            ::mozjs::conversions::FromJSValConvertible>::from_jsval(
+                                cx, value, ())?
+                        {
+                            ::mozjs::conversions::ConversionResult::Success(string) => string,
+                            ::mozjs::conversions::ConversionResult::Failure(e) => {
+                                return Ok(::mozjs::conversions::ConversionResult::Failure(e));
+                            }
+                        };
+                        Ok(::mozjs::conversions::ConversionResult::Success(match &*string {
+                            #(#arms)*
+                            _ => {
+                                return Ok(::mozjs::conversions::ConversionResult::Failure(
+                                    format!(
+                                        "'{}' is not a valid enum value for enumeration '{}'",
+                                        string,
+                                        #enumeration,
+                                    )
+                                    .into(),
+                                ));
+                            }
+                        }))
+                    }
+                }
+                Some(ref tag) => {
+                    let missing_tag = format!("missing required member '{}'", tag);
+                    let arms = variants.iter().map(|&(ref js_name, variant, ref fields)| {
+                        let ident = &variant.ident;
+                        if let Fields::Unnamed(_) = variant.fields {
+                            let ty = fields[0].ty;
+                            let config = fields[0].config();
+                            quote! {
+                                #js_name => {
+                                    match <#ty as ::mozjs::conversions::FromJSValConvertible>
+                                        ::from_jsval(cx, value, #config)?
+                                    {
+                                        ::mozjs::conversions::ConversionResult::Success(v) => {
+                                            #name::#ident(v)
+                                        }
+                                        ::mozjs::conversions::ConversionResult::Failure(e) => {
+                                            return Ok(
+                                                ::mozjs::conversions::ConversionResult::Failure(e),
+                                            );
+                                        }
+                                    }
+                                }
+                            }
+                        } else {
+                            let members = read_members(fields);
+                            let result = construct(quote!(#name::#ident), fields, false);
+                            quote! {
+                                #js_name => {
+                                    #members
+                                    #result
+                                }
+                            }
+                        }
+                    });
+                    quote! {
+                        #not_an_object
+                        let tag = match ::mozjs::dictionary::get_member::<::std::string::String>(
+                            cx, value, #tag, ())?
+                        {
+                            ::mozjs::conversions::ConversionResult::Success(
+                                ::std::option::Option::Some(tag),
+                            ) => tag,
+                            ::mozjs::conversions::ConversionResult::Success(
+                                ::std::option::Option::None,
+                            ) => {
+                                return Ok(::mozjs::conversions::ConversionResult::Failure(
+                                    #missing_tag.into(),
+                                ));
+                            }
+                            ::mozjs::conversions::ConversionResult::Failure(e) => {
+                                return Ok(::mozjs::conversions::ConversionResult::Failure(e));
+                            }
+                        };
+                        Ok(::mozjs::conversions::ConversionResult::Success(match &*tag {
+                            #(#arms)*
+                            _ => {
+                                return Ok(::mozjs::conversions::ConversionResult::Failure(
+                                    format!("'{}' is not a valid value for member '{}'", tag, #tag)
+                                        .into(),
+                                ));
+                            }
+                        }))
+                    }
+                }
+            };
+            (add_bounds(input, &all_fields(&variants), &from_bound), body)
+        }
+        Data::Union(_) => return Err(Error::new(input.span(), "unions cannot be converted")),
+    };
+
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::mozjs::conversions::FromJSValConvertible for #name #ty_generics
+            #where_clause
+        {
+            type Config = ();
+
+            #[allow(unused_variables)]
+            unsafe fn from_jsval(
+                cx: *mut ::mozjs::jsapi::JSContext,
+                value: ::mozjs::rust::HandleValue,
+                _option: (),
+            ) -> Result<::mozjs::conversions::ConversionResult<Self>, ()> {
+                #body
+            }
+        }
+    })
+}
+
+fn named_fields<'a>(fields: &'a Fields, container: &ContainerAttrs) -> syn::Result<Vec<Field<'a>>> {
+    match *fields {
+        Fields::Named(_) => parse_fields(fields, container),
+        _ => Err(Error::new(
+            fields.span(),
+            "only structs with named fields can be converted",
+        )),
+    }
+}
+
+fn parse_variants<'a>(
+    data: &'a syn::DataEnum,
+    container: &ContainerAttrs,
+) -> syn::Result<Vec<(String, &'a syn::Variant, Vec<Field<'a>>)>> {
+    data.variants
+        .iter()
+        .map(|variant| {
+            match (container.tag.as_ref(), &variant.fields) {
+                (_, Fields::Unit) => {}
+                (None, _) => {
+                    return Err(Error::new(
+                        variant.span(),
+                        "enums with fields need a #[jsval(tag = \"...\")] attribute",
+                    ))
+                }
+                (Some(_), Fields::Unnamed(fields)) if fields.unnamed.len() != 1 => {
+                    return Err(Error::new(
+                        variant.span(),
+                        "tuple variants must have exactly one field",
+                    ))
+                }
+                _ => {}
+            }
+            // Renaming applies to the variants, not to their fields.
+            let fields = parse_fields(
+                &variant.fields,
+                &ContainerAttrs {
+                    rename_all: None,
+                    tag: None,
+                },
+            )?;
+            Ok((variant_name(variant, container)?, variant, fields))
+        })
+        .collect()
+}
@@ -0,0 +1,283 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Element access on rooted JS arrays.
+//!
+//! ```ignore
+//! rooted!(in(cx) let mut result = ptr::null_mut::<JSObject>());
+//! let result = JsArray::new(cx, result.handle_mut())?;
+//! for name in &names {
+//!     result.push(cx, name)?;
+//! }
+//! ```
+//!
+//! Arrays behind proxies are supported: every operation goes through the
+//! ordinary property access paths, so proxy traps run as they would in JS.
+
+#![deny(missing_docs)]
+
+use conversions::{ConversionResult, FromJSValConvertible, ToJSValConvertible};
+use error::{throw_range_error, throw_type_error};
+use jsapi::{GetArrayLength, IsArray, NewArrayObject1, SetArrayLength};
+use jsapi::{JSContext, JSObject, JS_GetElement, JS_SetElement};
+use jsval::UndefinedValue;
+use rust::{Handle, HandleObject, HandleValue, MutableHandleObject, MutableHandleValue};
+
+/// A rooted array, or a proxy for one.
+#[derive(Clone, Copy)]
+pub struct JsArray<'a> {
+    handle: HandleObject<'a>,
+}
+
+impl<'a> JsArray<'a> {
+    /// Creates a new empty array, stored in `root`.
+    ///
+    /// # Failures
+    ///
+    /// Returns `Err` on JSAPI failure, with a pending exception.
+    pub unsafe fn new(
+        cx: *mut JSContext,
+        mut root: MutableHandleObject<'a>,
+    ) -> Result<JsArray<'a>, ()> {
+        root.set(NewArrayObject1(cx, 0));
+        if root.is_null() {
+            return Err(());
+        }
+        Ok(JsArray {
+            handle: Handle::from_raw(root.into()),
+        })
+    }
+
+    /// Wraps a rooted object that is an array, or a proxy for one, as
+    /// `Array.isArray` reports.
+    ///
+    /// # Failures
+    ///
+    /// Throws a `TypeError` and returns `Err` if the object is not an array,
+    /// and returns `Err` on JSAPI failure (for example for a revoked proxy),
+    /// with a pending exception.
+    pub unsafe fn from_handle(
+        cx: *mut JSContext,
+        handle: HandleObject<'a>,
+    ) -> Result<JsArray<'a>, ()> {
+        assert!(!handle.is_null());
+        let mut is_array = false;
+        if !IsArray(cx, handle.into(), &mut is_array) {
+            return Err(());
+        }
+        if !is_array {
+            throw_type_error(cx, "object is not an array");
+            return Err(());
+        }
+        Ok(JsArray { handle })
+    }
+
+    /// Returns whether `value` is an array, or a proxy for one, as
+    /// `Array.isArray` reports.
+    ///
+    /// # Failures
+    ///
+    /// Returns `Err` on JSAPI failure (for example for a revoked proxy), with
+    /// a pending exception.
+    pub unsafe fn is_array(cx: *mut JSContext, value: HandleValue) -> Result<bool, ()> {
+        if !value.is_object() {
+            return Ok(false);
+        }
+        rooted!(in(cx) let obj = value.to_object());
+        let mut is_array = false;
+        if IsArray(cx, obj.handle().into(), &mut is_array) {
+            Ok(is_array)
+        } else {
+            Err(())
+        }
+    }
+
+    /// Returns the root holding the array.
+    pub fn handle(&self) -> HandleObject<'a> {
+        self.handle
+    }
+
+    /// Returns the array.
+    pub fn get_object(&self) -> *mut JSObject {
+        *self.handle
+    }
+
+    /// Returns the value of the array's `length` property.
+    ///
+    /// # Failures
+    ///
+    /// Returns `Err` on JSAPI failure, with a pending exception.
+    pub unsafe fn len(&self, cx: *mut JSContext) -> Result<u32, ()> {
+        let mut length = 0;
+        if GetArrayLength(cx, self.handle.into(), &mut length) {
+            Ok(length)
+        } else {
+            Err(())
+        }
+    }
+
+    /// Returns whether the array has no elements.
+    ///
+    /// # Failures
+    ///
+    /// Returns `Err` on JSAPI failure, with a pending exception.
+    pub unsafe fn is_empty(&self, cx: *mut JSContext) -> Result<bool, ()> {
+        Ok(self.len(cx)? == 0)
+    }
+
+    /// Reads the element at `index` and converts it to `T`. Holes and
+    /// indices past the end read as `undefined`.
+    ///
+    /// # Failures
+    ///
+    /// Returns `Err` on JSAPI failure, with a pending exception.
+    pub unsafe fn get<T: FromJSValConvertible>(
+        &self,
+        cx: *mut JSContext,
+        index: u32,
+        option: T::Config,
+    ) -> Result<ConversionResult<T>, ()> {
+        rooted!(in(cx) let mut value = UndefinedValue());
+        self.get_value(cx, index, value.handle_mut())?;
+        T::from_jsval(cx, value.handle(), option)
+    }
+
+    /// Reads the element at `index` into `rval` without converting it.
+    ///
+    /// # Failures
+    ///
+    /// Returns `Err` on JSAPI failure, with a pending exception.
+    pub unsafe fn get_value(
+        &self,
+        cx: *mut JSContext,
+        index: u32,
+        rval: MutableHandleValue,
+    ) -> Result<(), ()> {
+        if JS_GetElement(cx, self.handle.into(), index, rval.into()) {
+            Ok(())
+        } else {
+            Err(())
+        }
+    }
+
+    /// Assigns `value` to the element at `index`, growing the array if
+    /// needed.
+    ///
+    /// # Failures
+    ///
+    /// Returns `Err` on JSAPI failure, with a pending exception.
+    pub unsafe fn set<T: ToJSValConvertible + ?Sized>(
+        &self,
+        cx: *mut JSContext,
+        index: u32,
+        value: &T,
+    ) -> Result<(), ()> {
+        rooted!(in(cx) let mut val = UndefinedValue());
+        value.to_jsval(cx, val.handle_mut());
+        if JS_SetElement(cx, self.handle.into(), index, val.handle().into()) {
+            Ok(())
+        } else {
+            Err(())
+        }
+    }
+
+    /// Appends `value` to the end of the array.
+    ///
+    /// # Failures
+    ///
+    /// Throws a `RangeError` and returns `Err` if the array is already at
+    /// the maximum length, and returns `Err` on JSAPI failure, with a pending
+    /// exception.
+    pub unsafe fn push<T: ToJSValConvertible + ?Sized>(
+        &self,
+        cx: *mut JSContext,
+        value: &T,
+    ) -> Result<(), ()> {
+        let length = self.len(cx)?;
+        if length == u32::max_value() {
+            throw_range_error(cx, "array length out of range");
+            return Err(());
+        }
+        self.set(cx, length, value)
+    }
+
+    /// Shortens the array to `len` elements. Does nothing if the array is
+    /// already that short.
+    ///
+    /// # Failures
+    ///
+    /// Returns `Err` on JSAPI failure, with a pending exception.
+    pub unsafe fn truncate(&self, cx: *mut JSContext, len: u32) -> Result<(), ()> {
+        if len >= self.len(cx)? {
+            return Ok(());
+        }
+        if SetArrayLength(cx, self.handle.into(), len) {
+            Ok(())
+        } else {
+            Err(())
+        }
+    }
+
+    /// Returns an iterator that reads and converts the elements lazily. The
+    /// length is read once, when the iterator is created.
+    ///
+    /// # Failures
+    ///
+    /// Returns `Err` on JSAPI failure, with a pending exception.
+    pub unsafe fn iter<T: FromJSValConvertible>(
+        &self,
+        cx: *mut JSContext,
+        option: T::Config,
+    ) -> Result<Elements<'a, T>, ()> {
+        Ok(Elements {
+            cx,
+            array: *self,
+            index: 0,
+            length: self.len(cx)?,
+            option,
+        })
+    }
+}
+
+impl<'a> ToJSValConvertible for JsArray<'a> {
+    #[inline]
+    unsafe fn to_jsval(&self, cx: *mut JSContext, rval: MutableHandleValue) {
+        self.get_object().to_jsval(cx, rval)
+    }
+}
+
+/// An iterator over the converted elements of an array, created by
+/// `JsArray::iter`.
+///
+/// Yields `Err` with a pending exception on JSAPI failure.
+pub struct Elements<'a, T: FromJSValConvertible> {
+    cx: *mut JSContext,
+    array: JsArray<'a>,
+    index: u32,
+    length: u32,
+    option: T::Config,
+}
+
+impl<'a, T: FromJSValConvertible> Iterator for Elements<'a, T>
+where
+    T::Config: Clone,
+{
+    type Item = Result<ConversionResult<T>, ()>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.length {
+            return None;
+        }
+        let index = self.index;
+        self.index += 1;
+        Some(unsafe { self.array.get(self.cx, index, self.option.clone()) })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = (self.length - self.index) as usize;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<'a, T: FromJSValConvertible> ExactSizeIterator for Elements<'a, T> where T::Config: Clone {}
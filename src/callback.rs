@@ -0,0 +1,226 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Persistent references to JS callbacks that Rust code invokes later.
+//!
+//! A `Callback` keeps its callable alive through a persistent root, so it can
+//! be stored in ordinary Rust data structures without a custom tracer:
+//!
+//! ```ignore
+//! let listener = Callback::new(cx, func.handle())?;
+//! listeners.push(listener);
+//! // Later:
+//! listeners[0].invoke::<(), _>(cx, HandleValue::undefined(), (event,), ())?;
+//! ```
+//!
+//! Adding and removing a persistent root is constant time, so keeping
+//! thousands of callbacks alive is cheap.
+
+#![deny(missing_docs)]
+
+use conversions::{ConversionResult, FromJSValConvertible, ToJSValConvertible};
+use error::throw_type_error;
+use function::{call, call_value, ToJSArgs};
+use glue::{CreatePersistentRootedObject, DeletePersistentRootedObject, GetPersistentRootedObject};
+use jsapi::{CurrentGlobalOrNull, GetScriptedCallerGlobal, IsCallable};
+use jsapi::{JSAutoRealm, JSContext, JSObject, PersistentRootedObject};
+use jsapi::{JS_ClearPendingException, JS_GetPendingException};
+use jsval::{ObjectValue, UndefinedValue};
+use rust::{maybe_wrap_value, HandleObject, HandleValue, MutableHandleValue};
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// A sink for exceptions thrown by a callback. It receives the exception
+/// value after it has been cleared from the context.
+pub type ExceptionSink = dyn Fn(*mut JSContext, HandleValue);
+
+thread_local!(static INCUMBENT_GLOBALS: RefCell<Vec<*mut JSObject>> = RefCell::new(Vec::new()));
+
+/// Returns the incumbent global of the innermost `Callback::invoke` on this
+/// thread, if any.
+///
+/// Embedders can return this from their job queue's `getIncumbentGlobal`
+/// trap so that promise jobs queued by a callback see the right incumbent.
+pub fn incumbent_global() -> Option<*mut JSObject> {
+    INCUMBENT_GLOBALS.with(|globals| globals.borrow().last().cloned())
+}
+
+/// Makes a global the incumbent one for as long as it is alive.
+struct AutoIncumbentGlobal;
+
+impl AutoIncumbentGlobal {
+    fn new(global: *mut JSObject) -> AutoIncumbentGlobal {
+        INCUMBENT_GLOBALS.with(|globals| globals.borrow_mut().push(global));
+        AutoIncumbentGlobal
+    }
+}
+
+impl Drop for AutoIncumbentGlobal {
+    fn drop(&mut self) {
+        INCUMBENT_GLOBALS.with(|globals| globals.borrow_mut().pop());
+    }
+}
+
+/// A persistently rooted reference to a function or callable object.
+///
+/// The callable stays alive until the `Callback` is dropped, which must
+/// happen before the runtime is destroyed.
+pub struct Callback {
+    callable: *mut PersistentRootedObject,
+    incumbent: *mut PersistentRootedObject,
+    sink: Option<Rc<ExceptionSink>>,
+}
+
+impl Callback {
+    /// Creates a callback for `callable`. The incumbent global is the global
+    /// of the running script, or the current global if no script is running.
+    ///
+    /// # Failures
+    ///
+    /// Throws a `TypeError` and returns `Err` if `callable` is not callable.
+    pub unsafe fn new(cx: *mut JSContext, callable: HandleObject) -> Result<Callback, ()> {
+        if !IsCallable(callable.get()) {
+            throw_type_error(cx, "callback is not callable");
+            return Err(());
+        }
+        let mut incumbent = GetScriptedCallerGlobal(cx);
+        if incumbent.is_null() {
+            incumbent = CurrentGlobalOrNull(cx);
+        }
+        Ok(Callback {
+            callable: CreatePersistentRootedObject(cx, callable.get()),
+            incumbent: CreatePersistentRootedObject(cx, incumbent),
+            sink: None,
+        })
+    }
+
+    /// Returns the callable object.
+    pub fn get(&self) -> *mut JSObject {
+        unsafe { GetPersistentRootedObject(self.callable) }
+    }
+
+    /// Returns the global that is incumbent while the callback runs. This may
+    /// be null if the callback was created outside of any realm.
+    pub fn incumbent(&self) -> *mut JSObject {
+        unsafe { GetPersistentRootedObject(self.incumbent) }
+    }
+
+    /// Sets the sink that receives exceptions thrown by `invoke`. Without a
+    /// sink, exceptions are left pending on the context.
+    ///
+    /// The sink is reference counted so that it can be shared between many
+    /// callbacks.
+    pub fn set_exception_sink(&mut self, sink: Option<Rc<ExceptionSink>>) {
+        self.sink = sink;
+    }
+
+    /// Calls the callback with the given `this` and arguments, in the
+    /// callback's realm and with its incumbent global set, and stores the
+    /// result in `rval`.
+    ///
+    /// The result is in the callback's realm; the caller must wrap it before
+    /// using it elsewhere.
+    ///
+    /// # Failures
+    ///
+    /// Returns `Err` if the callback throws or on JSAPI failure. The
+    /// exception is passed to the exception sink if there is one, and is
+    /// left pending otherwise.
+    pub unsafe fn invoke_value<A: ToJSArgs>(
+        &self,
+        cx: *mut JSContext,
+        this: HandleValue,
+        args: A,
+        rval: MutableHandleValue,
+    ) -> Result<(), ()> {
+        self.invoke_with(cx, this, args, |callee, this, args| {
+            call_value(cx, this, callee, args, rval)
+        })
+    }
+
+    /// Calls the callback like `invoke_value`, and converts the result to
+    /// `R` in the callback's realm.
+    ///
+    /// # Failures
+    ///
+    /// Returns `Err` if the callback or the conversion throws, or on JSAPI
+    /// failure. The exception is passed to the exception sink if there is
+    /// one, and is left pending otherwise.
+    pub unsafe fn invoke<R: FromJSValConvertible, A: ToJSArgs>(
+        &self,
+        cx: *mut JSContext,
+        this: HandleValue,
+        args: A,
+        option: R::Config,
+    ) -> Result<ConversionResult<R>, ()> {
+        self.invoke_with(cx, this, args, |callee, this, args| {
+            call(cx, this, callee, args, option)
+        })
+    }
+
+    unsafe fn invoke_with<A, T, F>(
+        &self,
+        cx: *mut JSContext,
+        this: HandleValue,
+        args: A,
+        body: F,
+    ) -> Result<T, ()>
+    where
+        F: FnOnce(HandleValue, HandleValue, A) -> Result<T, ()>,
+    {
+        rooted!(in(cx) let callable = self.get());
+        rooted!(in(cx) let incumbent = self.incumbent());
+        let result = {
+            let _ar = JSAutoRealm::new(cx, callable.get());
+            let _incumbent = AutoIncumbentGlobal::new(incumbent.get());
+            rooted!(in(cx) let callee = ObjectValue(callable.get()));
+            rooted!(in(cx) let mut this = this.get());
+            maybe_wrap_value(cx, this.handle_mut());
+            body(callee.handle(), this.handle(), args)
+        };
+        if result.is_err() {
+            if let Some(ref sink) = self.sink {
+                rooted!(in(cx) let mut exception = UndefinedValue());
+                if JS_GetPendingException(cx, exception.handle_mut().into()) {
+                    JS_ClearPendingException(cx);
+                    sink(cx, exception.handle());
+                }
+            }
+        }
+        result
+    }
+}
+
+impl Drop for Callback {
+    fn drop(&mut self) {
+        unsafe {
+            DeletePersistentRootedObject(self.callable);
+            DeletePersistentRootedObject(self.incumbent);
+        }
+    }
+}
+
+impl ToJSValConvertible for Callback {
+    #[inline]
+    unsafe fn to_jsval(&self, cx: *mut JSContext, rval: MutableHandleValue) {
+        self.get().to_jsval(cx, rval)
+    }
+}
+
+impl FromJSValConvertible for Callback {
+    type Config = ();
+    unsafe fn from_jsval(
+        cx: *mut JSContext,
+        value: HandleValue,
+        _option: (),
+    ) -> Result<ConversionResult<Callback>, ()> {
+        if !value.is_object() {
+            throw_type_error(cx, "value is not callable");
+            return Err(());
+        }
+        rooted!(in(cx) let callable = value.to_object());
+        Callback::new(cx, callable.handle()).map(ConversionResult::Success)
+    }
+}
@@ -0,0 +1,175 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! JS functions and accessor properties backed by Rust closures.
+//!
+//! Every function created here owns its closure through a small holder
+//! object stored in the function's native reserved slot. The closure is
+//! dropped when the holder is finalized, so it lives exactly as long as the
+//! function object itself.
+
+#![deny(missing_docs)]
+
+use error::throw_type_error;
+use jsapi::JS_GetFunctionObject;
+use jsapi::{CallArgs, JSContext, JSObject};
+use jsapi::{GetFunctionNativeReserved, NewFunctionWithReserved, SetFunctionNativeReserved};
+use jsapi::{JS_DefineUCProperty3, Value};
+use jsval::{ObjectValue, UndefinedValue};
+use owned::{get_object_data, new_object_with_data};
+use panic::wrap_panic;
+use rust::{Handle, HandleObject, HandleValue, MutableHandle, MutableHandleValue};
+
+use std::ffi::CString;
+use std::ptr;
+
+/// The signature of a Rust closure that can be called from JS.
+///
+/// Returning `Err(())` signals that a JS exception is pending.
+pub type NativeClosure = dyn Fn(*mut JSContext, &CallArgs) -> Result<(), ()>;
+
+/// A getter closure: receives the `this` object and stores the result in the
+/// given handle.
+pub type GetterClosure = dyn Fn(*mut JSContext, HandleObject, MutableHandleValue) -> Result<(), ()>;
+
+/// A setter closure: receives the `this` object and the assigned value.
+pub type SetterClosure = dyn Fn(*mut JSContext, HandleObject, HandleValue) -> Result<(), ()>;
+
+/// Native reserved slot of the function object that stores the holder.
+const FUNCTION_HOLDER_SLOT: usize = 0;
+
+unsafe extern "C" fn call_closure(cx: *mut JSContext, argc: u32, vp: *mut Value) -> bool {
+    let args = CallArgs::from_vp(vp, argc);
+    let holder = *GetFunctionNativeReserved(args.callee(), FUNCTION_HOLDER_SLOT);
+    let closure = get_object_data::<Box<NativeClosure>>(holder.to_object()).unwrap();
+
+    let mut result = false;
+    wrap_panic(&mut || result = closure(cx, &args).is_ok());
+    result
+}
+
+/// Creates a new JS function named `name` that calls `closure` whenever it is
+/// invoked. The closure is dropped when the function is garbage collected.
+///
+/// Returns a null pointer with a pending `TypeError` if `name` contains a
+/// NUL character, and on JSAPI failure.
+///
+/// # Safety
+///
+/// - `cx` must be valid and in a realm.
+/// - The closure must not outlive the data it borrows from the stack, which
+///   the `'static` bound enforces.
+pub unsafe fn new_function_from_closure(
+    cx: *mut JSContext,
+    name: &str,
+    nargs: u32,
+    closure: Box<NativeClosure>,
+) -> *mut JSObject {
+    let name = match CString::new(name) {
+        Ok(name) => name,
+        Err(_) => {
+            throw_type_error(cx, "function name contains a NUL character");
+            return ptr::null_mut();
+        }
+    };
+    let fun = NewFunctionWithReserved(cx, Some(call_closure), nargs, 0, name.as_ptr());
+    if fun.is_null() {
+        return ptr::null_mut();
+    }
+    rooted!(in(cx) let fun = JS_GetFunctionObject(fun));
+
+    rooted!(in(cx) let holder = new_object_with_data(cx, Box::new(closure)));
+    if holder.is_null() {
+        return ptr::null_mut();
+    }
+    SetFunctionNativeReserved(fun.get(), FUNCTION_HOLDER_SLOT, &ObjectValue(holder.get()));
+
+    fun.get()
+}
+
+/// Converts the `this` value of an accessor call to an object, throwing a
+/// `TypeError` on primitives.
+unsafe fn this_object(cx: *mut JSContext, args: &CallArgs) -> Result<*mut JSObject, ()> {
+    let thisv = args.thisv();
+    if !thisv.is_object() {
+        throw_type_error(cx, "accessor called on a non-object");
+        return Err(());
+    }
+    Ok(thisv.to_object())
+}
+
+/// Defines an accessor property named `name` on `obj`, backed by the given
+/// getter and setter closures. A missing getter makes the property read as
+/// `undefined`; a missing setter makes assignments fail in strict mode.
+///
+/// `attrs` is a combination of `JSPROP_ENUMERATE` and `JSPROP_PERMANENT`,
+/// the latter making the property non-configurable.
+///
+/// # Failures
+///
+/// Throws a `TypeError` and returns `Err` if `name` contains a NUL
+/// character, and returns `Err` on JSAPI failure.
+///
+/// # Safety
+///
+/// - `cx` must be valid and in the realm of `obj`.
+pub unsafe fn define_accessor(
+    cx: *mut JSContext,
+    obj: HandleObject,
+    name: &str,
+    getter: Option<Box<GetterClosure>>,
+    setter: Option<Box<SetterClosure>>,
+    attrs: u32,
+) -> Result<(), ()> {
+    rooted!(in(cx) let mut getter_obj = ptr::null_mut::<JSObject>());
+    if let Some(getter) = getter {
+        let closure: Box<NativeClosure> = Box::new(move |cx: *mut JSContext, args: &CallArgs| {
+            rooted!(in(cx) let this = this_object(cx, args)?);
+            getter(cx, this.handle(), MutableHandle::from_raw(args.rval()))
+        });
+        getter_obj.set(new_function_from_closure(
+            cx,
+            &format!("get {}", name),
+            0,
+            closure,
+        ));
+        if getter_obj.is_null() {
+            return Err(());
+        }
+    }
+
+    rooted!(in(cx) let mut setter_obj = ptr::null_mut::<JSObject>());
+    if let Some(setter) = setter {
+        let closure: Box<NativeClosure> = Box::new(move |cx: *mut JSContext, args: &CallArgs| {
+            rooted!(in(cx) let this = this_object(cx, args)?);
+            setter(cx, this.handle(), Handle::from_raw(args.get(0)))?;
+            args.rval().set(UndefinedValue());
+            Ok(())
+        });
+        setter_obj.set(new_function_from_closure(
+            cx,
+            &format!("set {}", name),
+            1,
+            closure,
+        ));
+        if setter_obj.is_null() {
+            return Err(());
+        }
+    }
+
+    let name: Vec<u16> = name.encode_utf16().collect();
+    if JS_DefineUCProperty3(
+        cx,
+        obj.into(),
+        name.as_ptr(),
+        name.len(),
+        getter_obj.handle().into(),
+        setter_obj.handle().into(),
+        attrs,
+    ) {
+        Ok(())
+    } else {
+        Err(())
+    }
+}
@@ -16,38 +16,95 @@
 //! | unsigned long           | `u32`                            |
 //! | long long               | `i64`                            |
 //! | unsigned long long      | `u64`                            |
+//! | bigint                  | `AsBigInt<i64>`, `AsBigInt<u64>` |
+//! | bigint                  | `i128`, `u128`, `BigInt`         |
 //! | unrestricted float      | `f32`                            |
 //! | float                   | `Finite<f32>`                    |
 //! | unrestricted double     | `f64`                            |
 //! | double                  | `Finite<f64>`                    |
 //! | USVString               | `String`                         |
 //! | object                  | `*mut JSObject`                  |
+//! | symbol                  | `*mut Symbol`                    |
 //! | nullable types          | `Option<T>`                      |
 //! | sequences               | `Vec<T>`                         |
+//! | record<DOMString, T>    | `HashMap<String, T>`             |
+//! | record<DOMString, T>    | `BTreeMap<String, T>`            |
+//!
+//! JS `Map` and `Set` objects convert through the `AsMap` and `AsSet`
+//! wrappers around Rust maps and sets.
+//!
+//! Some standard library types have conventional JS representations:
+//!
+//! | JS value                  | Type                                         |
+//! |---------------------------|----------------------------------------------|
+//! | string                    | `Box<str>`, `Cow<str>`                       |
+//! | string of one code point  | `char`                                       |
+//! | string                    | `PathBuf`, `OsString`, converted lossily     |
+//! | non-zero integer          | `NonZeroU8` to `NonZeroU64`, and signed      |
+//! | number of milliseconds    | `Duration`                                   |
+//! | `Date`                    | `SystemTime`, `DateTime<Utc>` with `chrono`  |
+//!
+//! `&T`, `Box<T>`, `Rc<T>`, `Arc<T>` and `Cow<T>` convert to JS like `T`.
+//!
+//! Tuples of up to twelve elements and arrays `[T; N]` convert to and from
+//! JS arrays, or any iterable when converting from JS, of exactly that
+//! length.
+//!
+//! With the `derive` feature, `#[derive(ToJSVal, FromJSVal)]` implements the
+//! conversions for structs, as WebIDL dictionaries, and for enums, as WebIDL
+//! enums or as objects tagged with the variant name. See the `mozjs_derive`
+//! crate for the attributes they take.
 
 #![deny(missing_docs)]
 
-use error::throw_type_error;
+use closure::{new_function_from_closure, NativeClosure};
+use error::{throw_range_error, throw_type_error};
 use glue::RUST_JS_NumberValue;
+use iterator::call_return;
 use jsapi::AssertSameCompartment;
+use jsapi::{BigIntFromInt64, BigIntFromUint64, BigIntToString, ToBigInt};
+use jsapi::{CallArgs, IsMapObject, IsSetObject, MapForEach, MapSet, NewMapObject, NewSetObject};
 use jsapi::{ForOfIterator, ForOfIterator_NonIterableBehavior};
+use jsapi::{HandleValueArray, JSProtoKey, JS_CallFunctionValue, JS_GetClassObject};
 use jsapi::{Heap, JS_DefineElement, JS_GetLatin1StringCharsAndLength};
-use jsapi::{JSContext, JSObject, JSString, RootedObject, RootedValue};
+use jsapi::{JSContext, JSObject, JSString, RootedObject, RootedValue, Symbol};
 use jsapi::{JS_DeprecatedStringHasLatin1Chars, JS_NewUCStringCopyN, JSPROP_ENUMERATE};
 use jsapi::{JS_GetTwoByteStringCharsAndLength, NewArrayObject1};
+use jsapi::{JS_NewPlainObject, SetAdd, SetForEach};
+use jsval::{BigIntValue, JSVal, ObjectOrNullValue, ObjectValue, StringValue, SymbolValue};
 use jsval::{BooleanValue, Int32Value, NullValue, UInt32Value, UndefinedValue};
-use jsval::{JSVal, ObjectOrNullValue, ObjectValue, StringValue};
 use libc;
-use num_traits::{Bounded, Zero};
+#[cfg(feature = "num-bigint")]
+use num_bigint;
+#[cfg(feature = "num-bigint")]
+use num_traits::ToPrimitive;
+use num_traits::{Bounded, Float, Zero};
+use object::{KeyFlags, Object};
+use property::{PropertyDescriptor, PropertyKey};
 use rust::maybe_wrap_value;
 use rust::{maybe_wrap_object_or_null_value, maybe_wrap_object_value, ToString};
-use rust::{HandleValue, MutableHandleValue};
+use rust::{Handle, HandleObject, HandleValue, MutableHandleValue};
 use rust::{ToBoolean, ToInt32, ToInt64, ToNumber, ToUint16, ToUint32, ToUint64};
 use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::convert::TryFrom;
+use std::ffi::{OsStr, OsString};
+use std::hash::{BuildHasher, Hash};
 use std::mem;
+use std::num::{NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI8};
+use std::num::{NonZeroU16, NonZeroU32, NonZeroU64, NonZeroU8};
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
 use std::{ptr, slice};
 
+#[cfg(feature = "derive")]
+pub use mozjs_derive::{FromJSVal, ToJSVal};
+
 trait As<O>: Copy {
     fn cast(self) -> O;
 }
@@ -141,6 +198,12 @@ pub enum ConversionBehavior {
     Clamp,
 }
 
+impl Default for ConversionBehavior {
+    fn default() -> ConversionBehavior {
+        ConversionBehavior::Default
+    }
+}
+
 /// Try to cast the number to a smaller type, but
 /// if it doesn't fit, it will return an error.
 unsafe fn enforce_range<D>(cx: *mut JSContext, d: f64) -> Result<ConversionResult<D>, ()>
@@ -189,6 +252,18 @@ impl ToJSValConvertible for () {
     }
 }
 
+/// Ignores the value, for results that are not needed.
+impl FromJSValConvertible for () {
+    type Config = ();
+    unsafe fn from_jsval(
+        _cx: *mut JSContext,
+        _value: HandleValue,
+        _option: (),
+    ) -> Result<ConversionResult<()>, ()> {
+        Ok(ConversionResult::Success(()))
+    }
+}
+
 impl FromJSValConvertible for JSVal {
     type Config = ();
     unsafe fn from_jsval(
@@ -464,6 +539,245 @@ impl FromJSValConvertible for f64 {
     }
 }
 
+/// A floating-point number that is neither NaN nor infinite, for WebIDL's
+/// restricted `float` and `double`.
+///
+/// Converting a non-finite value from JS throws a `TypeError`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+pub struct Finite<T>(T);
+
+impl<T: Float> Finite<T> {
+    /// Wraps `value`, or returns `None` if it is not finite.
+    #[inline]
+    pub fn new(value: T) -> Option<Finite<T>> {
+        if value.is_finite() {
+            Some(Finite(value))
+        } else {
+            None
+        }
+    }
+
+    /// Wraps `value`, panicking if it is not finite.
+    #[inline]
+    pub fn wrap(value: T) -> Finite<T> {
+        assert!(
+            value.is_finite(),
+            "Finite::wrap called with a non-finite value"
+        );
+        Finite(value)
+    }
+
+    /// Returns the number.
+    #[inline]
+    pub fn get(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for Finite<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: ToJSValConvertible> ToJSValConvertible for Finite<T> {
+    #[inline]
+    unsafe fn to_jsval(&self, cx: *mut JSContext, rval: MutableHandleValue) {
+        self.0.to_jsval(cx, rval)
+    }
+}
+
+// https://heycam.github.io/webidl/#es-float
+// https://heycam.github.io/webidl/#es-double
+impl<T: Float + FromJSValConvertible<Config = ()>> FromJSValConvertible for Finite<T> {
+    type Config = ();
+    unsafe fn from_jsval(
+        cx: *mut JSContext,
+        val: HandleValue,
+        _option: (),
+    ) -> Result<ConversionResult<Finite<T>>, ()> {
+        let result = match T::from_jsval(cx, val, ())? {
+            ConversionResult::Success(v) => v,
+            ConversionResult::Failure(e) => return Ok(ConversionResult::Failure(e)),
+        };
+        // Numbers too large for `f32` round to an infinity, which is
+        // rejected too.
+        match Finite::new(result) {
+            Some(v) => Ok(ConversionResult::Success(v)),
+            None => {
+                throw_type_error(cx, "this argument is not a finite floating-point value");
+                Err(())
+            }
+        }
+    }
+}
+
+/// A 64-bit integer that converts to and from a JS `BigInt` instead of a
+/// Number, so that values beyond 2^53 survive the round trip.
+///
+/// The bare `i64` and `u64` implementations follow WebIDL's `long long`,
+/// which goes through Number.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct AsBigInt<T>(pub T);
+
+/// Creates a BigInt from a string of decimal digits, for values that
+/// don't fit in 64 bits.
+unsafe fn decimal_to_bigint(cx: *mut JSContext, digits: &str, rval: MutableHandleValue) {
+    rooted!(in(cx) let mut ctor = ptr::null_mut::<JSObject>());
+    if !JS_GetClassObject(cx, JSProtoKey::JSProto_BigInt, ctor.handle_mut().into()) {
+        panic!("JS_GetClassObject failed");
+    }
+    rooted!(in(cx) let ctor = ObjectValue(ctor.get()));
+    rooted!(in(cx) let mut arg = UndefinedValue());
+    digits.to_jsval(cx, arg.handle_mut());
+    let args = HandleValueArray::from_rooted_slice(slice::from_ref(&*arg));
+    if !JS_CallFunctionValue(
+        cx,
+        HandleObject::null().into(),
+        ctor.handle().into(),
+        &args,
+        rval.into(),
+    ) {
+        panic!("BigInt construction failed");
+    }
+}
+
+/// Converts `val` with ToBigInt and parses its decimal digits, throwing a
+/// RangeError if they don't fit in `T`.
+unsafe fn bigint_from_jsval<T: FromStr>(cx: *mut JSContext, val: HandleValue) -> Result<T, ()> {
+    rooted!(in(cx) let bigint = ToBigInt(cx, val.into()));
+    if bigint.is_null() {
+        return Err(());
+    }
+    let digits = BigIntToString(cx, bigint.handle().into(), 10);
+    if digits.is_null() {
+        return Err(());
+    }
+    match jsstr_to_string(cx, digits).parse() {
+        Ok(value) => Ok(value),
+        Err(_) => {
+            throw_range_error(cx, "BigInt value out of range");
+            Err(())
+        }
+    }
+}
+
+impl ToJSValConvertible for AsBigInt<i64> {
+    #[inline]
+    unsafe fn to_jsval(&self, cx: *mut JSContext, mut rval: MutableHandleValue) {
+        let bigint = BigIntFromInt64(cx, self.0);
+        if bigint.is_null() {
+            panic!("BigIntFromInt64 failed");
+        }
+        rval.set(BigIntValue(&*bigint));
+    }
+}
+
+impl FromJSValConvertible for AsBigInt<i64> {
+    type Config = ();
+    unsafe fn from_jsval(
+        cx: *mut JSContext,
+        val: HandleValue,
+        _option: (),
+    ) -> Result<ConversionResult<AsBigInt<i64>>, ()> {
+        bigint_from_jsval(cx, val)
+            .map(AsBigInt)
+            .map(ConversionResult::Success)
+    }
+}
+
+impl ToJSValConvertible for AsBigInt<u64> {
+    #[inline]
+    unsafe fn to_jsval(&self, cx: *mut JSContext, mut rval: MutableHandleValue) {
+        let bigint = BigIntFromUint64(cx, self.0);
+        if bigint.is_null() {
+            panic!("BigIntFromUint64 failed");
+        }
+        rval.set(BigIntValue(&*bigint));
+    }
+}
+
+impl FromJSValConvertible for AsBigInt<u64> {
+    type Config = ();
+    unsafe fn from_jsval(
+        cx: *mut JSContext,
+        val: HandleValue,
+        _option: (),
+    ) -> Result<ConversionResult<AsBigInt<u64>>, ()> {
+        bigint_from_jsval(cx, val)
+            .map(AsBigInt)
+            .map(ConversionResult::Success)
+    }
+}
+
+impl ToJSValConvertible for i128 {
+    unsafe fn to_jsval(&self, cx: *mut JSContext, rval: MutableHandleValue) {
+        if *self >= i64::min_value() as i128 && *self <= i64::max_value() as i128 {
+            AsBigInt(*self as i64).to_jsval(cx, rval);
+        } else if *self >= 0 && *self <= u64::max_value() as i128 {
+            AsBigInt(*self as u64).to_jsval(cx, rval);
+        } else {
+            decimal_to_bigint(cx, &self.to_string(), rval);
+        }
+    }
+}
+
+impl FromJSValConvertible for i128 {
+    type Config = ();
+    unsafe fn from_jsval(
+        cx: *mut JSContext,
+        val: HandleValue,
+        _option: (),
+    ) -> Result<ConversionResult<i128>, ()> {
+        bigint_from_jsval(cx, val).map(ConversionResult::Success)
+    }
+}
+
+impl ToJSValConvertible for u128 {
+    unsafe fn to_jsval(&self, cx: *mut JSContext, rval: MutableHandleValue) {
+        if *self <= u64::max_value() as u128 {
+            AsBigInt(*self as u64).to_jsval(cx, rval);
+        } else {
+            decimal_to_bigint(cx, &self.to_string(), rval);
+        }
+    }
+}
+
+impl FromJSValConvertible for u128 {
+    type Config = ();
+    unsafe fn from_jsval(
+        cx: *mut JSContext,
+        val: HandleValue,
+        _option: (),
+    ) -> Result<ConversionResult<u128>, ()> {
+        bigint_from_jsval(cx, val).map(ConversionResult::Success)
+    }
+}
+
+#[cfg(feature = "num-bigint")]
+impl ToJSValConvertible for num_bigint::BigInt {
+    unsafe fn to_jsval(&self, cx: *mut JSContext, rval: MutableHandleValue) {
+        match self.to_i64() {
+            Some(value) => AsBigInt(value).to_jsval(cx, rval),
+            None => decimal_to_bigint(cx, &self.to_str_radix(10), rval),
+        }
+    }
+}
+
+#[cfg(feature = "num-bigint")]
+impl FromJSValConvertible for num_bigint::BigInt {
+    type Config = ();
+    unsafe fn from_jsval(
+        cx: *mut JSContext,
+        val: HandleValue,
+        _option: (),
+    ) -> Result<ConversionResult<num_bigint::BigInt>, ()> {
+        bigint_from_jsval(cx, val).map(ConversionResult::Success)
+    }
+}
+
 /// Converts a `JSString`, encoded in "Latin1" (i.e. U+0000-U+00FF encoded as 0x00-0xFF) into a
 /// `String`.
 pub unsafe fn latin1_to_string(cx: *mut JSContext, s: *mut JSString) -> String {
@@ -563,27 +877,242 @@ impl<T: FromJSValConvertible> FromJSValConvertible for Option<T> {
     }
 }
 
-impl<T: ToJSValConvertible> ToJSValConvertible for &'_ T {
+impl<T: ToJSValConvertible + ?Sized> ToJSValConvertible for &'_ T {
+    #[inline]
+    unsafe fn to_jsval(&self, cx: *mut JSContext, rval: MutableHandleValue) {
+        (**self).to_jsval(cx, rval)
+    }
+}
+
+impl<T: ToJSValConvertible + ?Sized> ToJSValConvertible for Box<T> {
+    #[inline]
+    unsafe fn to_jsval(&self, cx: *mut JSContext, rval: MutableHandleValue) {
+        (**self).to_jsval(cx, rval)
+    }
+}
+
+impl<T: ToJSValConvertible + ?Sized> ToJSValConvertible for Rc<T> {
     #[inline]
     unsafe fn to_jsval(&self, cx: *mut JSContext, rval: MutableHandleValue) {
         (**self).to_jsval(cx, rval)
     }
 }
 
-impl<T: ToJSValConvertible> ToJSValConvertible for Box<T> {
+impl<T: ToJSValConvertible + ?Sized> ToJSValConvertible for Arc<T> {
     #[inline]
     unsafe fn to_jsval(&self, cx: *mut JSContext, rval: MutableHandleValue) {
         (**self).to_jsval(cx, rval)
     }
 }
 
-impl<T: ToJSValConvertible> ToJSValConvertible for Rc<T> {
+impl<'a, T: ToJSValConvertible + ToOwned + ?Sized> ToJSValConvertible for Cow<'a, T> {
     #[inline]
     unsafe fn to_jsval(&self, cx: *mut JSContext, rval: MutableHandleValue) {
         (**self).to_jsval(cx, rval)
     }
 }
 
+/// Converts a value to a `String`, and then to `T`.
+unsafe fn from_string<T, F>(
+    cx: *mut JSContext,
+    value: HandleValue,
+    convert: F,
+) -> Result<ConversionResult<T>, ()>
+where
+    F: FnOnce(String) -> ConversionResult<T>,
+{
+    Ok(match String::from_jsval(cx, value, ())? {
+        ConversionResult::Success(string) => convert(string),
+        ConversionResult::Failure(e) => ConversionResult::Failure(e),
+    })
+}
+
+// https://heycam.github.io/webidl/#es-USVString
+impl FromJSValConvertible for Box<str> {
+    type Config = ();
+    unsafe fn from_jsval(
+        cx: *mut JSContext,
+        value: HandleValue,
+        _: (),
+    ) -> Result<ConversionResult<Box<str>>, ()> {
+        from_string(cx, value, |s| ConversionResult::Success(s.into_boxed_str()))
+    }
+}
+
+// https://heycam.github.io/webidl/#es-USVString
+impl<'a> FromJSValConvertible for Cow<'a, str> {
+    type Config = ();
+    unsafe fn from_jsval(
+        cx: *mut JSContext,
+        value: HandleValue,
+        _: (),
+    ) -> Result<ConversionResult<Cow<'a, str>>, ()> {
+        from_string(cx, value, |s| ConversionResult::Success(Cow::Owned(s)))
+    }
+}
+
+impl ToJSValConvertible for char {
+    #[inline]
+    unsafe fn to_jsval(&self, cx: *mut JSContext, rval: MutableHandleValue) {
+        self.encode_utf8(&mut [0; 4]).to_jsval(cx, rval)
+    }
+}
+
+/// Converts a string of exactly one code point, after the conversion to
+/// `String`.
+impl FromJSValConvertible for char {
+    type Config = ();
+    unsafe fn from_jsval(
+        cx: *mut JSContext,
+        value: HandleValue,
+        _: (),
+    ) -> Result<ConversionResult<char>, ()> {
+        from_string(cx, value, |s| {
+            let mut chars = s.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => ConversionResult::Success(c),
+                _ => ConversionResult::Failure("Value is not a single character".into()),
+            }
+        })
+    }
+}
+
+macro_rules! impl_non_zero {
+    ($NonZero:ident, $T:ty) => {
+        impl ToJSValConvertible for $NonZero {
+            #[inline]
+            unsafe fn to_jsval(&self, cx: *mut JSContext, rval: MutableHandleValue) {
+                self.get().to_jsval(cx, rval)
+            }
+        }
+
+        /// Converts like the underlying integer type, and fails if the
+        /// result is zero.
+        impl FromJSValConvertible for $NonZero {
+            type Config = ConversionBehavior;
+            unsafe fn from_jsval(
+                cx: *mut JSContext,
+                value: HandleValue,
+                option: ConversionBehavior,
+            ) -> Result<ConversionResult<$NonZero>, ()> {
+                Ok(match <$T>::from_jsval(cx, value, option)? {
+                    ConversionResult::Success(v) => match $NonZero::new(v) {
+                        Some(v) => ConversionResult::Success(v),
+                        None => ConversionResult::Failure("Value is zero".into()),
+                    },
+                    ConversionResult::Failure(e) => ConversionResult::Failure(e),
+                })
+            }
+        }
+    };
+}
+
+impl_non_zero!(NonZeroI8, i8);
+impl_non_zero!(NonZeroU8, u8);
+impl_non_zero!(NonZeroI16, i16);
+impl_non_zero!(NonZeroU16, u16);
+impl_non_zero!(NonZeroI32, i32);
+impl_non_zero!(NonZeroU32, u32);
+impl_non_zero!(NonZeroI64, i64);
+impl_non_zero!(NonZeroU64, u64);
+
+/// Converts to a number of milliseconds.
+impl ToJSValConvertible for Duration {
+    #[inline]
+    unsafe fn to_jsval(&self, cx: *mut JSContext, rval: MutableHandleValue) {
+        duration_to_millis(*self).to_jsval(cx, rval)
+    }
+}
+
+/// Converts from a number of milliseconds, which must be finite and not
+/// negative. Fractions of a millisecond are kept, to nanosecond precision.
+impl FromJSValConvertible for Duration {
+    type Config = ();
+    unsafe fn from_jsval(
+        cx: *mut JSContext,
+        value: HandleValue,
+        _: (),
+    ) -> Result<ConversionResult<Duration>, ()> {
+        let millis = ToNumber(cx, value)?;
+        Ok(match millis_to_duration(millis) {
+            Some(duration) => ConversionResult::Success(duration),
+            None => ConversionResult::Failure(
+                "Duration is not a finite, non-negative number of milliseconds".into(),
+            ),
+        })
+    }
+}
+
+pub(crate) fn duration_to_millis(duration: Duration) -> f64 {
+    duration.as_secs() as f64 * 1000.0 + duration.subsec_nanos() as f64 / 1_000_000.0
+}
+
+pub(crate) fn millis_to_duration(millis: f64) -> Option<Duration> {
+    let secs = millis / 1000.0;
+    if secs.is_finite() && secs >= 0.0 && secs < u64::max_value() as f64 {
+        Some(Duration::from_secs_f64(secs))
+    } else {
+        None
+    }
+}
+
+/// Converts to a string. Paths that are not valid Unicode are converted
+/// lossily, with U+FFFD REPLACEMENT CHARACTER.
+impl ToJSValConvertible for Path {
+    #[inline]
+    unsafe fn to_jsval(&self, cx: *mut JSContext, rval: MutableHandleValue) {
+        self.as_os_str().to_jsval(cx, rval)
+    }
+}
+
+/// Converts to a string, like `Path`.
+impl ToJSValConvertible for PathBuf {
+    #[inline]
+    unsafe fn to_jsval(&self, cx: *mut JSContext, rval: MutableHandleValue) {
+        self.as_path().to_jsval(cx, rval)
+    }
+}
+
+/// Converts from a string, like `String`.
+impl FromJSValConvertible for PathBuf {
+    type Config = ();
+    unsafe fn from_jsval(
+        cx: *mut JSContext,
+        value: HandleValue,
+        _: (),
+    ) -> Result<ConversionResult<PathBuf>, ()> {
+        from_string(cx, value, |s| ConversionResult::Success(s.into()))
+    }
+}
+
+/// Converts to a string, like `Path`.
+impl ToJSValConvertible for OsStr {
+    #[inline]
+    unsafe fn to_jsval(&self, cx: *mut JSContext, rval: MutableHandleValue) {
+        self.to_string_lossy().to_jsval(cx, rval)
+    }
+}
+
+/// Converts to a string, like `Path`.
+impl ToJSValConvertible for OsString {
+    #[inline]
+    unsafe fn to_jsval(&self, cx: *mut JSContext, rval: MutableHandleValue) {
+        self.as_os_str().to_jsval(cx, rval)
+    }
+}
+
+/// Converts from a string, like `String`.
+impl FromJSValConvertible for OsString {
+    type Config = ();
+    unsafe fn from_jsval(
+        cx: *mut JSContext,
+        value: HandleValue,
+        _: (),
+    ) -> Result<ConversionResult<OsString>, ()> {
+        from_string(cx, value, |s| ConversionResult::Success(s.into()))
+    }
+}
+
 // https://heycam.github.io/webidl/#es-sequence
 impl<T: ToJSValConvertible> ToJSValConvertible for [T] {
     #[inline]
@@ -620,12 +1149,12 @@ impl<T: ToJSValConvertible> ToJSValConvertible for Vec<T> {
 /// Behaves like RootedGuard (roots on creation, unroots on drop),
 /// but borrows and allows access to the whole ForOfIterator, so
 /// that methods on ForOfIterator can still be used through it.
-struct ForOfIteratorGuard<'a> {
-    root: &'a mut ForOfIterator,
+pub(crate) struct ForOfIteratorGuard<'a> {
+    pub(crate) root: &'a mut ForOfIterator,
 }
 
 impl<'a> ForOfIteratorGuard<'a> {
-    fn new(cx: *mut JSContext, root: &'a mut ForOfIterator) -> Self {
+    pub(crate) fn new(cx: *mut JSContext, root: &'a mut ForOfIterator) -> Self {
         unsafe {
             root.iterator.add_to_root_stack(cx);
         }
@@ -706,6 +1235,625 @@ impl<C: Clone, T: FromJSValConvertible<Config = C>> FromJSValConvertible for Vec
     }
 }
 
+/// Converts a value to a Rust element of a sequence, throwing a `TypeError`
+/// if the conversion fails.
+unsafe fn element_from_jsval<T: FromJSValConvertible>(
+    cx: *mut JSContext,
+    value: HandleValue,
+    option: T::Config,
+) -> Result<T, ()> {
+    match T::from_jsval(cx, value, option)? {
+        ConversionResult::Success(v) => Ok(v),
+        ConversionResult::Failure(e) => {
+            throw_type_error(cx, &e);
+            Err(())
+        }
+    }
+}
+
+/// Iterates over an iterable that must have exactly `length` elements, as
+/// the tuple and array conversions do, calling `convert` with the index and
+/// value of each element.
+///
+/// The iterator is closed if it has too many elements.
+unsafe fn fixed_length_from_jsval<F>(
+    cx: *mut JSContext,
+    value: HandleValue,
+    length: usize,
+    mut convert: F,
+) -> Result<ConversionResult<()>, ()>
+where
+    F: FnMut(usize, HandleValue) -> Result<(), ()>,
+{
+    if !value.is_object() {
+        return Ok(ConversionResult::Failure("Value is not an object".into()));
+    }
+
+    // See the conversion to `Vec<T>` for why the iterator starts out zeroed.
+    let zero = mem::zeroed();
+    let mut iterator = ForOfIterator {
+        cx_: cx,
+        iterator: RootedObject::new_unrooted(),
+        nextMethod: RootedValue::new_unrooted(),
+        index: ::std::u32::MAX, // NOT_ARRAY
+        ..zero
+    };
+    let iterator = ForOfIteratorGuard::new(cx, &mut iterator);
+    let iterator = &mut *iterator.root;
+
+    if !iterator.init(
+        value.into(),
+        ForOfIterator_NonIterableBehavior::AllowNonIterable,
+    ) {
+        return Err(());
+    }
+
+    if iterator.iterator.ptr.is_null() {
+        return Ok(ConversionResult::Failure("Value is not iterable".into()));
+    }
+
+    let mut index = 0;
+    loop {
+        let mut done = false;
+        rooted!(in(cx) let mut val = UndefinedValue());
+        if !iterator.next(val.handle_mut().into(), &mut done) {
+            return Err(());
+        }
+
+        if done {
+            break;
+        }
+
+        if index == length {
+            // Arrays are iterated by index, with no iterator to close.
+            if iterator.index == ::std::u32::MAX {
+                call_return(cx, Handle::from_marked_location(&iterator.iterator.ptr))?;
+            }
+            return Ok(ConversionResult::Failure(
+                format!("Expected {} elements, got more", length).into(),
+            ));
+        }
+
+        convert(index, val.handle())?;
+        index += 1;
+    }
+
+    if index < length {
+        return Ok(ConversionResult::Failure(
+            format!("Expected {} elements, got {}", length, index).into(),
+        ));
+    }
+    Ok(ConversionResult::Success(()))
+}
+
+/// Creates an array holding the converted elements.
+unsafe fn elements_to_jsval(
+    cx: *mut JSContext,
+    elements: &[&dyn ToJSValConvertible],
+    mut rval: MutableHandleValue,
+) {
+    rooted!(in(cx) let js_array = NewArrayObject1(cx, elements.len() as libc::size_t));
+    assert!(!js_array.handle().is_null());
+
+    rooted!(in(cx) let mut val = UndefinedValue());
+    for (index, element) in elements.iter().enumerate() {
+        element.to_jsval(cx, val.handle_mut());
+
+        assert!(JS_DefineElement(
+            cx,
+            js_array.handle().into(),
+            index as u32,
+            val.handle().into(),
+            JSPROP_ENUMERATE as u32
+        ));
+    }
+
+    rval.set(ObjectValue(js_array.handle().get()));
+}
+
+macro_rules! impl_tuple {
+    ($length:expr; $($T:ident $index:tt),+) => {
+        impl<$($T: ToJSValConvertible),+> ToJSValConvertible for ($($T,)+) {
+            #[inline]
+            unsafe fn to_jsval(&self, cx: *mut JSContext, rval: MutableHandleValue) {
+                elements_to_jsval(cx, &[$(&self.$index),+], rval)
+            }
+        }
+
+        impl<$($T: FromJSValConvertible),+> FromJSValConvertible for ($($T,)+) {
+            type Config = ($($T::Config,)+);
+
+            unsafe fn from_jsval(
+                cx: *mut JSContext,
+                value: HandleValue,
+                option: ($($T::Config,)+),
+            ) -> Result<ConversionResult<($($T,)+)>, ()> {
+                let mut option = ($(Some(option.$index),)+);
+                let mut elements: ($(Option<$T>,)+) = ($(None::<$T>,)+);
+                let result = fixed_length_from_jsval(cx, value, $length, |index, value| {
+                    match index {
+                        $($index => {
+                            let option = option.$index.take().unwrap();
+                            elements.$index = Some(element_from_jsval(cx, value, option)?);
+                        })+
+                        _ => unreachable!(),
+                    }
+                    Ok(())
+                })?;
+                Ok(match result {
+                    ConversionResult::Success(()) => {
+                        ConversionResult::Success(($(elements.$index.unwrap(),)+))
+                    }
+                    ConversionResult::Failure(e) => ConversionResult::Failure(e),
+                })
+            }
+        }
+    };
+}
+
+impl_tuple!(1; A 0);
+impl_tuple!(2; A 0, B 1);
+impl_tuple!(3; A 0, B 1, C 2);
+impl_tuple!(4; A 0, B 1, C 2, D 3);
+impl_tuple!(5; A 0, B 1, C 2, D 3, E 4);
+impl_tuple!(6; A 0, B 1, C 2, D 3, E 4, F 5);
+impl_tuple!(7; A 0, B 1, C 2, D 3, E 4, F 5, G 6);
+impl_tuple!(8; A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7);
+impl_tuple!(9; A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8);
+impl_tuple!(10; A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9);
+impl_tuple!(11; A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9, K 10);
+impl_tuple!(12; A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9, K 10, L 11);
+
+impl<T: ToJSValConvertible, const N: usize> ToJSValConvertible for [T; N] {
+    #[inline]
+    unsafe fn to_jsval(&self, cx: *mut JSContext, rval: MutableHandleValue) {
+        <[_]>::to_jsval(self, cx, rval)
+    }
+}
+
+impl<C: Clone, T: FromJSValConvertible<Config = C>, const N: usize> FromJSValConvertible
+    for [T; N]
+{
+    type Config = C;
+
+    unsafe fn from_jsval(
+        cx: *mut JSContext,
+        value: HandleValue,
+        option: C,
+    ) -> Result<ConversionResult<[T; N]>, ()> {
+        let mut elements = Vec::with_capacity(N);
+        let result = fixed_length_from_jsval(cx, value, N, |_, value| {
+            elements.push(element_from_jsval(cx, value, option.clone())?);
+            Ok(())
+        })?;
+        Ok(match result {
+            ConversionResult::Success(()) => match <[T; N]>::try_from(elements) {
+                Ok(array) => ConversionResult::Success(array),
+                Err(_) => unreachable!(),
+            },
+            ConversionResult::Failure(e) => ConversionResult::Failure(e),
+        })
+    }
+}
+
+/// Creates a plain object with an enumerable data property for each entry.
+unsafe fn record_to_jsval<'a, T, I>(cx: *mut JSContext, entries: I, mut rval: MutableHandleValue)
+where
+    T: ToJSValConvertible + 'a,
+    I: Iterator<Item = (&'a String, &'a T)>,
+{
+    rooted!(in(cx) let obj = JS_NewPlainObject(cx));
+    assert!(!obj.is_null());
+    let record = Object::new(obj.handle());
+    for (key, value) in entries {
+        assert!(record
+            .define(cx, key.as_str(), value, JSPROP_ENUMERATE as u32)
+            .is_ok());
+    }
+    rval.set(ObjectValue(obj.get()));
+}
+
+/// Reads the own enumerable properties of an object, as the WebIDL
+/// conversion to `record<DOMString, T>` does.
+// https://heycam.github.io/webidl/#es-record
+unsafe fn record_from_jsval<C: Clone, T: FromJSValConvertible<Config = C>>(
+    cx: *mut JSContext,
+    value: HandleValue,
+    option: C,
+) -> Result<ConversionResult<Vec<(String, T)>>, ()> {
+    if !value.is_object() {
+        return Ok(ConversionResult::Failure("Value is not an object".into()));
+    }
+    rooted!(in(cx) let obj = value.to_object());
+    let record = Object::new(obj.handle());
+    let flags = KeyFlags {
+        inherited: false,
+        symbols: true,
+        non_enumerable: true,
+    };
+
+    let mut entries = vec![];
+    for key in record.keys(cx, flags)? {
+        let key = key?;
+        match record.get_own_property_descriptor(cx, key.clone())? {
+            Some(PropertyDescriptor::Data { enumerable, .. })
+            | Some(PropertyDescriptor::Accessor { enumerable, .. })
+                if enumerable => {}
+            _ => continue,
+        }
+        let name = match key {
+            PropertyKey::Index(index) => index.to_string(),
+            PropertyKey::String(ref name) => name.clone(),
+            PropertyKey::Symbol(_) => {
+                throw_type_error(cx, "record keys cannot be symbols");
+                return Err(());
+            }
+        };
+        match record.get::<T, _>(cx, key, option.clone())? {
+            ConversionResult::Success(value) => entries.push((name, value)),
+            ConversionResult::Failure(e) => {
+                throw_type_error(cx, &e);
+                return Err(());
+            }
+        }
+    }
+    Ok(ConversionResult::Success(entries))
+}
+
+impl<T: ToJSValConvertible, S: BuildHasher> ToJSValConvertible for HashMap<String, T, S> {
+    #[inline]
+    unsafe fn to_jsval(&self, cx: *mut JSContext, rval: MutableHandleValue) {
+        record_to_jsval(cx, self.iter(), rval)
+    }
+}
+
+impl<C, T, S> FromJSValConvertible for HashMap<String, T, S>
+where
+    C: Clone,
+    T: FromJSValConvertible<Config = C>,
+    S: BuildHasher + Default,
+{
+    type Config = C;
+
+    unsafe fn from_jsval(
+        cx: *mut JSContext,
+        value: HandleValue,
+        option: C,
+    ) -> Result<ConversionResult<HashMap<String, T, S>>, ()> {
+        Ok(match record_from_jsval(cx, value, option)? {
+            ConversionResult::Success(entries) => {
+                ConversionResult::Success(entries.into_iter().collect())
+            }
+            ConversionResult::Failure(e) => ConversionResult::Failure(e),
+        })
+    }
+}
+
+impl<T: ToJSValConvertible> ToJSValConvertible for BTreeMap<String, T> {
+    #[inline]
+    unsafe fn to_jsval(&self, cx: *mut JSContext, rval: MutableHandleValue) {
+        record_to_jsval(cx, self.iter(), rval)
+    }
+}
+
+impl<C: Clone, T: FromJSValConvertible<Config = C>> FromJSValConvertible for BTreeMap<String, T> {
+    type Config = C;
+
+    unsafe fn from_jsval(
+        cx: *mut JSContext,
+        value: HandleValue,
+        option: C,
+    ) -> Result<ConversionResult<BTreeMap<String, T>>, ()> {
+        Ok(match record_from_jsval(cx, value, option)? {
+            ConversionResult::Success(entries) => {
+                ConversionResult::Success(entries.into_iter().collect())
+            }
+            ConversionResult::Failure(e) => ConversionResult::Failure(e),
+        })
+    }
+}
+
+/// A Rust map that converts to and from a JS `Map`, rather than a plain
+/// object like the bare `HashMap` and `BTreeMap` implementations.
+///
+/// Keys and values may be of any convertible type. When converting from JS,
+/// the configuration is a pair of the key and value configurations.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct AsMap<M>(pub M);
+
+/// A Rust set that converts to and from a JS `Set`, rather than an array.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct AsSet<S>(pub S);
+
+unsafe fn map_to_jsval<'a, K, V, I>(cx: *mut JSContext, entries: I, mut rval: MutableHandleValue)
+where
+    K: ToJSValConvertible + 'a,
+    V: ToJSValConvertible + 'a,
+    I: Iterator<Item = (&'a K, &'a V)>,
+{
+    rooted!(in(cx) let map = NewMapObject(cx));
+    assert!(!map.is_null());
+    rooted!(in(cx) let mut key = UndefinedValue());
+    rooted!(in(cx) let mut value = UndefinedValue());
+    for (k, v) in entries {
+        k.to_jsval(cx, key.handle_mut());
+        v.to_jsval(cx, value.handle_mut());
+        assert!(MapSet(
+            cx,
+            map.handle().into(),
+            key.handle().into(),
+            value.handle().into()
+        ));
+    }
+    rval.set(ObjectValue(map.get()));
+}
+
+unsafe fn set_to_jsval<'a, T, I>(cx: *mut JSContext, values: I, mut rval: MutableHandleValue)
+where
+    T: ToJSValConvertible + 'a,
+    I: Iterator<Item = &'a T>,
+{
+    rooted!(in(cx) let set = NewSetObject(cx));
+    assert!(!set.is_null());
+    rooted!(in(cx) let mut value = UndefinedValue());
+    for v in values {
+        v.to_jsval(cx, value.handle_mut());
+        assert!(SetAdd(cx, set.handle().into(), value.handle().into()));
+    }
+    rval.set(ObjectValue(set.get()));
+}
+
+/// Converts an argument of a `forEach` callback, throwing a `TypeError` on
+/// failure.
+unsafe fn for_each_arg<T: FromJSValConvertible>(
+    cx: *mut JSContext,
+    args: &CallArgs,
+    index: u32,
+    option: T::Config,
+) -> Result<T, ()> {
+    match T::from_jsval(cx, Handle::from_raw(args.get(index)), option)? {
+        ConversionResult::Success(value) => Ok(value),
+        ConversionResult::Failure(e) => {
+            throw_type_error(cx, &e);
+            Err(())
+        }
+    }
+}
+
+/// Calls `for_each` with a native callback that converts the key and value
+/// arguments, and returns the converted entries. Sets pass each value as
+/// both the key and the value.
+unsafe fn collect_for_each<K, V, F>(
+    cx: *mut JSContext,
+    key_option: K::Config,
+    value_option: V::Config,
+    for_each: F,
+) -> Result<Vec<(K, V)>, ()>
+where
+    K: FromJSValConvertible + 'static,
+    V: FromJSValConvertible + 'static,
+    K::Config: Clone + 'static,
+    V::Config: Clone + 'static,
+    F: FnOnce(HandleValue) -> bool,
+{
+    let entries = Rc::new(RefCell::new(vec![]));
+    let sink = entries.clone();
+    let callback: Box<NativeClosure> = Box::new(move |cx: *mut JSContext, args: &CallArgs| {
+        let value = for_each_arg::<V>(cx, args, 0, value_option.clone())?;
+        let key = for_each_arg::<K>(cx, args, 1, key_option.clone())?;
+        sink.borrow_mut().push((key, value));
+        args.rval().set(UndefinedValue());
+        Ok(())
+    });
+    rooted!(in(cx) let callback = new_function_from_closure(cx, "", 3, callback));
+    if callback.is_null() {
+        return Err(());
+    }
+    rooted!(in(cx) let callback = ObjectValue(callback.get()));
+    if !for_each(callback.handle()) {
+        return Err(());
+    }
+    let entries = mem::replace(&mut *entries.borrow_mut(), vec![]);
+    Ok(entries)
+}
+
+/// Converts the entries of a JS `Map` with `MapForEach`.
+unsafe fn map_from_jsval<K, V>(
+    cx: *mut JSContext,
+    value: HandleValue,
+    option: (K::Config, V::Config),
+) -> Result<ConversionResult<Vec<(K, V)>>, ()>
+where
+    K: FromJSValConvertible + 'static,
+    V: FromJSValConvertible + 'static,
+    K::Config: Clone + 'static,
+    V::Config: Clone + 'static,
+{
+    if !value.is_object() {
+        return Ok(ConversionResult::Failure("Value is not an object".into()));
+    }
+    rooted!(in(cx) let map = value.to_object());
+    let mut is_map = false;
+    if !IsMapObject(cx, map.handle().into(), &mut is_map) {
+        return Err(());
+    }
+    if !is_map {
+        return Ok(ConversionResult::Failure("Value is not a Map".into()));
+    }
+    collect_for_each(cx, option.0, option.1, |callback| {
+        MapForEach(
+            cx,
+            map.handle().into(),
+            callback.into(),
+            HandleValue::undefined().into(),
+        )
+    })
+    .map(ConversionResult::Success)
+}
+
+/// Converts the values of a JS `Set` with `SetForEach`.
+unsafe fn set_from_jsval<T>(
+    cx: *mut JSContext,
+    value: HandleValue,
+    option: T::Config,
+) -> Result<ConversionResult<Vec<T>>, ()>
+where
+    T: FromJSValConvertible + 'static,
+    T::Config: Clone + 'static,
+{
+    if !value.is_object() {
+        return Ok(ConversionResult::Failure("Value is not an object".into()));
+    }
+    rooted!(in(cx) let set = value.to_object());
+    let mut is_set = false;
+    if !IsSetObject(cx, set.handle().into(), &mut is_set) {
+        return Err(());
+    }
+    if !is_set {
+        return Ok(ConversionResult::Failure("Value is not a Set".into()));
+    }
+    let entries = collect_for_each::<(), T, _>(cx, (), option, |callback| {
+        SetForEach(
+            cx,
+            set.handle().into(),
+            callback.into(),
+            HandleValue::undefined().into(),
+        )
+    })?;
+    Ok(ConversionResult::Success(
+        entries.into_iter().map(|((), value)| value).collect(),
+    ))
+}
+
+impl<K, V, S> ToJSValConvertible for AsMap<HashMap<K, V, S>>
+where
+    K: ToJSValConvertible + Eq + Hash,
+    V: ToJSValConvertible,
+    S: BuildHasher,
+{
+    #[inline]
+    unsafe fn to_jsval(&self, cx: *mut JSContext, rval: MutableHandleValue) {
+        map_to_jsval(cx, self.0.iter(), rval)
+    }
+}
+
+impl<K, V, S> FromJSValConvertible for AsMap<HashMap<K, V, S>>
+where
+    K: FromJSValConvertible + Eq + Hash + 'static,
+    V: FromJSValConvertible + 'static,
+    K::Config: Clone + 'static,
+    V::Config: Clone + 'static,
+    S: BuildHasher + Default,
+{
+    type Config = (K::Config, V::Config);
+
+    unsafe fn from_jsval(
+        cx: *mut JSContext,
+        value: HandleValue,
+        option: (K::Config, V::Config),
+    ) -> Result<ConversionResult<AsMap<HashMap<K, V, S>>>, ()> {
+        Ok(match map_from_jsval(cx, value, option)? {
+            ConversionResult::Success(entries) => {
+                ConversionResult::Success(AsMap(entries.into_iter().collect()))
+            }
+            ConversionResult::Failure(e) => ConversionResult::Failure(e),
+        })
+    }
+}
+
+impl<K: ToJSValConvertible + Ord, V: ToJSValConvertible> ToJSValConvertible
+    for AsMap<BTreeMap<K, V>>
+{
+    #[inline]
+    unsafe fn to_jsval(&self, cx: *mut JSContext, rval: MutableHandleValue) {
+        map_to_jsval(cx, self.0.iter(), rval)
+    }
+}
+
+impl<K, V> FromJSValConvertible for AsMap<BTreeMap<K, V>>
+where
+    K: FromJSValConvertible + Ord + 'static,
+    V: FromJSValConvertible + 'static,
+    K::Config: Clone + 'static,
+    V::Config: Clone + 'static,
+{
+    type Config = (K::Config, V::Config);
+
+    unsafe fn from_jsval(
+        cx: *mut JSContext,
+        value: HandleValue,
+        option: (K::Config, V::Config),
+    ) -> Result<ConversionResult<AsMap<BTreeMap<K, V>>>, ()> {
+        Ok(match map_from_jsval(cx, value, option)? {
+            ConversionResult::Success(entries) => {
+                ConversionResult::Success(AsMap(entries.into_iter().collect()))
+            }
+            ConversionResult::Failure(e) => ConversionResult::Failure(e),
+        })
+    }
+}
+
+impl<T, S> ToJSValConvertible for AsSet<HashSet<T, S>>
+where
+    T: ToJSValConvertible + Eq + Hash,
+    S: BuildHasher,
+{
+    #[inline]
+    unsafe fn to_jsval(&self, cx: *mut JSContext, rval: MutableHandleValue) {
+        set_to_jsval(cx, self.0.iter(), rval)
+    }
+}
+
+impl<T, S> FromJSValConvertible for AsSet<HashSet<T, S>>
+where
+    T: FromJSValConvertible + Eq + Hash + 'static,
+    T::Config: Clone + 'static,
+    S: BuildHasher + Default,
+{
+    type Config = T::Config;
+
+    unsafe fn from_jsval(
+        cx: *mut JSContext,
+        value: HandleValue,
+        option: T::Config,
+    ) -> Result<ConversionResult<AsSet<HashSet<T, S>>>, ()> {
+        Ok(match set_from_jsval(cx, value, option)? {
+            ConversionResult::Success(values) => {
+                ConversionResult::Success(AsSet(values.into_iter().collect()))
+            }
+            ConversionResult::Failure(e) => ConversionResult::Failure(e),
+        })
+    }
+}
+
+impl<T: ToJSValConvertible + Ord> ToJSValConvertible for AsSet<BTreeSet<T>> {
+    #[inline]
+    unsafe fn to_jsval(&self, cx: *mut JSContext, rval: MutableHandleValue) {
+        set_to_jsval(cx, self.0.iter(), rval)
+    }
+}
+
+impl<T> FromJSValConvertible for AsSet<BTreeSet<T>>
+where
+    T: FromJSValConvertible + Ord + 'static,
+    T::Config: Clone + 'static,
+{
+    type Config = T::Config;
+
+    unsafe fn from_jsval(
+        cx: *mut JSContext,
+        value: HandleValue,
+        option: T::Config,
+    ) -> Result<ConversionResult<AsSet<BTreeSet<T>>>, ()> {
+        Ok(match set_from_jsval(cx, value, option)? {
+            ConversionResult::Success(values) => {
+                ConversionResult::Success(AsSet(values.into_iter().collect()))
+            }
+            ConversionResult::Failure(e) => ConversionResult::Failure(e),
+        })
+    }
+}
+
 // https://heycam.github.io/webidl/#es-object
 impl ToJSValConvertible for *mut JSObject {
     #[inline]
@@ -752,3 +1900,27 @@ impl FromJSValConvertible for *mut JSObject {
         Ok(ConversionResult::Success(value.to_object()))
     }
 }
+
+impl ToJSValConvertible for *mut Symbol {
+    #[inline]
+    unsafe fn to_jsval(&self, _cx: *mut JSContext, mut rval: MutableHandleValue) {
+        rval.set(SymbolValue(&**self));
+    }
+}
+
+impl FromJSValConvertible for *mut Symbol {
+    type Config = ();
+    #[inline]
+    unsafe fn from_jsval(
+        cx: *mut JSContext,
+        value: HandleValue,
+        _option: (),
+    ) -> Result<ConversionResult<*mut Symbol>, ()> {
+        if !value.is_symbol() {
+            throw_type_error(cx, "value is not a symbol");
+            return Err(());
+        }
+
+        Ok(ConversionResult::Success(value.to_symbol()))
+    }
+}
@@ -0,0 +1,304 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Rooted JS `Date` objects, and conversions of Rust times to and from them.
+//!
+//! ```ignore
+//! rooted!(in(cx) let mut date = ptr::null_mut::<JSObject>());
+//! let date = JsDate::from_system_time(cx, SystemTime::now(), date.handle_mut())?;
+//! assert!(date.is_valid(cx)?);
+//! ```
+//!
+//! `SystemTime` converts to and from `Date` directly, and so does
+//! `chrono::DateTime<Utc>` with the `chrono` feature. Converting an invalid
+//! date, or one that the Rust type cannot represent, fails with
+//! `ConversionResult::Failure`.
+
+#![deny(missing_docs)]
+
+#[cfg(feature = "chrono")]
+use chrono::{DateTime, TimeZone, Utc};
+use conversions::{duration_to_millis, millis_to_duration};
+use conversions::{ConversionResult, FromJSValConvertible, ToJSValConvertible};
+use error::throw_type_error;
+use jsapi::{ClippedTime, DateGetMsecSinceEpoch, DateIsValid, NewDateObject, ObjectIsDate};
+use jsapi::{JSContext, JSObject};
+use jsval::ObjectValue;
+use rust::{Handle, HandleObject, HandleValue, MutableHandleObject, MutableHandleValue};
+
+use std::f64;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The largest number of milliseconds from the epoch that a `Date` can hold,
+/// in either direction.
+const MAX_TIME: f64 = 8.64e15;
+
+/// Clips a number of milliseconds since the epoch to the range of `Date`,
+/// as the TimeClip operation does.
+// https://tc39.es/ecma262/#sec-timeclip
+fn time_clip(millis: f64) -> ClippedTime {
+    let t = if !millis.is_finite() || millis.abs() > MAX_TIME {
+        f64::NAN
+    } else {
+        // Adding zero turns -0 into +0.
+        millis.trunc() + 0.0
+    };
+    ClippedTime { t }
+}
+
+fn system_time_to_millis(time: SystemTime) -> f64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(after) => duration_to_millis(after),
+        Err(before) => -duration_to_millis(before.duration()),
+    }
+}
+
+/// Returns `None` for `NaN`, and for times `SystemTime` cannot represent.
+fn millis_to_system_time(millis: f64) -> Option<SystemTime> {
+    if millis.is_nan() {
+        None
+    } else if millis >= 0.0 {
+        millis_to_duration(millis).and_then(|after| UNIX_EPOCH.checked_add(after))
+    } else {
+        millis_to_duration(-millis).and_then(|before| UNIX_EPOCH.checked_sub(before))
+    }
+}
+
+/// Returns `None` for `NaN`, and for times `DateTime` cannot represent.
+#[cfg(feature = "chrono")]
+fn millis_to_date_time(millis: f64) -> Option<DateTime<Utc>> {
+    if millis.is_nan() {
+        None
+    } else {
+        Utc.timestamp_millis_opt(millis as i64).single()
+    }
+}
+
+/// A rooted `Date`, or a wrapper for one.
+#[derive(Clone, Copy)]
+pub struct JsDate<'a> {
+    handle: HandleObject<'a>,
+}
+
+impl<'a> JsDate<'a> {
+    /// Creates a new `Date` for a number of milliseconds since the epoch,
+    /// stored in `root`. The time is truncated to whole milliseconds, and
+    /// gives an invalid date if it is out of range or not finite.
+    ///
+    /// # Failures
+    ///
+    /// Returns `Err` on JSAPI failure, with a pending exception.
+    pub unsafe fn new(
+        cx: *mut JSContext,
+        millis: f64,
+        mut root: MutableHandleObject<'a>,
+    ) -> Result<JsDate<'a>, ()> {
+        root.set(NewDateObject(cx, time_clip(millis)));
+        if root.is_null() {
+            return Err(());
+        }
+        Ok(JsDate {
+            handle: Handle::from_raw(root.into()),
+        })
+    }
+
+    /// Creates a new `Date` for a system time, stored in `root`. The date is
+    /// invalid if the time is out of range.
+    ///
+    /// # Failures
+    ///
+    /// Returns `Err` on JSAPI failure, with a pending exception.
+    pub unsafe fn from_system_time(
+        cx: *mut JSContext,
+        time: SystemTime,
+        root: MutableHandleObject<'a>,
+    ) -> Result<JsDate<'a>, ()> {
+        JsDate::new(cx, system_time_to_millis(time), root)
+    }
+
+    /// Wraps a rooted object that is a `Date`, or a wrapper for one.
+    ///
+    /// # Failures
+    ///
+    /// Throws a `TypeError` and returns `Err` if the object is not a `Date`,
+    /// and returns `Err` on JSAPI failure, with a pending exception.
+    pub unsafe fn from_handle(
+        cx: *mut JSContext,
+        handle: HandleObject<'a>,
+    ) -> Result<JsDate<'a>, ()> {
+        assert!(!handle.is_null());
+        let mut is_date = false;
+        if !ObjectIsDate(cx, handle.into(), &mut is_date) {
+            return Err(());
+        }
+        if !is_date {
+            throw_type_error(cx, "object is not a Date");
+            return Err(());
+        }
+        Ok(JsDate { handle })
+    }
+
+    /// Returns whether `value` is a `Date`, or a wrapper for one.
+    ///
+    /// # Failures
+    ///
+    /// Returns `Err` on JSAPI failure, with a pending exception.
+    pub unsafe fn is_date(cx: *mut JSContext, value: HandleValue) -> Result<bool, ()> {
+        if !value.is_object() {
+            return Ok(false);
+        }
+        rooted!(in(cx) let obj = value.to_object());
+        let mut is_date = false;
+        if ObjectIsDate(cx, obj.handle().into(), &mut is_date) {
+            Ok(is_date)
+        } else {
+            Err(())
+        }
+    }
+
+    /// Returns the root holding the date.
+    pub fn handle(&self) -> HandleObject<'a> {
+        self.handle
+    }
+
+    /// Returns the date.
+    pub fn get_object(&self) -> *mut JSObject {
+        *self.handle
+    }
+
+    /// Returns whether the date is valid, that is, whether its time is not
+    /// `NaN`.
+    ///
+    /// # Failures
+    ///
+    /// Returns `Err` on JSAPI failure, with a pending exception.
+    pub unsafe fn is_valid(&self, cx: *mut JSContext) -> Result<bool, ()> {
+        let mut is_valid = false;
+        if DateIsValid(cx, self.handle.into(), &mut is_valid) {
+            Ok(is_valid)
+        } else {
+            Err(())
+        }
+    }
+
+    /// Returns the number of milliseconds since the epoch, as
+    /// `Date.prototype.getTime` does. This is `NaN` for an invalid date.
+    ///
+    /// # Failures
+    ///
+    /// Returns `Err` on JSAPI failure, with a pending exception.
+    pub unsafe fn millis_since_epoch(&self, cx: *mut JSContext) -> Result<f64, ()> {
+        let mut millis = 0.0;
+        if DateGetMsecSinceEpoch(cx, self.handle.into(), &mut millis) {
+            Ok(millis)
+        } else {
+            Err(())
+        }
+    }
+
+    /// Returns the time of the date, or `None` if the date is invalid or
+    /// `SystemTime` cannot represent it.
+    ///
+    /// # Failures
+    ///
+    /// Returns `Err` on JSAPI failure, with a pending exception.
+    pub unsafe fn to_system_time(&self, cx: *mut JSContext) -> Result<Option<SystemTime>, ()> {
+        Ok(millis_to_system_time(self.millis_since_epoch(cx)?))
+    }
+
+    /// Returns the time of the date, or `None` if the date is invalid or
+    /// `DateTime` cannot represent it.
+    ///
+    /// # Failures
+    ///
+    /// Returns `Err` on JSAPI failure, with a pending exception.
+    #[cfg(feature = "chrono")]
+    pub unsafe fn to_date_time(&self, cx: *mut JSContext) -> Result<Option<DateTime<Utc>>, ()> {
+        Ok(millis_to_date_time(self.millis_since_epoch(cx)?))
+    }
+}
+
+impl<'a> ToJSValConvertible for JsDate<'a> {
+    #[inline]
+    unsafe fn to_jsval(&self, cx: *mut JSContext, rval: MutableHandleValue) {
+        self.get_object().to_jsval(cx, rval)
+    }
+}
+
+/// Converts the time of a `Date` with `convert`, failing if the value is not
+/// a `Date` or if `convert` returns `None`.
+unsafe fn from_date<T, F>(
+    cx: *mut JSContext,
+    value: HandleValue,
+    convert: F,
+) -> Result<ConversionResult<T>, ()>
+where
+    F: FnOnce(f64) -> Option<T>,
+{
+    if !value.is_object() {
+        return Ok(ConversionResult::Failure("Value is not a Date".into()));
+    }
+    rooted!(in(cx) let obj = value.to_object());
+    let mut is_date = false;
+    if !ObjectIsDate(cx, obj.handle().into(), &mut is_date) {
+        return Err(());
+    }
+    if !is_date {
+        return Ok(ConversionResult::Failure("Value is not a Date".into()));
+    }
+    let mut millis = 0.0;
+    if !DateGetMsecSinceEpoch(cx, obj.handle().into(), &mut millis) {
+        return Err(());
+    }
+    Ok(match convert(millis) {
+        Some(time) => ConversionResult::Success(time),
+        None => ConversionResult::Failure("Date is invalid or out of range".into()),
+    })
+}
+
+/// Converts to a `Date`. Times outside of the range of `Date` convert to an
+/// invalid date.
+impl ToJSValConvertible for SystemTime {
+    unsafe fn to_jsval(&self, cx: *mut JSContext, mut rval: MutableHandleValue) {
+        let date = NewDateObject(cx, time_clip(system_time_to_millis(*self)));
+        assert!(!date.is_null());
+        rval.set(ObjectValue(date));
+    }
+}
+
+/// Converts from a valid `Date`, or a wrapper for one.
+impl FromJSValConvertible for SystemTime {
+    type Config = ();
+    unsafe fn from_jsval(
+        cx: *mut JSContext,
+        value: HandleValue,
+        _: (),
+    ) -> Result<ConversionResult<SystemTime>, ()> {
+        from_date(cx, value, millis_to_system_time)
+    }
+}
+
+/// Converts to a `Date`, truncated to whole milliseconds. Times outside of
+/// the range of `Date` convert to an invalid date.
+#[cfg(feature = "chrono")]
+impl<Tz: TimeZone> ToJSValConvertible for DateTime<Tz> {
+    unsafe fn to_jsval(&self, cx: *mut JSContext, mut rval: MutableHandleValue) {
+        let date = NewDateObject(cx, time_clip(self.timestamp_millis() as f64));
+        assert!(!date.is_null());
+        rval.set(ObjectValue(date));
+    }
+}
+
+/// Converts from a valid `Date`, or a wrapper for one.
+#[cfg(feature = "chrono")]
+impl FromJSValConvertible for DateTime<Utc> {
+    type Config = ();
+    unsafe fn from_jsval(
+        cx: *mut JSContext,
+        value: HandleValue,
+        _: (),
+    ) -> Result<ConversionResult<DateTime<Utc>>, ()> {
+        from_date(cx, value, millis_to_date_time)
+    }
+}
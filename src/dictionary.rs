@@ -0,0 +1,119 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Conversions of WebIDL dictionaries, which are plain JS objects with a
+//! fixed set of named members.
+//!
+//! These are the building blocks of the `ToJSVal` and `FromJSVal` derives,
+//! and can be used directly for hand-written conversions:
+//!
+//! ```ignore
+//! if !is_dictionary(value) {
+//!     return Ok(ConversionResult::Failure("Value is not an object".into()));
+//! }
+//! let port = match get_member::<u16>(cx, value, "port", ConversionBehavior::EnforceRange)? {
+//!     ConversionResult::Success(port) => port.unwrap_or(80),
+//!     ConversionResult::Failure(e) => return Ok(ConversionResult::Failure(e)),
+//! };
+//! ```
+
+#![deny(missing_docs)]
+
+use conversions::{ConversionResult, FromJSValConvertible, ToJSValConvertible};
+use jsapi::{JSContext, JS_AssignObject, JS_NewPlainObject, JSPROP_ENUMERATE};
+use jsval::{ObjectValue, UndefinedValue};
+use object::Object;
+use rust::{HandleValue, MutableHandleValue};
+
+/// Returns whether `value` can be converted to a dictionary. As in WebIDL,
+/// `undefined` and `null` convert to a dictionary with no members present.
+pub fn is_dictionary(value: HandleValue) -> bool {
+    value.is_object() || value.is_null_or_undefined()
+}
+
+/// Stores a new, empty dictionary object in `rval`.
+///
+/// JSAPI failure causes a panic, as in `ToJSValConvertible::to_jsval`.
+pub unsafe fn new_dictionary(cx: *mut JSContext, mut rval: MutableHandleValue) {
+    let obj = JS_NewPlainObject(cx);
+    assert!(!obj.is_null());
+    rval.set(ObjectValue(obj));
+}
+
+/// Defines the member `name` of `dictionary` as an enumerable data property
+/// holding `value`.
+///
+/// Panics if `dictionary` is not an object; JSAPI failure causes a panic, as
+/// in `ToJSValConvertible::to_jsval`.
+pub unsafe fn set_member<T: ToJSValConvertible + ?Sized>(
+    cx: *mut JSContext,
+    dictionary: HandleValue,
+    name: &str,
+    value: &T,
+) {
+    assert!(dictionary.is_object());
+    rooted!(in(cx) let obj = dictionary.to_object());
+    assert!(Object::new(obj.handle())
+        .define(cx, name, value, JSPROP_ENUMERATE as u32)
+        .is_ok());
+}
+
+/// Converts `value` and copies the own enumerable properties of the result
+/// to `dictionary`, as `Object.assign` does. Values that do not convert to
+/// objects contribute no members.
+///
+/// Panics if `dictionary` is not an object; JSAPI failure causes a panic, as
+/// in `ToJSValConvertible::to_jsval`.
+pub unsafe fn assign_members<T: ToJSValConvertible + ?Sized>(
+    cx: *mut JSContext,
+    dictionary: HandleValue,
+    value: &T,
+) {
+    assert!(dictionary.is_object());
+    rooted!(in(cx) let mut source = UndefinedValue());
+    value.to_jsval(cx, source.handle_mut());
+    if !source.is_object() {
+        return;
+    }
+    rooted!(in(cx) let obj = dictionary.to_object());
+    rooted!(in(cx) let source = source.to_object());
+    assert!(JS_AssignObject(
+        cx,
+        obj.handle().into(),
+        source.handle().into()
+    ));
+}
+
+/// Reads the member `name` of `dictionary` and converts it to `T`. Returns
+/// `None` if the member is `undefined`, or if `dictionary` is `undefined` or
+/// `null`.
+///
+/// Conversion failures are prefixed with the name of the member.
+///
+/// # Failures
+///
+/// Returns `Err` if a getter throws or on JSAPI failure, with a pending
+/// exception.
+pub unsafe fn get_member<T: FromJSValConvertible>(
+    cx: *mut JSContext,
+    dictionary: HandleValue,
+    name: &str,
+    option: T::Config,
+) -> Result<ConversionResult<Option<T>>, ()> {
+    if !dictionary.is_object() {
+        return Ok(ConversionResult::Success(None));
+    }
+    rooted!(in(cx) let obj = dictionary.to_object());
+    rooted!(in(cx) let mut value = UndefinedValue());
+    Object::new(obj.handle()).get_value(cx, name, value.handle_mut())?;
+    if value.is_undefined() {
+        return Ok(ConversionResult::Success(None));
+    }
+    Ok(match T::from_jsval(cx, value.handle(), option)? {
+        ConversionResult::Success(value) => ConversionResult::Success(Some(value)),
+        ConversionResult::Failure(e) => {
+            ConversionResult::Failure(format!("member '{}': {}", name, e).into())
+        }
+    })
+}
@@ -2,14 +2,44 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
-//! Functions to throw JavaScript exceptions from Rust.
+//! Functions to throw JavaScript exceptions from Rust, and an error type for
+//! APIs that can fail either with a JS exception or a failed conversion.
 
 #![deny(missing_docs)]
 
 use jsapi::{JSContext, JSErrorFormatString, JSExnType, JS_ReportErrorNumberUTF8};
 use libc;
+use std::borrow::Cow;
+use std::error::Error;
 use std::ffi::CString;
-use std::{mem, os, ptr};
+use std::{fmt, mem, os, ptr};
+
+/// An error from an operation that runs JS code and converts its results.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum JSError {
+    /// A JS exception was thrown, and is pending on the context.
+    Exception,
+    /// A value could not be converted to the requested Rust type. No
+    /// exception is pending.
+    Conversion(Cow<'static, str>),
+}
+
+impl fmt::Display for JSError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            JSError::Exception => f.write_str("a JS exception is pending"),
+            JSError::Conversion(ref message) => write!(f, "conversion failed: {}", message),
+        }
+    }
+}
+
+impl Error for JSError {}
+
+impl From<()> for JSError {
+    fn from(_: ()) -> JSError {
+        JSError::Exception
+    }
+}
 
 /// Format string used to throw javascript errors.
 static ERROR_FORMAT_STRING_STRING: [libc::c_char; 4] = [
@@ -0,0 +1,183 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Calling JS functions with converted arguments and results.
+//!
+//! Arguments are passed as a tuple (or slice) of `ToJSValConvertible`
+//! values, which are converted and rooted for the duration of the call:
+//!
+//! ```ignore
+//! let sum = call::<i32, _>(cx, this, func, (1, 2, 3), ConversionBehavior::Default)?;
+//! call_method::<(), _, _>(cx, listener, "handleEvent", (event,), ())?;
+//! ```
+
+#![deny(missing_docs)]
+
+use conversions::{ConversionResult, FromJSValConvertible, ToJSValConvertible};
+use jsapi::{Call, Construct, HandleValueArray, JSContext, JSObject, Value};
+use jsval::{ObjectValue, UndefinedValue};
+use object::Object;
+use property::PropertyKey;
+use rust::{HandleObject, HandleValue, MutableHandleValue};
+
+use std::ptr;
+
+/// A list of arguments for a JS call.
+pub trait ToJSArgs {
+    /// Returns the number of arguments.
+    fn count(&self) -> usize;
+
+    /// Converts the arguments and appends them to `args`, which is rooted.
+    unsafe fn append_to(&self, cx: *mut JSContext, args: &mut Vec<Value>);
+}
+
+impl ToJSArgs for () {
+    fn count(&self) -> usize {
+        0
+    }
+
+    unsafe fn append_to(&self, _cx: *mut JSContext, _args: &mut Vec<Value>) {}
+}
+
+impl<'a, T: ToJSValConvertible> ToJSArgs for &'a [T] {
+    fn count(&self) -> usize {
+        self.len()
+    }
+
+    unsafe fn append_to(&self, cx: *mut JSContext, args: &mut Vec<Value>) {
+        for arg in self.iter() {
+            append_arg(cx, args, arg);
+        }
+    }
+}
+
+macro_rules! impl_to_js_args {
+    ($len:expr; $($name:ident: $index:tt),+) => {
+        impl<$($name: ToJSValConvertible),+> ToJSArgs for ($($name,)+) {
+            fn count(&self) -> usize {
+                $len
+            }
+
+            unsafe fn append_to(&self, cx: *mut JSContext, args: &mut Vec<Value>) {
+                $(append_arg(cx, args, &self.$index);)+
+            }
+        }
+    };
+}
+
+impl_to_js_args!(1; A: 0);
+impl_to_js_args!(2; A: 0, B: 1);
+impl_to_js_args!(3; A: 0, B: 1, C: 2);
+impl_to_js_args!(4; A: 0, B: 1, C: 2, D: 3);
+impl_to_js_args!(5; A: 0, B: 1, C: 2, D: 3, E: 4);
+impl_to_js_args!(6; A: 0, B: 1, C: 2, D: 3, E: 4, F: 5);
+impl_to_js_args!(7; A: 0, B: 1, C: 2, D: 3, E: 4, F: 5, G: 6);
+impl_to_js_args!(8; A: 0, B: 1, C: 2, D: 3, E: 4, F: 5, G: 6, H: 7);
+impl_to_js_args!(9; A: 0, B: 1, C: 2, D: 3, E: 4, F: 5, G: 6, H: 7, I: 8);
+impl_to_js_args!(10; A: 0, B: 1, C: 2, D: 3, E: 4, F: 5, G: 6, H: 7, I: 8, J: 9);
+impl_to_js_args!(11; A: 0, B: 1, C: 2, D: 3, E: 4, F: 5, G: 6, H: 7, I: 8, J: 9, K: 10);
+impl_to_js_args!(12; A: 0, B: 1, C: 2, D: 3, E: 4, F: 5, G: 6, H: 7, I: 8, J: 9, K: 10, L: 11);
+
+unsafe fn append_arg<T: ToJSValConvertible + ?Sized>(
+    cx: *mut JSContext,
+    args: &mut Vec<Value>,
+    arg: &T,
+) {
+    rooted!(in(cx) let mut value = UndefinedValue());
+    arg.to_jsval(cx, value.handle_mut());
+    args.push(value.get());
+}
+
+/// Calls `func` with the given `this` and arguments, storing the result in
+/// `rval`.
+///
+/// # Failures
+///
+/// Returns `Err` if the function throws or on JSAPI failure, with a pending
+/// exception.
+pub unsafe fn call_value<A: ToJSArgs>(
+    cx: *mut JSContext,
+    this: HandleValue,
+    func: HandleValue,
+    args: A,
+    rval: MutableHandleValue,
+) -> Result<(), ()> {
+    auto_root!(in(cx) let mut argv = Vec::<Value>::with_capacity(args.count()));
+    args.append_to(cx, &mut *argv);
+    if Call(
+        cx,
+        this.into(),
+        func.into(),
+        &HandleValueArray::from_rooted_slice(&*argv),
+        rval.into(),
+    ) {
+        Ok(())
+    } else {
+        Err(())
+    }
+}
+
+/// Calls `func` with the given `this` and arguments, and converts the
+/// result to `R`.
+///
+/// # Failures
+///
+/// Returns `Err` if the function throws or on JSAPI failure, with a pending
+/// exception.
+pub unsafe fn call<R: FromJSValConvertible, A: ToJSArgs>(
+    cx: *mut JSContext,
+    this: HandleValue,
+    func: HandleValue,
+    args: A,
+    option: R::Config,
+) -> Result<ConversionResult<R>, ()> {
+    rooted!(in(cx) let mut rval = UndefinedValue());
+    call_value(cx, this, func, args, rval.handle_mut())?;
+    R::from_jsval(cx, rval.handle(), option)
+}
+
+/// Calls the method `name` of `obj`, as `obj[name](...args)` does, and
+/// converts the result to `R`.
+///
+/// # Failures
+///
+/// Returns `Err` if the method is missing or throws, or on JSAPI failure,
+/// with a pending exception.
+pub unsafe fn call_method<R: FromJSValConvertible, K: Into<PropertyKey>, A: ToJSArgs>(
+    cx: *mut JSContext,
+    obj: HandleObject,
+    name: K,
+    args: A,
+    option: R::Config,
+) -> Result<ConversionResult<R>, ()> {
+    rooted!(in(cx) let mut func = UndefinedValue());
+    Object::new(obj).get_value(cx, name, func.handle_mut())?;
+    rooted!(in(cx) let this = ObjectValue(obj.get()));
+    call(cx, this.handle(), func.handle(), args, option)
+}
+
+/// Calls `ctor` as a constructor, as `new ctor(...args)` does.
+///
+/// Returns a null pointer if the constructor throws or on JSAPI failure,
+/// with a pending exception.
+pub unsafe fn construct<A: ToJSArgs>(
+    cx: *mut JSContext,
+    ctor: HandleObject,
+    args: A,
+) -> *mut JSObject {
+    auto_root!(in(cx) let mut argv = Vec::<Value>::with_capacity(args.count()));
+    args.append_to(cx, &mut *argv);
+    rooted!(in(cx) let callee = ObjectValue(ctor.get()));
+    rooted!(in(cx) let mut result = ptr::null_mut::<JSObject>());
+    if !Construct(
+        cx,
+        callee.handle().into(),
+        ctor.into(),
+        &HandleValueArray::from_rooted_slice(&*argv),
+        result.handle_mut().into(),
+    ) {
+        return ptr::null_mut();
+    }
+    result.get()
+}
@@ -366,6 +366,7 @@ extern "C" {
         aExtra: *const ::libc::c_void,
     ) -> *const ::libc::c_void;
     pub fn CreateWrapperProxyHandler(aTraps: *const ProxyTraps) -> *const ::libc::c_void;
+    pub fn CreateSecurityWrapperProxyHandler(aTraps: *const ProxyTraps) -> *const ::libc::c_void;
     pub fn GetCrossCompartmentWrapper() -> *const ::libc::c_void;
     pub fn GetSecurityWrapper() -> *const ::libc::c_void;
     pub fn NewCompileOptions(
@@ -406,6 +407,8 @@ extern "C" {
     pub fn int_to_jsid(i: i32, id: MutableHandleId);
     pub fn RUST_JSID_IS_STRING(id: HandleId) -> bool;
     pub fn RUST_JSID_TO_STRING(id: HandleId) -> *mut JSString;
+    pub fn RUST_JSID_IS_SYMBOL(id: HandleId) -> bool;
+    pub fn RUST_JSID_TO_SYMBOL(id: HandleId) -> *mut Symbol;
     pub fn RUST_SYMBOL_TO_JSID(sym: *mut Symbol, id: MutableHandleId);
     pub fn RUST_JSID_IS_VOID(id: HandleId) -> bool;
     pub fn SetBuildId(buildId: *mut JS::BuildIdCharVector, chars: *const u8, len: usize) -> bool;
@@ -421,6 +424,7 @@ extern "C" {
     ) -> *const JSErrorFormatString;
     pub fn IsProxyHandlerFamily(obj: *mut JSObject) -> u8;
     pub fn GetProxyHandlerExtra(obj: *mut JSObject) -> *const ::libc::c_void;
+    pub fn HasProxyHandler(obj: *mut JSObject, aHandler: *const ::libc::c_void) -> bool;
     pub fn GetProxyHandler(obj: *mut JSObject) -> *const ::libc::c_void;
     pub fn ReportError(aCx: *mut JSContext, aError: *const i8);
     pub fn IsWrapper(obj: *mut JSObject) -> bool;
@@ -446,6 +450,18 @@ extern "C" {
     ) -> bool;
     pub fn GetObjectVectorAddress(v: *mut PersistentRootedObjectVector) -> *mut ::libc::c_void;
     pub fn DeleteRootedObjectVector(v: *mut PersistentRootedObjectVector);
+    pub fn CreatePersistentRootedObject(
+        aCx: *mut JSContext,
+        obj: *mut JSObject,
+    ) -> *mut PersistentRootedObject;
+    pub fn GetPersistentRootedObject(root: *const PersistentRootedObject) -> *mut JSObject;
+    pub fn DeletePersistentRootedObject(root: *mut PersistentRootedObject);
+    pub fn CreatePersistentRootedValue(
+        aCx: *mut JSContext,
+        value: *const Value,
+    ) -> *mut PersistentRootedValue;
+    pub fn GetPersistentRootedValue(root: *const PersistentRootedValue, dest: *mut Value);
+    pub fn DeletePersistentRootedValue(root: *mut PersistentRootedValue);
     pub fn CollectServoSizes(
         cx: *mut JSContext,
         sizes: *mut ServoSizes,
@@ -0,0 +1,491 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Lazy iteration over JS iterables, and JS iterators backed by Rust ones.
+//!
+//! Unlike the `Vec<T>` conversion, `ForOfIter` pulls one element at a time,
+//! so it works with infinite generators and stops running script as soon as
+//! the caller stops asking:
+//!
+//! ```ignore
+//! let first_three = ForOfIter::<u32>::new(cx, iterable, ConversionBehavior::Default)?
+//!     .take(3)
+//!     .collect::<Result<Vec<_>, _>>()?;
+//! ```
+//!
+//! Dropping the iterator before it is exhausted calls the JS iterator's
+//! `return()` method, as a `break` out of a `for-of` loop does.
+//!
+//! In the other direction, `new_iterator` hands a Rust iterator to JS as an
+//! iterator object that works with `for-of`, spread and `Array.from`. Its
+//! elements are converted one at a time, as JS asks for them:
+//!
+//! ```ignore
+//! rooted!(in(cx) let rows = new_iterator(cx, cursor.map(|row| row.name)));
+//! ```
+
+#![deny(missing_docs)]
+
+use conversions::ToJSValConvertible;
+use conversions::{ConversionResult, ForOfIteratorGuard, FromJSValConvertible};
+use error::{throw_type_error, JSError};
+use function::call_value;
+use glue::{CreatePersistentRootedObject, DeletePersistentRootedObject, GetPersistentRootedObject};
+use jsapi::JS_SetPendingException;
+use jsapi::{jsid, ExceptionStackBehavior, GetArrayLength, JS_GetElement, JS_GetPropertyById};
+use jsapi::{CallArgs, JSContext, JSObject, PersistentRootedObject, RootedObject, RootedValue};
+use jsapi::{ForOfIterator, ForOfIterator_NonIterableBehavior};
+use jsapi::{JS_ClearPendingException, JS_GetPendingException, JS_IsExceptionPending};
+use jsapi::{JS_DefinePropertyById2, JS_NewPlainObject, Value, JSPROP_ENUMERATE};
+use jsval::{BooleanValue, ObjectValue, UndefinedValue};
+use owned::{get_object_data, new_object_with_data};
+use panic::wrap_panic;
+use property::interned_id;
+use rust::{FunctionSpecs, HandleObject, HandleValue, MutableHandleValue, ToBoolean};
+use symbol::SymbolCode;
+
+use std::cell::RefCell;
+use std::marker::PhantomData;
+use std::{mem, ptr};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum State {
+    /// The iterator may yield more elements.
+    Running,
+    /// An element failed to convert; the JS iterator still has to be closed.
+    Failed,
+    /// The JS iterator is exhausted, threw, or was closed.
+    Done,
+}
+
+/// An iterator over the elements of a JS iterable, converted to `T`.
+///
+/// The JS iterator is kept alive through persistent roots, so a `ForOfIter`
+/// can be moved and stored freely. It must be dropped while `cx` is alive.
+///
+/// Yields `Err(JSError::Exception)` if the iteration throws, and
+/// `Err(JSError::Conversion)` if an element does not convert; either ends the
+/// iteration.
+pub struct ForOfIter<T: FromJSValConvertible> {
+    cx: *mut JSContext,
+    iterator: *mut PersistentRootedObject,
+    next_method: *mut PersistentRootedObject,
+    /// The next index when iterating a plain array directly, which
+    /// `ForOfIterator` does when the array's iteration is not customized.
+    index: Option<u32>,
+    state: State,
+    option: T::Config,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T: FromJSValConvertible> ForOfIter<T> {
+    /// Starts iterating `iterable`, as `for (x of iterable)` does.
+    ///
+    /// # Failures
+    ///
+    /// Throws a `TypeError` and returns `Err` if the value is not iterable,
+    /// and returns `Err` if getting the iterator throws.
+    pub unsafe fn new(
+        cx: *mut JSContext,
+        iterable: HandleValue,
+        option: T::Config,
+    ) -> Result<ForOfIter<T>, JSError> {
+        // See the `Vec<T>` conversion for why the iterator is built this way.
+        let zero = mem::zeroed();
+        let mut iterator = ForOfIterator {
+            cx_: cx,
+            iterator: RootedObject::new_unrooted(),
+            nextMethod: RootedValue::new_unrooted(),
+            index: ::std::u32::MAX, // NOT_ARRAY
+            ..zero
+        };
+        let iterator = ForOfIteratorGuard::new(cx, &mut iterator);
+        let iterator = &mut *iterator.root;
+
+        if !iterator.init(
+            iterable.into(),
+            ForOfIterator_NonIterableBehavior::ThrowOnNonIterable,
+        ) {
+            return Err(JSError::Exception);
+        }
+
+        let (index, next_method) = if iterator.index != ::std::u32::MAX {
+            (Some(iterator.index), ptr::null_mut())
+        } else if iterator.nextMethod.ptr.is_object() {
+            (None, iterator.nextMethod.ptr.to_object())
+        } else {
+            throw_type_error(cx, "iterator's next method is not callable");
+            return Err(JSError::Exception);
+        };
+
+        Ok(ForOfIter {
+            cx,
+            iterator: CreatePersistentRootedObject(cx, iterator.iterator.ptr),
+            next_method: CreatePersistentRootedObject(cx, next_method),
+            index,
+            state: State::Running,
+            option,
+            marker: PhantomData,
+        })
+    }
+
+    /// Closes the JS iterator early by calling its `return()` method, if it
+    /// has one. Dropping the iterator does the same, but ignores errors.
+    ///
+    /// # Failures
+    ///
+    /// Returns `Err` with a pending exception if `return()` throws, or if an
+    /// exception was already pending.
+    pub unsafe fn close(mut self) -> Result<(), JSError> {
+        self.close_iterator().map_err(JSError::from)
+    }
+
+    /// Stores the next value of the JS iterator in `rval`. Returns `false`
+    /// once the iterator is exhausted.
+    unsafe fn next_value(&mut self, rval: MutableHandleValue) -> Result<bool, ()> {
+        let cx = self.cx;
+        rooted!(in(cx) let iterator = GetPersistentRootedObject(self.iterator));
+
+        if let Some(index) = self.index {
+            let mut length = 0;
+            if !GetArrayLength(cx, iterator.handle().into(), &mut length) {
+                return Err(());
+            }
+            if index >= length {
+                return Ok(false);
+            }
+            self.index = Some(index + 1);
+            return if JS_GetElement(cx, iterator.handle().into(), index, rval.into()) {
+                Ok(true)
+            } else {
+                Err(())
+            };
+        }
+
+        rooted!(in(cx) let this = ObjectValue(iterator.get()));
+        rooted!(in(cx) let next = ObjectValue(GetPersistentRootedObject(self.next_method)));
+        rooted!(in(cx) let mut result = UndefinedValue());
+        call_value(cx, this.handle(), next.handle(), (), result.handle_mut())?;
+        iter_result_value(cx, result.handle(), rval)
+    }
+
+    /// Calls the JS iterator's `return()` method unless it is exhausted or
+    /// a plain array. An exception that is already pending wins over any
+    /// exception `return()` throws, as in `IteratorClose`.
+    unsafe fn close_iterator(&mut self) -> Result<(), ()> {
+        let state = mem::replace(&mut self.state, State::Done);
+        if state == State::Done || self.index.is_some() {
+            return Ok(());
+        }
+
+        let cx = self.cx;
+        rooted!(in(cx) let mut exception = UndefinedValue());
+        let pending = JS_IsExceptionPending(cx);
+        if pending {
+            if !JS_GetPendingException(cx, exception.handle_mut().into()) {
+                return Err(());
+            }
+            JS_ClearPendingException(cx);
+        }
+
+        rooted!(in(cx) let iterator = GetPersistentRootedObject(self.iterator));
+        let result = call_return(cx, iterator.handle());
+
+        if pending {
+            JS_ClearPendingException(cx);
+            JS_SetPendingException(
+                cx,
+                exception.handle().into(),
+                ExceptionStackBehavior::DoNotCapture,
+            );
+            return Err(());
+        }
+        result
+    }
+}
+
+/// Reads an iterator result object: stores its `value` in `rval` and returns
+/// `true`, or returns `false` if it is `done`.
+pub(crate) unsafe fn iter_result_value(
+    cx: *mut JSContext,
+    result: HandleValue,
+    rval: MutableHandleValue,
+) -> Result<bool, ()> {
+    if !result.is_object() {
+        throw_type_error(cx, "iterator result is not an object");
+        return Err(());
+    }
+    rooted!(in(cx) let result = result.to_object());
+
+    rooted!(in(cx) let mut id: jsid);
+    rooted!(in(cx) let mut done = UndefinedValue());
+    interned_id(cx, "done", id.handle_mut())?;
+    if !JS_GetPropertyById(
+        cx,
+        result.handle().into(),
+        id.handle().into(),
+        done.handle_mut().into(),
+    ) {
+        return Err(());
+    }
+    if ToBoolean(done.handle()) {
+        return Ok(false);
+    }
+
+    interned_id(cx, "value", id.handle_mut())?;
+    if JS_GetPropertyById(cx, result.handle().into(), id.handle().into(), rval.into()) {
+        Ok(true)
+    } else {
+        Err(())
+    }
+}
+
+/// Calls the `return()` method of `iterator`, if it has one, and checks that
+/// it returns an object.
+pub(crate) unsafe fn call_return(cx: *mut JSContext, iterator: HandleObject) -> Result<(), ()> {
+    rooted!(in(cx) let mut id: jsid);
+    rooted!(in(cx) let mut method = UndefinedValue());
+    interned_id(cx, "return", id.handle_mut())?;
+    if !JS_GetPropertyById(
+        cx,
+        iterator.into(),
+        id.handle().into(),
+        method.handle_mut().into(),
+    ) {
+        return Err(());
+    }
+    if method.is_null_or_undefined() {
+        return Ok(());
+    }
+
+    rooted!(in(cx) let this = ObjectValue(iterator.get()));
+    rooted!(in(cx) let mut result = UndefinedValue());
+    call_value(cx, this.handle(), method.handle(), (), result.handle_mut())?;
+    if !result.is_object() {
+        throw_type_error(cx, "iterator result is not an object");
+        return Err(());
+    }
+    Ok(())
+}
+
+impl<T: FromJSValConvertible> Iterator for ForOfIter<T>
+where
+    T::Config: Clone,
+{
+    type Item = Result<T, JSError>;
+
+    fn next(&mut self) -> Option<Result<T, JSError>> {
+        if self.state != State::Running {
+            return None;
+        }
+        let cx = self.cx;
+        unsafe {
+            rooted!(in(cx) let mut value = UndefinedValue());
+            match self.next_value(value.handle_mut()) {
+                Ok(true) => {}
+                Ok(false) => {
+                    self.state = State::Done;
+                    return None;
+                }
+                Err(()) => {
+                    self.state = State::Done;
+                    return Some(Err(JSError::Exception));
+                }
+            }
+            match T::from_jsval(cx, value.handle(), self.option.clone()) {
+                Ok(ConversionResult::Success(value)) => Some(Ok(value)),
+                Ok(ConversionResult::Failure(message)) => {
+                    self.state = State::Failed;
+                    Some(Err(JSError::Conversion(message)))
+                }
+                Err(()) => {
+                    self.state = State::Failed;
+                    Some(Err(JSError::Exception))
+                }
+            }
+        }
+    }
+}
+
+impl<T: FromJSValConvertible> Drop for ForOfIter<T> {
+    fn drop(&mut self) {
+        unsafe {
+            let pending = JS_IsExceptionPending(self.cx);
+            if self.close_iterator().is_err() && !pending {
+                // There is nobody to report the exception to.
+                JS_ClearPendingException(self.cx);
+            }
+            DeletePersistentRootedObject(self.iterator);
+            DeletePersistentRootedObject(self.next_method);
+        }
+    }
+}
+
+/// Produces the next element of a Rust iterator into the given handle, or
+/// returns `false` once the iterator is exhausted.
+type NextClosure = dyn FnMut(*mut JSContext, MutableHandleValue) -> bool;
+
+/// The data owned by an iterator object created by `new_iterator`. The Rust
+/// iterator is dropped as soon as it is exhausted or closed.
+struct RustIterator {
+    next: RefCell<Option<Box<NextClosure>>>,
+}
+
+/// Creates a JS iterator object that yields the elements of `iter`,
+/// converted when JS asks for them.
+///
+/// The object has `next()`, `return()` and `[Symbol.iterator]()` methods, so
+/// it can be used with `for-of`, spread and `Array.from`. The Rust iterator
+/// is dropped when it is exhausted, when JS stops iterating early, or when
+/// the object is garbage collected, whichever happens first. Like any data
+/// owned by a JS object, it must not hold GC things.
+///
+/// Returns a null pointer on JSAPI failure.
+///
+/// # Safety
+///
+/// - `cx` must be valid and in a realm.
+pub unsafe fn new_iterator<I>(cx: *mut JSContext, iter: I) -> *mut JSObject
+where
+    I: IntoIterator,
+    I::IntoIter: 'static,
+    I::Item: ToJSValConvertible,
+{
+    let mut iter = iter.into_iter();
+    let next: Box<NextClosure> = Box::new(move |cx, rval| match iter.next() {
+        Some(item) => {
+            unsafe { item.to_jsval(cx, rval) };
+            true
+        }
+        None => false,
+    });
+    let data = Box::new(RustIterator {
+        next: RefCell::new(Some(next)),
+    });
+    rooted!(in(cx) let obj = new_object_with_data(cx, data));
+    if obj.is_null() {
+        return ptr::null_mut();
+    }
+    let specs = FunctionSpecs::new()
+        .method("next", Some(iterator_next), 0, 0)
+        .method("return", Some(iterator_return), 0, 0)
+        .symbol_method(SymbolCode::Iterator, Some(iterator_self), 0, 0);
+    if specs.define(cx, obj.handle()).is_err() {
+        return ptr::null_mut();
+    }
+    obj.get()
+}
+
+unsafe extern "C" fn iterator_next(cx: *mut JSContext, argc: u32, vp: *mut Value) -> bool {
+    let args = CallArgs::from_vp(vp, argc);
+    let mut result = false;
+    wrap_panic(&mut || result = next_impl(cx, &args).is_ok());
+    result
+}
+
+unsafe extern "C" fn iterator_return(cx: *mut JSContext, argc: u32, vp: *mut Value) -> bool {
+    let args = CallArgs::from_vp(vp, argc);
+    let mut result = false;
+    wrap_panic(&mut || result = return_impl(cx, &args).is_ok());
+    result
+}
+
+unsafe extern "C" fn iterator_self(_cx: *mut JSContext, argc: u32, vp: *mut Value) -> bool {
+    let args = CallArgs::from_vp(vp, argc);
+    args.rval().set(args.thisv().get());
+    true
+}
+
+/// Returns the Rust iterator state of the `this` object, throwing a
+/// `TypeError` if it is not an iterator created by `new_iterator`.
+unsafe fn this_iterator<'a>(cx: *mut JSContext, args: &CallArgs) -> Result<&'a RustIterator, ()> {
+    let thisv = args.thisv();
+    let iterator = if thisv.is_object() {
+        get_object_data::<RustIterator>(thisv.to_object())
+    } else {
+        None
+    };
+    iterator.ok_or_else(|| throw_type_error(cx, "not a Rust iterator"))
+}
+
+unsafe fn next_impl(cx: *mut JSContext, args: &CallArgs) -> Result<(), ()> {
+    let iterator = this_iterator(cx, args)?;
+    rooted!(in(cx) let mut value = UndefinedValue());
+    let done = {
+        let mut next = match iterator.next.try_borrow_mut() {
+            Ok(next) => next,
+            Err(_) => {
+                throw_type_error(cx, "iterator is already running");
+                return Err(());
+            }
+        };
+        let produced = match *next {
+            Some(ref mut next) => next(cx, value.handle_mut()),
+            None => false,
+        };
+        if !produced {
+            *next = None;
+        }
+        !produced
+    };
+    set_iter_result(cx, value.handle(), done, args)
+}
+
+unsafe fn return_impl(cx: *mut JSContext, args: &CallArgs) -> Result<(), ()> {
+    let iterator = this_iterator(cx, args)?;
+    match iterator.next.try_borrow_mut() {
+        Ok(mut next) => *next = None,
+        Err(_) => {
+            throw_type_error(cx, "iterator is already running");
+            return Err(());
+        }
+    }
+    rooted!(in(cx) let value = UndefinedValue());
+    set_iter_result(cx, value.handle(), true, args)
+}
+
+/// Stores a `{ value, done }` iterator result object in the return value.
+unsafe fn set_iter_result(
+    cx: *mut JSContext,
+    value: HandleValue,
+    done: bool,
+    args: &CallArgs,
+) -> Result<(), ()> {
+    rooted!(in(cx) let result = new_iter_result(cx, value, done));
+    if result.is_null() {
+        return Err(());
+    }
+    args.rval().set(ObjectValue(result.get()));
+    Ok(())
+}
+
+/// Creates a `{ value, done }` iterator result object.
+///
+/// Returns a null pointer on JSAPI failure.
+pub(crate) unsafe fn new_iter_result(
+    cx: *mut JSContext,
+    value: HandleValue,
+    done: bool,
+) -> *mut JSObject {
+    rooted!(in(cx) let result = JS_NewPlainObject(cx));
+    if result.is_null() {
+        return ptr::null_mut();
+    }
+    rooted!(in(cx) let done = BooleanValue(done));
+    rooted!(in(cx) let mut id: jsid);
+    for &(name, value) in &[("value", value), ("done", done.handle())] {
+        if interned_id(cx, name, id.handle_mut()).is_err()
+            || !JS_DefinePropertyById2(
+                cx,
+                result.handle().into(),
+                id.handle().into(),
+                value.into(),
+                JSPROP_ENUMERATE as u32,
+            )
+        {
+            return ptr::null_mut();
+        }
+    }
+    result.get()
+}
@@ -384,8 +384,8 @@ class WrapperProxyHandler : public js::Wrapper
 {
     ProxyTraps mTraps;
   public:
-    WrapperProxyHandler(const ProxyTraps& aTraps)
-    : js::Wrapper(0), mTraps(aTraps) {}
+    WrapperProxyHandler(const ProxyTraps& aTraps, bool aHasSecurityPolicy = false)
+    : js::Wrapper(0, false, aHasSecurityPolicy), mTraps(aTraps) {}
 
     virtual bool finalizeInBackground(const JS::Value& priv) const override
     {
@@ -675,6 +675,12 @@ CreateWrapperProxyHandler(const ProxyTraps* aTraps)
     return new WrapperProxyHandler(*aTraps);
 }
 
+const void*
+CreateSecurityWrapperProxyHandler(const ProxyTraps* aTraps)
+{
+    return new WrapperProxyHandler(*aTraps, /* aHasSecurityPolicy = */ true);
+}
+
 const void*
 GetCrossCompartmentWrapper()
 {
@@ -818,6 +824,18 @@ RUST_SYMBOL_TO_JSID(JS::Symbol* sym, JS::MutableHandleId id)
     id.set(SYMBOL_TO_JSID(sym));
 }
 
+bool
+RUST_JSID_IS_SYMBOL(JS::HandleId id)
+{
+    return JSID_IS_SYMBOL(id);
+}
+
+JS::Symbol*
+RUST_JSID_TO_SYMBOL(JS::HandleId id)
+{
+    return JSID_TO_SYMBOL(id);
+}
+
 bool
 RUST_JSID_IS_VOID(JS::HandleId id)
 {
@@ -867,6 +885,12 @@ GetProxyHandlerExtra(JSObject* obj)
     return static_cast<const ForwardingProxyHandler*>(handler)->getExtra();
 }
 
+bool
+HasProxyHandler(JSObject* obj, const void* aHandler)
+{
+    return js::IsProxy(obj) && js::GetProxyHandler(obj) == aHandler;
+}
+
 const void*
 GetProxyHandler(JSObject* obj)
 {
@@ -974,6 +998,42 @@ DeleteRootedObjectVector(JS::PersistentRootedObjectVector* v)
     delete v;
 }
 
+JS::PersistentRootedObject*
+CreatePersistentRootedObject(JSContext* aCx, JSObject* obj)
+{
+    return new JS::PersistentRootedObject(aCx, obj);
+}
+
+JSObject*
+GetPersistentRootedObject(const JS::PersistentRootedObject* root)
+{
+    return root->get();
+}
+
+void
+DeletePersistentRootedObject(JS::PersistentRootedObject* root)
+{
+    delete root;
+}
+
+JS::PersistentRootedValue*
+CreatePersistentRootedValue(JSContext* aCx, const JS::Value* value)
+{
+    return new JS::PersistentRootedValue(aCx, *value);
+}
+
+void
+GetPersistentRootedValue(const JS::PersistentRootedValue* root, JS::Value* dest)
+{
+    *dest = root->get();
+}
+
+void
+DeletePersistentRootedValue(JS::PersistentRootedValue* root)
+{
+    delete root;
+}
+
 #if defined(__linux__)
  #include <malloc.h>
 #elif defined(__APPLE__)
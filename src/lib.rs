@@ -30,10 +30,18 @@
 
 #[macro_use]
 extern crate lazy_static;
+#[cfg(feature = "chrono")]
+extern crate chrono;
+#[cfg(feature = "futures")]
+extern crate futures;
 extern crate libc;
 #[macro_use]
 extern crate log;
+#[cfg(feature = "derive")]
+extern crate mozjs_derive;
 extern crate mozjs_sys;
+#[cfg(feature = "num-bigint")]
+extern crate num_bigint;
 extern crate num_traits;
 
 pub mod jsapi {
@@ -51,12 +59,29 @@ pub mod jsapi {
 #[macro_use]
 pub mod rust;
 
+pub mod array;
+pub mod callback;
+pub mod closure;
 mod consts;
 pub mod conversions;
+pub mod date;
+pub mod dictionary;
 pub mod error;
+pub mod function;
 pub mod glue;
+pub mod iterator;
+pub mod membrane;
+pub mod object;
+pub mod owned;
 pub mod panic;
+pub mod property;
+pub mod proxy;
+pub mod regexp;
+#[cfg(feature = "futures")]
+pub mod stream;
+pub mod symbol;
 pub mod typedarray;
+pub mod value;
 
 pub use consts::*;
 pub use mozjs_sys::jsval;
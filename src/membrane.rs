@@ -0,0 +1,781 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Membranes: mediated views of a graph of objects.
+//!
+//! Objects handed out through a [`Membrane`] are wrapped in security
+//! wrappers. Every object reachable through a wrapper, whether as a property
+//! value, an accessor, a prototype or the result of a call, is wrapped by the
+//! same membrane when it crosses. A [`MembranePolicy`] decides which
+//! properties can be read, written or deleted, and which functions can be
+//! called. Revoking the membrane cuts off every wrapper it ever created.
+//!
+//! Values flowing back in, such as assigned values or call arguments, are not
+//! wrapped; wrappers of the same membrane are replaced by their target.
+//!
+//! Each object has at most one wrapper per membrane, so identity is
+//! preserved across the membrane: `plugin.nested === plugin.nested`. The
+//! membrane remembers wrappers in a `WeakMap` keyed by their target, which
+//! does not keep targets alive.
+//!
+//! Wrappers live in the compartment of their target, so the membrane
+//! mediates between realms of a single compartment.
+//!
+//! [`Membrane`]: struct.Membrane.html
+//! [`MembranePolicy`]: trait.MembranePolicy.html
+
+#![deny(missing_docs)]
+
+use error::throw_type_error;
+use glue::{AppendToIdVector, CreateSecurityWrapperProxyHandler, GetProxyPrivate};
+use glue::{CreatePersistentRootedObject, DeletePersistentRootedObject, GetPersistentRootedObject};
+use glue::{GetProxyReservedSlot, HasProxyHandler, ProxyTraps, SetProxyReservedSlot, WrapperNew};
+use jsapi::Handle as RawHandle;
+use jsapi::MutableHandleValue as RawMutableHandleValue;
+use jsapi::PropertyDescriptor as RawPropertyDescriptor;
+use jsapi::{Call, Construct, GetPropertyKeys, JS_GetPropertyById, JS_GetPrototype};
+use jsapi::{CallArgs, HandleValueArray, IsAcceptableThis, NativeImpl};
+use jsapi::{GetWeakMapEntry, NewWeakMapObject, PersistentRootedObject, SetWeakMapEntry};
+use jsapi::{HandleId as RawHandleId, HandleObject as RawHandleObject};
+use jsapi::{HandleValue as RawHandleValue, MutableHandleIdVector as RawMutableHandleIdVector};
+use jsapi::{JSContext, JSFreeOp, JSObject, ObjectOpResult, Value};
+use jsapi::{JS_DefinePropertyById, JS_DeletePropertyById, JS_GetOwnPropertyDescriptorById};
+use jsapi::{JS_ForwardSetPropertyTo, JS_HasOwnPropertyById, JS_HasPropertyById};
+use jsapi::{MutableHandle as RawMutableHandle, MutableHandleObject as RawMutableHandleObject};
+use jsapi::{JSITER_HIDDEN, JSITER_OWNONLY, JSITER_SYMBOLS};
+use jsval::{ObjectValue, PrivateValue, UndefinedValue};
+use panic::wrap_panic;
+use property::{PropertyDefinition, PropertyDescriptor, PropertyKey};
+use rust::{Handle, HandleObject, IdVector, MutableHandle, MutableHandleValue};
+
+use std::cell::Cell;
+use std::os::raw::c_void;
+use std::ptr;
+use std::rc::Rc;
+
+/// The kind of property access checked by a [`MembranePolicy`].
+///
+/// [`MembranePolicy`]: trait.MembranePolicy.html
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Access {
+    /// Reading, testing for or enumerating the property.
+    Read,
+    /// Assigning to or defining the property.
+    Write,
+    /// Deleting the property.
+    Delete,
+}
+
+/// Decides what can be done to objects through a [`Membrane`].
+///
+/// A property whose reads are denied looks absent through the wrapper.
+/// Denied writes, deletions and calls throw a `TypeError`.
+///
+/// [`Membrane`]: struct.Membrane.html
+pub trait MembranePolicy: 'static {
+    /// Whether the property `key` of `target` can be accessed as `access`.
+    fn allow_property(
+        &self,
+        cx: *mut JSContext,
+        target: HandleObject,
+        key: &PropertyKey,
+        access: Access,
+    ) -> bool;
+
+    /// Whether the function `target` can be called or constructed. Denies
+    /// all calls by default.
+    fn allow_call(&self, _cx: *mut JSContext, _target: HandleObject) -> bool {
+        false
+    }
+}
+
+struct MembraneState {
+    policy: Box<dyn MembranePolicy>,
+    revoked: Cell<bool>,
+}
+
+/// A membrane, shared by all the wrappers it creates.
+///
+/// Dropping the `Membrane` does not revoke it; wrappers keep the policy
+/// alive for as long as they exist.
+pub struct Membrane {
+    state: Rc<MembraneState>,
+    /// The `WeakMap` from targets to wrappers, created on first use.
+    wrappers: Cell<*mut PersistentRootedObject>,
+}
+
+/// Proxy reserved slot that stores a `Box<Rc<MembraneState>>`.
+const MEMBRANE_SLOT: u32 = 0;
+
+/// Proxy reserved slot that stores the `WeakMap` from targets to wrappers.
+const WRAPPERS_SLOT: u32 = 1;
+
+lazy_static! {
+    /// The C++ handler shared by all membrane wrappers.
+    static ref HANDLER: usize = {
+        let traps = ProxyTraps {
+            getOwnPropertyDescriptor: Some(get_own_property_descriptor),
+            defineProperty: Some(define_property),
+            ownPropertyKeys: Some(own_property_keys),
+            delete_: Some(delete),
+            enumerate: Some(enumerate),
+            getPrototypeIfOrdinary: Some(get_prototype_if_ordinary),
+            getPrototype: Some(get_prototype),
+            setPrototype: Some(set_prototype),
+            preventExtensions: Some(prevent_extensions),
+            has: Some(has),
+            get: Some(get),
+            set: Some(set),
+            call: Some(call),
+            construct: Some(construct),
+            hasOwn: Some(has_own),
+            getOwnEnumerablePropertyKeys: Some(get_own_enumerable_property_keys),
+            nativeCall: Some(native_call),
+            finalize: Some(finalize),
+            ..ProxyTraps::default()
+        };
+        unsafe { CreateSecurityWrapperProxyHandler(&traps) as usize }
+    };
+}
+
+fn handler() -> *const c_void {
+    *HANDLER as *const c_void
+}
+
+impl Membrane {
+    /// Creates a membrane that mediates access with `policy`.
+    pub fn new<P: MembranePolicy>(policy: P) -> Membrane {
+        Membrane {
+            state: Rc::new(MembraneState {
+                policy: Box::new(policy),
+                revoked: Cell::new(false),
+            }),
+            wrappers: Cell::new(ptr::null_mut()),
+        }
+    }
+
+    /// Returns the `WeakMap` from targets to wrappers, creating it in the
+    /// current compartment on first use.
+    unsafe fn wrappers(&self, cx: *mut JSContext) -> *mut JSObject {
+        if self.wrappers.get().is_null() {
+            let wrappers = NewWeakMapObject(cx);
+            if wrappers.is_null() {
+                return ptr::null_mut();
+            }
+            self.wrappers.set(CreatePersistentRootedObject(cx, wrappers));
+        }
+        GetPersistentRootedObject(self.wrappers.get())
+    }
+
+    /// Returns the wrapper for `obj`, which must be in the current
+    /// compartment, creating it the first time `obj` is wrapped. Wrappers of
+    /// this membrane are returned unchanged.
+    ///
+    /// Returns a null pointer on JSAPI failure.
+    pub unsafe fn wrap_object(&self, cx: *mut JSContext, obj: HandleObject) -> *mut JSObject {
+        rooted!(in(cx) let wrappers = self.wrappers(cx));
+        if wrappers.is_null() {
+            return ptr::null_mut();
+        }
+        wrap_object(cx, &self.state, wrappers.handle(), obj)
+    }
+
+    /// Replaces an object in `value` by its wrapper. Primitives are left
+    /// alone.
+    ///
+    /// # Failures
+    ///
+    /// Returns `Err` on JSAPI failure, with a pending exception.
+    pub unsafe fn wrap_value(
+        &self,
+        cx: *mut JSContext,
+        value: MutableHandleValue,
+    ) -> Result<(), ()> {
+        rooted!(in(cx) let wrappers = self.wrappers(cx));
+        if wrappers.is_null() {
+            return Err(());
+        }
+        wrap_value(cx, &self.state, wrappers.handle(), value)
+    }
+
+    /// Cuts off every wrapper created by this membrane. Any further use of
+    /// them throws a `TypeError`.
+    pub fn revoke(&self) {
+        self.state.revoked.set(true);
+    }
+
+    /// Whether `revoke` has been called.
+    pub fn is_revoked(&self) -> bool {
+        self.state.revoked.get()
+    }
+}
+
+impl Drop for Membrane {
+    fn drop(&mut self) {
+        if !self.wrappers.get().is_null() {
+            unsafe { DeletePersistentRootedObject(self.wrappers.get()) }
+        }
+    }
+}
+
+/// Returns the wrapper of `obj` recorded in `wrappers`, or creates one.
+unsafe fn wrap_object(
+    cx: *mut JSContext,
+    state: &Rc<MembraneState>,
+    wrappers: HandleObject,
+    obj: HandleObject,
+) -> *mut JSObject {
+    if belongs_to(obj.get(), state) {
+        return obj.get();
+    }
+    rooted!(in(cx) let mut existing = UndefinedValue());
+    if !GetWeakMapEntry(
+        cx,
+        wrappers.into(),
+        obj.into(),
+        existing.handle_mut().into(),
+    ) {
+        return ptr::null_mut();
+    }
+    if existing.is_object() {
+        return existing.to_object();
+    }
+
+    rooted!(in(cx) let wrapper = WrapperNew(cx, obj.into(), handler(), ptr::null(), false));
+    if wrapper.is_null() {
+        return ptr::null_mut();
+    }
+    let state = Box::into_raw(Box::new(state.clone()));
+    SetProxyReservedSlot(
+        wrapper.get(),
+        MEMBRANE_SLOT,
+        &PrivateValue(state as *const c_void),
+    );
+    SetProxyReservedSlot(wrapper.get(), WRAPPERS_SLOT, &ObjectValue(wrappers.get()));
+    rooted!(in(cx) let value = ObjectValue(wrapper.get()));
+    if !SetWeakMapEntry(cx, wrappers.into(), obj.into(), value.handle().into()) {
+        return ptr::null_mut();
+    }
+    wrapper.get()
+}
+
+unsafe fn wrap_value(
+    cx: *mut JSContext,
+    state: &Rc<MembraneState>,
+    wrappers: HandleObject,
+    mut value: MutableHandleValue,
+) -> Result<(), ()> {
+    if !value.is_object() {
+        return Ok(());
+    }
+    rooted!(in(cx) let obj = value.to_object());
+    let wrapper = wrap_object(cx, state, wrappers, obj.handle());
+    if wrapper.is_null() {
+        return Err(());
+    }
+    value.set(ObjectValue(wrapper));
+    Ok(())
+}
+
+/// Replaces a wrapper of the membrane in `value` by its target.
+unsafe fn unwrap_value(state: &Rc<MembraneState>, mut value: MutableHandleValue) {
+    if value.is_object() && belongs_to(value.to_object(), state) {
+        value.set(ObjectValue(target(value.to_object())));
+    }
+}
+
+unsafe fn wrap_descriptor(
+    cx: *mut JSContext,
+    state: &Rc<MembraneState>,
+    wrappers: HandleObject,
+    desc: &mut PropertyDescriptor,
+) -> Result<(), ()> {
+    match *desc {
+        PropertyDescriptor::Data { ref mut value, .. } => {
+            rooted!(in(cx) let mut rooted = *value);
+            wrap_value(cx, state, wrappers, rooted.handle_mut())?;
+            *value = rooted.get();
+        }
+        PropertyDescriptor::Accessor {
+            ref mut get,
+            ref mut set,
+            ..
+        } => {
+            for accessor in &mut [get, set] {
+                if accessor.is_null() {
+                    continue;
+                }
+                rooted!(in(cx) let obj = **accessor);
+                **accessor = wrap_object(cx, state, wrappers, obj.handle());
+                if accessor.is_null() {
+                    return Err(());
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+unsafe fn unwrap_descriptor(state: &Rc<MembraneState>, desc: &mut PropertyDescriptor) {
+    let unwrap = |obj: &mut *mut JSObject| {
+        if !obj.is_null() && belongs_to(*obj, state) {
+            *obj = target(*obj);
+        }
+    };
+    match *desc {
+        PropertyDescriptor::Data { ref mut value, .. } => {
+            if value.is_object() && belongs_to(value.to_object(), state) {
+                *value = ObjectValue(target(value.to_object()));
+            }
+        }
+        PropertyDescriptor::Accessor {
+            ref mut get,
+            ref mut set,
+            ..
+        } => {
+            unwrap(get);
+            unwrap(set);
+        }
+    }
+}
+
+unsafe fn belongs_to(obj: *mut JSObject, state: &Rc<MembraneState>) -> bool {
+    HasProxyHandler(obj, handler()) && Rc::ptr_eq(&*membrane(obj), state)
+}
+
+unsafe fn membrane(wrapper: *mut JSObject) -> *const Rc<MembraneState> {
+    let mut slot = UndefinedValue();
+    GetProxyReservedSlot(wrapper, MEMBRANE_SLOT, &mut slot);
+    slot.to_private() as *const Rc<MembraneState>
+}
+
+unsafe fn target(wrapper: *mut JSObject) -> *mut JSObject {
+    let mut private = UndefinedValue();
+    GetProxyPrivate(wrapper, &mut private);
+    private.to_object()
+}
+
+unsafe fn wrappers(wrapper: *mut JSObject) -> *mut JSObject {
+    let mut slot = UndefinedValue();
+    GetProxyReservedSlot(wrapper, WRAPPERS_SLOT, &mut slot);
+    slot.to_object()
+}
+
+/// Runs a trap body with the membrane state and target of `proxy`, throwing
+/// if the membrane was revoked. Errors and panics become a `false` return.
+unsafe fn with_membrane(
+    cx: *mut JSContext,
+    proxy: RawHandleObject,
+    body: &mut dyn FnMut(&Rc<MembraneState>, HandleObject) -> Result<(), ()>,
+) -> bool {
+    let mut result = false;
+    wrap_panic(&mut || {
+        let state = (*membrane(proxy.get())).clone();
+        if state.revoked.get() {
+            throw_type_error(cx, "access through a revoked membrane");
+            return;
+        }
+        rooted!(in(cx) let target = target(proxy.get()));
+        result = body(&state, target.handle()).is_ok();
+    });
+    result
+}
+
+unsafe fn check(
+    cx: *mut JSContext,
+    state: &MembraneState,
+    target: HandleObject,
+    key: &PropertyKey,
+    access: Access,
+) -> Result<(), ()> {
+    if state.policy.allow_property(cx, target, key, access) {
+        Ok(())
+    } else {
+        throw_type_error(cx, "property access denied by the membrane");
+        Err(())
+    }
+}
+
+/// Appends the keys of `target` selected by `flags` and readable under the
+/// policy to `props`.
+unsafe fn filtered_keys(
+    cx: *mut JSContext,
+    state: &MembraneState,
+    target: HandleObject,
+    flags: u32,
+    props: RawMutableHandleIdVector,
+) -> Result<(), ()> {
+    let mut ids = IdVector::new(cx);
+    if !GetPropertyKeys(cx, target.into(), flags, ids.handle_mut()) {
+        return Err(());
+    }
+    for &id in &*ids {
+        rooted!(in(cx) let id = id);
+        let key = PropertyKey::from_id(cx, id.handle())?;
+        if state.policy.allow_property(cx, target, &key, Access::Read)
+            && !AppendToIdVector(props, id.handle().into())
+        {
+            return Err(());
+        }
+    }
+    Ok(())
+}
+
+unsafe extern "C" fn get_own_property_descriptor(
+    cx: *mut JSContext,
+    proxy: RawHandleObject,
+    id: RawHandleId,
+    desc: RawMutableHandle<RawPropertyDescriptor>,
+) -> bool {
+    with_membrane(cx, proxy, &mut |state, target| {
+        let key = PropertyKey::from_id(cx, Handle::from_raw(id))?;
+        if !state.policy.allow_property(cx, target, &key, Access::Read) {
+            return Ok(());
+        }
+        rooted!(in(cx) let mut raw = RawPropertyDescriptor::default());
+        if !JS_GetOwnPropertyDescriptorById(cx, target.into(), id, raw.handle_mut().into()) {
+            return Err(());
+        }
+        let found = PropertyDescriptor::from_raw(cx, raw.handle())?;
+        if let Some(found) = found {
+            auto_root!(in(cx) let mut found = found);
+            rooted!(in(cx) let wrappers = wrappers(proxy.get()));
+            wrap_descriptor(cx, state, wrappers.handle(), &mut *found)?;
+            found.to_raw(cx, Handle::from_raw(proxy), MutableHandle::from_raw(desc))?;
+        }
+        Ok(())
+    })
+}
+
+unsafe extern "C" fn define_property(
+    cx: *mut JSContext,
+    proxy: RawHandleObject,
+    id: RawHandleId,
+    desc: RawHandle<RawPropertyDescriptor>,
+    result: *mut ObjectOpResult,
+) -> bool {
+    with_membrane(cx, proxy, &mut |state, target| {
+        let key = PropertyKey::from_id(cx, Handle::from_raw(id))?;
+        check(cx, state, target, &key, Access::Write)?;
+        let definition = PropertyDefinition::from_raw(cx, target, Handle::from_raw(desc))?;
+        auto_root!(in(cx) let definition = definition);
+        // Complete the definition against the target's property, so that
+        // fields missing from it keep their current values.
+        rooted!(in(cx) let mut raw = RawPropertyDescriptor::default());
+        if !JS_GetOwnPropertyDescriptorById(cx, target.into(), id, raw.handle_mut().into()) {
+            return Err(());
+        }
+        let current = PropertyDescriptor::from_raw(cx, raw.handle())?;
+        auto_root!(in(cx) let mut desc = definition.apply(current.as_ref()));
+        unwrap_descriptor(state, &mut *desc);
+        desc.to_raw(cx, target, raw.handle_mut())?;
+        if JS_DefinePropertyById(cx, target.into(), id, raw.handle().into(), result) {
+            Ok(())
+        } else {
+            Err(())
+        }
+    })
+}
+
+unsafe extern "C" fn own_property_keys(
+    cx: *mut JSContext,
+    proxy: RawHandleObject,
+    props: RawMutableHandleIdVector,
+) -> bool {
+    with_membrane(cx, proxy, &mut |state, target| {
+        let flags = JSITER_OWNONLY | JSITER_HIDDEN | JSITER_SYMBOLS;
+        filtered_keys(cx, state, target, flags, props)
+    })
+}
+
+unsafe extern "C" fn get_own_enumerable_property_keys(
+    cx: *mut JSContext,
+    proxy: RawHandleObject,
+    props: RawMutableHandleIdVector,
+) -> bool {
+    with_membrane(cx, proxy, &mut |state, target| {
+        filtered_keys(cx, state, target, JSITER_OWNONLY, props)
+    })
+}
+
+unsafe extern "C" fn enumerate(
+    cx: *mut JSContext,
+    proxy: RawHandleObject,
+    props: RawMutableHandleIdVector,
+) -> bool {
+    with_membrane(cx, proxy, &mut |state, target| {
+        filtered_keys(cx, state, target, 0, props)
+    })
+}
+
+unsafe extern "C" fn delete(
+    cx: *mut JSContext,
+    proxy: RawHandleObject,
+    id: RawHandleId,
+    result: *mut ObjectOpResult,
+) -> bool {
+    with_membrane(cx, proxy, &mut |state, target| {
+        let key = PropertyKey::from_id(cx, Handle::from_raw(id))?;
+        check(cx, state, target, &key, Access::Delete)?;
+        if JS_DeletePropertyById(cx, target.into(), id, result) {
+            Ok(())
+        } else {
+            Err(())
+        }
+    })
+}
+
+unsafe extern "C" fn get_prototype_if_ordinary(
+    _cx: *mut JSContext,
+    _proxy: RawHandleObject,
+    is_ordinary: *mut bool,
+    _protop: RawMutableHandleObject,
+) -> bool {
+    // The prototype must be wrapped on the way out, which `get_prototype`
+    // does.
+    *is_ordinary = false;
+    true
+}
+
+unsafe extern "C" fn get_prototype(
+    cx: *mut JSContext,
+    proxy: RawHandleObject,
+    protop: RawMutableHandleObject,
+) -> bool {
+    with_membrane(cx, proxy, &mut |state, target| {
+        rooted!(in(cx) let mut proto = ptr::null_mut::<JSObject>());
+        if !JS_GetPrototype(cx, target.into(), proto.handle_mut().into()) {
+            return Err(());
+        }
+        if !proto.is_null() {
+            rooted!(in(cx) let wrappers = wrappers(proxy.get()));
+            let wrapper = wrap_object(cx, state, wrappers.handle(), proto.handle());
+            if wrapper.is_null() {
+                return Err(());
+            }
+            proto.set(wrapper);
+        }
+        MutableHandle::from_raw(protop).set(proto.get());
+        Ok(())
+    })
+}
+
+unsafe extern "C" fn set_prototype(
+    cx: *mut JSContext,
+    proxy: RawHandleObject,
+    _proto: RawHandleObject,
+    result: *mut ObjectOpResult,
+) -> bool {
+    with_membrane(cx, proxy, &mut |_, _| {
+        (*result).failCantSetProto();
+        Ok(())
+    })
+}
+
+unsafe extern "C" fn prevent_extensions(
+    cx: *mut JSContext,
+    proxy: RawHandleObject,
+    result: *mut ObjectOpResult,
+) -> bool {
+    with_membrane(cx, proxy, &mut |_, _| {
+        (*result).failCantPreventExtensions();
+        Ok(())
+    })
+}
+
+unsafe extern "C" fn has(
+    cx: *mut JSContext,
+    proxy: RawHandleObject,
+    id: RawHandleId,
+    bp: *mut bool,
+) -> bool {
+    with_membrane(cx, proxy, &mut |state, target| {
+        let key = PropertyKey::from_id(cx, Handle::from_raw(id))?;
+        *bp = false;
+        if !state.policy.allow_property(cx, target, &key, Access::Read) {
+            return Ok(());
+        }
+        if JS_HasPropertyById(cx, target.into(), id, bp) {
+            Ok(())
+        } else {
+            Err(())
+        }
+    })
+}
+
+unsafe extern "C" fn has_own(
+    cx: *mut JSContext,
+    proxy: RawHandleObject,
+    id: RawHandleId,
+    bp: *mut bool,
+) -> bool {
+    with_membrane(cx, proxy, &mut |state, target| {
+        let key = PropertyKey::from_id(cx, Handle::from_raw(id))?;
+        *bp = false;
+        if !state.policy.allow_property(cx, target, &key, Access::Read) {
+            return Ok(());
+        }
+        if JS_HasOwnPropertyById(cx, target.into(), id, bp) {
+            Ok(())
+        } else {
+            Err(())
+        }
+    })
+}
+
+unsafe extern "C" fn get(
+    cx: *mut JSContext,
+    proxy: RawHandleObject,
+    _receiver: RawHandleValue,
+    id: RawHandleId,
+    vp: RawMutableHandleValue,
+) -> bool {
+    with_membrane(cx, proxy, &mut |state, target| {
+        let key = PropertyKey::from_id(cx, Handle::from_raw(id))?;
+        let mut vp = MutableHandle::from_raw(vp);
+        if !state.policy.allow_property(cx, target, &key, Access::Read) {
+            vp.set(UndefinedValue());
+            return Ok(());
+        }
+        // Getters run with the target as `this`, so that host code never
+        // sees a wrapper.
+        if !JS_GetPropertyById(cx, target.into(), id, vp.into()) {
+            return Err(());
+        }
+        rooted!(in(cx) let wrappers = wrappers(proxy.get()));
+        wrap_value(cx, state, wrappers.handle(), vp)
+    })
+}
+
+unsafe extern "C" fn set(
+    cx: *mut JSContext,
+    proxy: RawHandleObject,
+    id: RawHandleId,
+    v: RawHandleValue,
+    receiver: RawHandleValue,
+    result: *mut ObjectOpResult,
+) -> bool {
+    with_membrane(cx, proxy, &mut |state, target| {
+        let key = PropertyKey::from_id(cx, Handle::from_raw(id))?;
+        check(cx, state, target, &key, Access::Write)?;
+        rooted!(in(cx) let mut value = *v.ptr);
+        unwrap_value(state, value.handle_mut());
+        rooted!(in(cx) let mut receiver = *receiver.ptr);
+        unwrap_value(state, receiver.handle_mut());
+        if JS_ForwardSetPropertyTo(
+            cx,
+            target.into(),
+            id,
+            value.handle().into(),
+            receiver.handle().into(),
+            result,
+        ) {
+            Ok(())
+        } else {
+            Err(())
+        }
+    })
+}
+
+/// Collects the `this` value and arguments of a call into `argv`, replacing
+/// wrappers of the membrane by their targets.
+unsafe fn unwrapped_arguments(state: &Rc<MembraneState>, args: &CallArgs) -> Vec<Value> {
+    let mut argv = vec![];
+    for i in 0..args.argc_ {
+        let mut arg = *args.get(i).ptr;
+        if arg.is_object() && belongs_to(arg.to_object(), state) {
+            arg = ObjectValue(target(arg.to_object()));
+        }
+        argv.push(arg);
+    }
+    argv
+}
+
+unsafe fn check_call(
+    cx: *mut JSContext,
+    state: &MembraneState,
+    target: HandleObject,
+) -> Result<(), ()> {
+    if state.policy.allow_call(cx, target) {
+        Ok(())
+    } else {
+        throw_type_error(cx, "call denied by the membrane");
+        Err(())
+    }
+}
+
+unsafe extern "C" fn call(
+    cx: *mut JSContext,
+    proxy: RawHandleObject,
+    args: *const CallArgs,
+) -> bool {
+    with_membrane(cx, proxy, &mut |state, target| {
+        let args = &*args;
+        check_call(cx, state, target)?;
+        rooted!(in(cx) let mut this = *args.thisv().ptr);
+        unwrap_value(state, this.handle_mut());
+        auto_root!(in(cx) let argv = unwrapped_arguments(state, args));
+        rooted!(in(cx) let callee = ObjectValue(target.get()));
+        let rval = MutableHandle::from_raw(args.rval());
+        if !Call(
+            cx,
+            this.handle().into(),
+            callee.handle().into(),
+            &HandleValueArray::from_rooted_slice(&*argv),
+            rval.into(),
+        ) {
+            return Err(());
+        }
+        rooted!(in(cx) let wrappers = wrappers(proxy.get()));
+        wrap_value(cx, state, wrappers.handle(), rval)
+    })
+}
+
+unsafe extern "C" fn construct(
+    cx: *mut JSContext,
+    proxy: RawHandleObject,
+    args: *const CallArgs,
+) -> bool {
+    with_membrane(cx, proxy, &mut |state, target| {
+        let args = &*args;
+        check_call(cx, state, target)?;
+        auto_root!(in(cx) let argv = unwrapped_arguments(state, args));
+        rooted!(in(cx) let callee = ObjectValue(target.get()));
+        rooted!(in(cx) let mut result = ptr::null_mut::<JSObject>());
+        if !Construct(
+            cx,
+            callee.handle().into(),
+            target.into(),
+            &HandleValueArray::from_rooted_slice(&*argv),
+            result.handle_mut().into(),
+        ) {
+            return Err(());
+        }
+        let mut rval = MutableHandle::from_raw(args.rval());
+        rval.set(ObjectValue(result.get()));
+        rooted!(in(cx) let wrappers = wrappers(proxy.get()));
+        wrap_value(cx, state, wrappers.handle(), rval)
+    })
+}
+
+unsafe extern "C" fn native_call(
+    cx: *mut JSContext,
+    _test: IsAcceptableThis,
+    _impl: NativeImpl,
+    _args: CallArgs,
+) -> bool {
+    // Built-in methods would otherwise run on the unwrapped target and
+    // return its internals unwrapped.
+    throw_type_error(cx, "membrane wrappers cannot be used with built-in methods");
+    false
+}
+
+unsafe extern "C" fn finalize(_fop: *mut JSFreeOp, proxy: *mut JSObject) {
+    let mut slot = UndefinedValue();
+    GetProxyReservedSlot(proxy, MEMBRANE_SLOT, &mut slot);
+    if !slot.is_undefined() {
+        let state = slot.to_private() as *mut Rc<MembraneState>;
+        wrap_panic(&mut || drop(Box::from_raw(state)));
+    }
+}
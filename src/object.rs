@@ -0,0 +1,434 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Typed property access on rooted objects.
+//!
+//! ```ignore
+//! let config = Object::new(obj.handle());
+//! if config.has(cx, "port")? {
+//!     let port = config.get::<u16, _>(cx, "port", ConversionBehavior::EnforceRange)?;
+//! }
+//! config.set(cx, "ready", &true)?;
+//! ```
+//!
+//! Keys are anything that converts into a `PropertyKey`: `&str`, `String`
+//! or `u32`. Symbol keys come from `Symbol::to_property_key`.
+//!
+//! Keys and entries can be walked lazily:
+//!
+//! ```ignore
+//! for entry in config.own_entries::<u32>(cx, ConversionBehavior::Default)? {
+//!     let (key, value) = entry?;
+//!     ...
+//! }
+//! ```
+
+#![deny(missing_docs)]
+
+use conversions::{ConversionResult, FromJSValConvertible, ToJSValConvertible};
+use jsapi::PropertyDescriptor as RawPropertyDescriptor;
+use jsapi::{jsid, GetPropertyKeys, JSContext, JSObject};
+use jsapi::{JS_DefinePropertyById1, JS_DefinePropertyById2, JS_DeletePropertyById};
+use jsapi::{JS_GetOwnPropertyDescriptorById, JS_GetPropertyById};
+use jsapi::{JS_HasOwnPropertyById, JS_HasPropertyById, JS_SetPropertyById};
+use jsapi::{ObjectOpResult, ObjectOpResult_SpecialCodes};
+use jsapi::{JSITER_HIDDEN, JSITER_OWNONLY, JSITER_SYMBOLS};
+use jsval::UndefinedValue;
+use property::{PropertyDescriptor, PropertyKey};
+use rust::{HandleId, HandleObject, IdVector, MutableHandleValue};
+
+use std::marker::PhantomData;
+
+/// Which keys `Object::keys` returns. The default matches `Object.keys`:
+/// own, enumerable, string keys.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct KeyFlags {
+    /// Include enumerable keys from the prototype chain, as `for-in` does.
+    pub inherited: bool,
+    /// Include symbol keys.
+    pub symbols: bool,
+    /// Include non-enumerable keys.
+    pub non_enumerable: bool,
+}
+
+impl KeyFlags {
+    fn to_raw(self) -> u32 {
+        let mut flags = 0;
+        if !self.inherited {
+            flags |= JSITER_OWNONLY;
+        }
+        if self.symbols {
+            flags |= JSITER_SYMBOLS;
+        }
+        if self.non_enumerable {
+            flags |= JSITER_HIDDEN;
+        }
+        flags
+    }
+}
+
+/// A rooted object.
+#[derive(Clone, Copy)]
+pub struct Object<'a> {
+    handle: HandleObject<'a>,
+}
+
+impl<'a> Object<'a> {
+    /// Wraps a rooted, non-null object.
+    pub fn new(handle: HandleObject<'a>) -> Object<'a> {
+        assert!(!handle.is_null());
+        Object { handle }
+    }
+
+    /// Returns the root holding the object.
+    pub fn handle(&self) -> HandleObject<'a> {
+        self.handle
+    }
+
+    /// Returns the object.
+    pub fn get(&self) -> *mut JSObject {
+        *self.handle
+    }
+
+    /// Reads the property `key` and converts it to `T`. Getters and proxy
+    /// traps run as they would for `obj[key]`.
+    ///
+    /// # Failures
+    ///
+    /// Returns `Err` on JSAPI failure, with a pending exception.
+    pub unsafe fn get<T: FromJSValConvertible, K: Into<PropertyKey>>(
+        &self,
+        cx: *mut JSContext,
+        key: K,
+        option: T::Config,
+    ) -> Result<ConversionResult<T>, ()> {
+        rooted!(in(cx) let mut value = UndefinedValue());
+        self.get_value(cx, key, value.handle_mut())?;
+        T::from_jsval(cx, value.handle(), option)
+    }
+
+    /// Reads the property `key` into `rval` without converting it.
+    ///
+    /// # Failures
+    ///
+    /// Returns `Err` on JSAPI failure, with a pending exception.
+    pub unsafe fn get_value<K: Into<PropertyKey>>(
+        &self,
+        cx: *mut JSContext,
+        key: K,
+        rval: MutableHandleValue,
+    ) -> Result<(), ()> {
+        rooted!(in(cx) let mut id: jsid);
+        key.into().to_id(cx, id.handle_mut())?;
+        if JS_GetPropertyById(cx, self.handle.into(), id.handle().into(), rval.into()) {
+            Ok(())
+        } else {
+            Err(())
+        }
+    }
+
+    /// Assigns `value` to the property `key`, as `obj[key] = value` does.
+    ///
+    /// # Failures
+    ///
+    /// Returns `Err` on JSAPI failure, with a pending exception.
+    pub unsafe fn set<T: ToJSValConvertible + ?Sized, K: Into<PropertyKey>>(
+        &self,
+        cx: *mut JSContext,
+        key: K,
+        value: &T,
+    ) -> Result<(), ()> {
+        rooted!(in(cx) let mut id: jsid);
+        key.into().to_id(cx, id.handle_mut())?;
+        rooted!(in(cx) let mut v = UndefinedValue());
+        value.to_jsval(cx, v.handle_mut());
+        if JS_SetPropertyById(
+            cx,
+            self.handle.into(),
+            id.handle().into(),
+            v.handle().into(),
+        ) {
+            Ok(())
+        } else {
+            Err(())
+        }
+    }
+
+    /// Returns whether the object or its prototype chain has the property
+    /// `key`, as `key in obj` does.
+    ///
+    /// # Failures
+    ///
+    /// Returns `Err` on JSAPI failure, with a pending exception.
+    pub unsafe fn has<K: Into<PropertyKey>>(&self, cx: *mut JSContext, key: K) -> Result<bool, ()> {
+        rooted!(in(cx) let mut id: jsid);
+        key.into().to_id(cx, id.handle_mut())?;
+        let mut found = false;
+        if JS_HasPropertyById(cx, self.handle.into(), id.handle().into(), &mut found) {
+            Ok(found)
+        } else {
+            Err(())
+        }
+    }
+
+    /// Returns whether the object itself has the property `key`, as
+    /// `Object.prototype.hasOwnProperty` does.
+    ///
+    /// # Failures
+    ///
+    /// Returns `Err` on JSAPI failure, with a pending exception.
+    pub unsafe fn has_own<K: Into<PropertyKey>>(
+        &self,
+        cx: *mut JSContext,
+        key: K,
+    ) -> Result<bool, ()> {
+        rooted!(in(cx) let mut id: jsid);
+        key.into().to_id(cx, id.handle_mut())?;
+        let mut found = false;
+        if JS_HasOwnPropertyById(cx, self.handle.into(), id.handle().into(), &mut found) {
+            Ok(found)
+        } else {
+            Err(())
+        }
+    }
+
+    /// Deletes the property `key`, as `delete obj[key]` does in sloppy mode.
+    /// Returns `false` if the property exists but is not configurable.
+    ///
+    /// # Failures
+    ///
+    /// Returns `Err` on JSAPI failure, with a pending exception.
+    pub unsafe fn delete<K: Into<PropertyKey>>(
+        &self,
+        cx: *mut JSContext,
+        key: K,
+    ) -> Result<bool, ()> {
+        rooted!(in(cx) let mut id: jsid);
+        key.into().to_id(cx, id.handle_mut())?;
+        let mut result = ObjectOpResult {
+            code_: ObjectOpResult_SpecialCodes::Uninitialized as usize,
+        };
+        if JS_DeletePropertyById(cx, self.handle.into(), id.handle().into(), &mut result) {
+            Ok(result.ok())
+        } else {
+            Err(())
+        }
+    }
+
+    /// Defines the data property `key` with the given value. `attrs` is a
+    /// combination of `JSPROP_ENUMERATE`, `JSPROP_READONLY` and
+    /// `JSPROP_PERMANENT`.
+    ///
+    /// # Failures
+    ///
+    /// Returns `Err` on JSAPI failure, with a pending exception. Redefining
+    /// a non-configurable property throws a TypeError.
+    pub unsafe fn define<T: ToJSValConvertible + ?Sized, K: Into<PropertyKey>>(
+        &self,
+        cx: *mut JSContext,
+        key: K,
+        value: &T,
+        attrs: u32,
+    ) -> Result<(), ()> {
+        rooted!(in(cx) let mut id: jsid);
+        key.into().to_id(cx, id.handle_mut())?;
+        rooted!(in(cx) let mut v = UndefinedValue());
+        value.to_jsval(cx, v.handle_mut());
+        if JS_DefinePropertyById2(
+            cx,
+            self.handle.into(),
+            id.handle().into(),
+            v.handle().into(),
+            attrs,
+        ) {
+            Ok(())
+        } else {
+            Err(())
+        }
+    }
+
+    /// Returns the descriptor of the own property `key`, or `None` if there
+    /// is no such property.
+    ///
+    /// # Failures
+    ///
+    /// Returns `Err` on JSAPI failure, with a pending exception.
+    pub unsafe fn get_own_property_descriptor<K: Into<PropertyKey>>(
+        &self,
+        cx: *mut JSContext,
+        key: K,
+    ) -> Result<Option<PropertyDescriptor>, ()> {
+        rooted!(in(cx) let mut id: jsid);
+        key.into().to_id(cx, id.handle_mut())?;
+        rooted!(in(cx) let mut desc = RawPropertyDescriptor::default());
+        if !JS_GetOwnPropertyDescriptorById(
+            cx,
+            self.handle.into(),
+            id.handle().into(),
+            desc.handle_mut().into(),
+        ) {
+            return Err(());
+        }
+        PropertyDescriptor::from_raw(cx, desc.handle())
+    }
+
+    /// Defines or redefines the property `key` from `desc`, as
+    /// `Object.defineProperty` does.
+    ///
+    /// # Failures
+    ///
+    /// Returns `Err` on JSAPI failure, with a pending exception. Invalid
+    /// redefinitions throw a TypeError.
+    pub unsafe fn define_property<K: Into<PropertyKey>>(
+        &self,
+        cx: *mut JSContext,
+        key: K,
+        desc: &PropertyDescriptor,
+    ) -> Result<(), ()> {
+        auto_root!(in(cx) let desc = *desc);
+        rooted!(in(cx) let mut id: jsid);
+        key.into().to_id(cx, id.handle_mut())?;
+        rooted!(in(cx) let mut raw = RawPropertyDescriptor::default());
+        desc.to_raw(cx, self.handle, raw.handle_mut())?;
+        if JS_DefinePropertyById1(
+            cx,
+            self.handle.into(),
+            id.handle().into(),
+            raw.handle().into(),
+        ) {
+            Ok(())
+        } else {
+            Err(())
+        }
+    }
+
+    /// Returns an iterator over the keys selected by `flags`. The keys are
+    /// collected up front, as `Reflect.ownKeys` does, but only converted to
+    /// `PropertyKey`s as the iterator advances.
+    ///
+    /// # Failures
+    ///
+    /// Returns `Err` on JSAPI failure, with a pending exception.
+    pub unsafe fn keys(&self, cx: *mut JSContext, flags: KeyFlags) -> Result<Keys<'a>, ()> {
+        let mut ids = IdVector::new(cx);
+        if !GetPropertyKeys(cx, self.handle.into(), flags.to_raw(), ids.handle_mut()) {
+            return Err(());
+        }
+        Ok(Keys {
+            cx,
+            ids,
+            index: 0,
+            marker: PhantomData,
+        })
+    }
+
+    /// Returns an iterator over the keys a `for-in` loop visits: enumerable
+    /// string keys of the object and its prototype chain.
+    ///
+    /// # Failures
+    ///
+    /// Returns `Err` on JSAPI failure, with a pending exception.
+    pub unsafe fn for_in(&self, cx: *mut JSContext) -> Result<Keys<'a>, ()> {
+        self.keys(
+            cx,
+            KeyFlags {
+                inherited: true,
+                ..KeyFlags::default()
+            },
+        )
+    }
+
+    /// Returns an iterator over the own enumerable string-keyed properties,
+    /// as `Object.entries` does. Each value is read and converted to `T`
+    /// when the iterator reaches it.
+    ///
+    /// # Failures
+    ///
+    /// Returns `Err` on JSAPI failure, with a pending exception.
+    pub unsafe fn own_entries<T: FromJSValConvertible>(
+        &self,
+        cx: *mut JSContext,
+        option: T::Config,
+    ) -> Result<Entries<'a, T>, ()> {
+        Ok(Entries {
+            object: *self,
+            keys: self.keys(cx, KeyFlags::default())?,
+            option,
+        })
+    }
+}
+
+/// An iterator over the keys of an object, created by `Object::keys`.
+///
+/// Yields `Err` with a pending exception on JSAPI failure.
+pub struct Keys<'a> {
+    cx: *mut JSContext,
+    ids: IdVector,
+    index: usize,
+    marker: PhantomData<HandleObject<'a>>,
+}
+
+impl<'a> Keys<'a> {
+    /// Returns the rooted id the iterator will yield next.
+    fn next_id(&mut self) -> Option<HandleId> {
+        let id = self.ids.get(self.index)?;
+        self.index += 1;
+        // The ids are rooted by the vector.
+        Some(unsafe { HandleId::from_marked_location(id) })
+    }
+}
+
+impl<'a> Iterator for Keys<'a> {
+    type Item = Result<PropertyKey, ()>;
+
+    fn next(&mut self) -> Option<Result<PropertyKey, ()>> {
+        let cx = self.cx;
+        let id = self.next_id()?;
+        Some(unsafe { PropertyKey::from_id(cx, id) })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.ids.len() - self.index;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<'a> ExactSizeIterator for Keys<'a> {}
+
+/// An iterator over the keys and converted values of an object, created by
+/// `Object::own_entries`.
+///
+/// Yields `Err` with a pending exception on JSAPI failure.
+pub struct Entries<'a, T: FromJSValConvertible> {
+    object: Object<'a>,
+    keys: Keys<'a>,
+    option: T::Config,
+}
+
+impl<'a, T: FromJSValConvertible> Iterator for Entries<'a, T>
+where
+    T::Config: Clone,
+{
+    type Item = Result<(PropertyKey, ConversionResult<T>), ()>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let cx = self.keys.cx;
+        let handle = self.object.handle;
+        let option = self.option.clone();
+        let id = self.keys.next_id()?;
+        Some(unsafe {
+            PropertyKey::from_id(cx, id).and_then(|key| {
+                rooted!(in(cx) let mut value = UndefinedValue());
+                if !JS_GetPropertyById(cx, handle.into(), id.into(), value.handle_mut().into()) {
+                    return Err(());
+                }
+                Ok((key, T::from_jsval(cx, value.handle(), option)?))
+            })
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.keys.size_hint()
+    }
+}
@@ -0,0 +1,178 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! JS objects that own Rust data.
+//!
+//! The objects created here belong to a crate-provided class that holds a
+//! `Box<dyn Any>`. The box is dropped when the object is finalized, so
+//! native resources stored in it live exactly as long as the object.
+//!
+//! `size_of::<T>()` is reported to the GC as memory associated with the
+//! object. Heap allocations owned by the data, such as the buffer of a
+//! `Vec`, are not counted; pass their size to `new_object_with_data_and_size`
+//! so that large native resources put the appropriate pressure on the GC.
+
+#![deny(missing_docs)]
+
+use consts::JSCLASS_RESERVED_SLOTS_MASK;
+use jsapi::{AddAssociatedMemory, MemoryUse, RemoveAssociatedMemory};
+use jsapi::{JSClass, JSClassOps, JSContext, JSFreeOp, JSObject, JSTracer, JS_NewObject};
+use jsapi::{JSCLASS_FOREGROUND_FINALIZE, JSCLASS_RESERVED_SLOTS_SHIFT};
+use panic::{abort_on_panic, wrap_panic};
+use rust::CustomTrace;
+use rust::{get_object_class, get_reserved_slot_private, set_reserved_slot_private};
+
+use std::any::Any;
+use std::mem;
+use std::ptr;
+use std::thread::{self, ThreadId};
+
+struct Holder {
+    data: Box<dyn Any>,
+    trace: Option<fn(&dyn Any, *mut JSTracer)>,
+    size: usize,
+    thread: ThreadId,
+}
+
+/// Reserved slot that stores a `Box<Holder>`.
+const HOLDER_SLOT: u32 = 0;
+
+static DATA_CLASS_OPS: JSClassOps = JSClassOps {
+    addProperty: None,
+    delProperty: None,
+    enumerate: None,
+    newEnumerate: None,
+    resolve: None,
+    mayResolve: None,
+    finalize: Some(finalize),
+    call: None,
+    hasInstance: None,
+    construct: None,
+    trace: Some(trace),
+};
+
+static DATA_CLASS: JSClass = JSClass {
+    name: b"RustData\0" as *const u8 as *const _,
+    flags: JSCLASS_FOREGROUND_FINALIZE
+        | ((1 & JSCLASS_RESERVED_SLOTS_MASK) << JSCLASS_RESERVED_SLOTS_SHIFT),
+    cOps: &DATA_CLASS_OPS as *const JSClassOps,
+    spec: ptr::null(),
+    ext: ptr::null(),
+    oOps: ptr::null(),
+};
+
+/// Creates a plain object that owns `data`. The data must not hold GC
+/// things; use `new_object_with_traced_data` for that.
+///
+/// Returns a null pointer on JSAPI failure, in which case `data` is dropped.
+///
+/// # Safety
+///
+/// - `cx` must be valid and in a realm.
+pub unsafe fn new_object_with_data<T: Any>(cx: *mut JSContext, data: Box<T>) -> *mut JSObject {
+    new_object(cx, data, None, mem::size_of::<T>())
+}
+
+/// Like `new_object_with_data`, but reports `size` bytes of associated
+/// memory to the GC instead of `size_of::<T>()`. `size` should include the
+/// heap allocations owned by `data`.
+///
+/// # Safety
+///
+/// - `cx` must be valid and in a realm.
+pub unsafe fn new_object_with_data_and_size<T: Any>(
+    cx: *mut JSContext,
+    data: Box<T>,
+    size: usize,
+) -> *mut JSObject {
+    new_object(cx, data, None, size)
+}
+
+/// Like `new_object_with_data`, but traces `data` whenever the object is
+/// traced, keeping the GC things it holds alive. A panic while tracing
+/// aborts the process, as it cannot unwind through the GC.
+///
+/// # Safety
+///
+/// - `cx` must be valid and in a realm.
+pub unsafe fn new_object_with_traced_data<T: Any + CustomTrace>(
+    cx: *mut JSContext,
+    data: Box<T>,
+) -> *mut JSObject {
+    new_object(cx, data, Some(trace_data::<T>), mem::size_of::<T>())
+}
+
+/// Returns the data owned by `obj`, or `None` if `obj` was not created by
+/// this module or owns data of another type.
+///
+/// # Safety
+///
+/// `obj` must be a valid object that outlives the returned reference.
+pub unsafe fn get_object_data<'a, T: Any>(obj: *mut JSObject) -> Option<&'a T> {
+    if get_object_class(obj) != &DATA_CLASS as *const JSClass {
+        return None;
+    }
+    let holder = get_reserved_slot_private::<Holder>(obj, HOLDER_SLOT);
+    if holder.is_null() {
+        return None;
+    }
+    debug_assert!((*holder).thread == thread::current().id());
+    (*holder).data.downcast_ref::<T>()
+}
+
+unsafe fn new_object<T: Any>(
+    cx: *mut JSContext,
+    data: Box<T>,
+    trace: Option<fn(&dyn Any, *mut JSTracer)>,
+    size: usize,
+) -> *mut JSObject {
+    let obj = JS_NewObject(cx, &DATA_CLASS);
+    if obj.is_null() {
+        return ptr::null_mut();
+    }
+    let holder = Box::new(Holder {
+        data,
+        trace,
+        size,
+        thread: thread::current().id(),
+    });
+    set_reserved_slot_private(obj, HOLDER_SLOT, Box::into_raw(holder));
+    if size != 0 {
+        AddAssociatedMemory(obj, size, MemoryUse::Embedding1);
+    }
+    obj
+}
+
+fn trace_data<T: Any + CustomTrace>(data: &dyn Any, trc: *mut JSTracer) {
+    data.downcast_ref::<T>().unwrap().trace(trc);
+}
+
+unsafe extern "C" fn trace(trc: *mut JSTracer, obj: *mut JSObject) {
+    let holder = get_reserved_slot_private::<Holder>(obj, HOLDER_SLOT);
+    if holder.is_null() {
+        return;
+    }
+    if let Some(trace) = (*holder).trace {
+        abort_on_panic(&mut || trace(&*(*holder).data, trc));
+    }
+}
+
+unsafe extern "C" fn finalize(_fop: *mut JSFreeOp, obj: *mut JSObject) {
+    let holder = get_reserved_slot_private::<Holder>(obj, HOLDER_SLOT);
+    if holder.is_null() {
+        return;
+    }
+    let holder = Box::from_raw(holder);
+    if holder.size != 0 {
+        RemoveAssociatedMemory(obj, holder.size, MemoryUse::Embedding1);
+    }
+    // The data need not be `Send`, so it must not be dropped elsewhere.
+    if holder.thread != thread::current().id() {
+        error!("Leaking Rust data of an object finalized off its owning thread");
+        mem::forget(holder);
+        return;
+    }
+    let mut holder = Some(holder);
+    wrap_panic(&mut || drop(holder.take()));
+}
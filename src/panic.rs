@@ -5,6 +5,7 @@
 use std::any::Any;
 use std::cell::RefCell;
 use std::panic::{catch_unwind, resume_unwind, AssertUnwindSafe};
+use std::process;
 
 thread_local!(static PANIC_PAYLOAD: RefCell<Option<Box<dyn Any + Send>>> = RefCell::new(None));
 
@@ -30,3 +31,13 @@ pub fn wrap_panic(function: &mut dyn FnMut()) {
         }
     }
 }
+
+/// Runs a JS engine callback that cannot fail, such as a trace hook,
+/// aborting the process if it panics. Unwinding into the engine is
+/// undefined behaviour, and the engine cannot recover from a failure there.
+#[inline(never)]
+pub(crate) fn abort_on_panic(function: &mut dyn FnMut()) {
+    if catch_unwind(AssertUnwindSafe(function)).is_err() {
+        process::abort();
+    }
+}
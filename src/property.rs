@@ -0,0 +1,626 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Typed property keys and property descriptors.
+
+#![deny(missing_docs)]
+
+use conversions::{jsstr_to_string, ConversionResult, FromJSValConvertible, ToJSValConvertible};
+use glue::{int_to_jsid, RUST_INTERNED_STRING_TO_JSID, RUST_SYMBOL_TO_JSID};
+use glue::{RUST_JSID_IS_INT, RUST_JSID_IS_STRING, RUST_JSID_IS_SYMBOL};
+use glue::{RUST_JSID_TO_INT, RUST_JSID_TO_STRING, RUST_JSID_TO_SYMBOL};
+use jsapi::PropertyDescriptor as RawPropertyDescriptor;
+use jsapi::{FromPropertyDescriptor, ObjectToCompletePropertyDescriptor};
+use jsapi::{JSContext, JSObject, JSRuntime, JSString, JSTracer, Symbol, Value};
+use jsapi::{JS_AtomizeAndPinJSString, JS_GetRuntime};
+use jsapi::{JS_DefineProperty, JS_GetProperty, JS_HasProperty, JS_NewPlainObject};
+use jsapi::{JS_ValueToId, JSPROP_ENUMERATE};
+use jsval::{BooleanValue, ObjectOrNullValue, ObjectValue, UndefinedValue};
+use rust::ToBoolean;
+use rust::{CustomTrace, Handle, HandleId, HandleObject, HandleValue, MutableHandle};
+use rust::{MutableHandleId, MutableHandleValue};
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::os::raw::c_char;
+use std::ptr;
+
+/// A property key: an array index, a string or a symbol.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PropertyKey {
+    /// An integer index, such as the `0` in `a[0]`.
+    Index(u32),
+    /// A string name that is not an index.
+    String(String),
+    /// A symbol. The symbol must be kept alive by the caller.
+    Symbol(*mut Symbol),
+}
+
+impl PropertyKey {
+    /// Converts a rooted `jsid` to a `PropertyKey`. String ids that are
+    /// array indices become `Index` keys.
+    ///
+    /// # Failures
+    ///
+    /// Returns `Err` on JSAPI failure, with a pending exception.
+    pub unsafe fn from_id(cx: *mut JSContext, id: HandleId) -> Result<PropertyKey, ()> {
+        if RUST_JSID_IS_INT(id.into()) {
+            Ok(PropertyKey::Index(RUST_JSID_TO_INT(id.into()) as u32))
+        } else if RUST_JSID_IS_STRING(id.into()) {
+            let name = jsstr_to_string(cx, RUST_JSID_TO_STRING(id.into()));
+            // Indices above `i32::MAX` do not fit in an integer id and are
+            // stored as strings.
+            match name.parse::<u32>() {
+                Ok(index) if index != u32::max_value() && index.to_string() == name => {
+                    Ok(PropertyKey::Index(index))
+                }
+                _ => Ok(PropertyKey::String(name)),
+            }
+        } else {
+            assert!(RUST_JSID_IS_SYMBOL(id.into()));
+            Ok(PropertyKey::Symbol(RUST_JSID_TO_SYMBOL(id.into())))
+        }
+    }
+
+    /// Stores the `jsid` corresponding to this key in `id`. Strings that
+    /// look like array indices are converted to integer ids.
+    ///
+    /// # Failures
+    ///
+    /// Returns `Err` on JSAPI failure, with a pending exception.
+    pub unsafe fn to_id(&self, cx: *mut JSContext, id: MutableHandleId) -> Result<(), ()> {
+        rooted!(in(cx) let mut value = UndefinedValue());
+        match *self {
+            PropertyKey::Index(index) if index <= i32::max_value() as u32 => {
+                int_to_jsid(index as i32, id.into());
+                return Ok(());
+            }
+            PropertyKey::Index(index) => index.to_jsval(cx, value.handle_mut()),
+            PropertyKey::String(ref name) => name.to_jsval(cx, value.handle_mut()),
+            PropertyKey::Symbol(symbol) => {
+                RUST_SYMBOL_TO_JSID(symbol, id.into());
+                return Ok(());
+            }
+        }
+        if JS_ValueToId(cx, value.handle().into(), id.into()) {
+            Ok(())
+        } else {
+            Err(())
+        }
+    }
+}
+
+thread_local! {
+    /// Atoms pinned by `interned_id`, by runtime and name.
+    static INTERNED_NAMES: RefCell<HashMap<(*mut JSRuntime, &'static str), *mut JSString>> =
+        RefCell::new(HashMap::new());
+}
+
+/// Stores the `jsid` for `name` in `id`, atomizing and pinning the name the
+/// first time it is used in the runtime and reusing the atom afterwards.
+///
+/// Pinned atoms are never collected, so this is meant for the fixed set of
+/// names that native code looks up often, such as `"length"` or `"then"`.
+///
+/// # Failures
+///
+/// Returns `Err` on JSAPI failure, with a pending exception.
+pub unsafe fn interned_id(
+    cx: *mut JSContext,
+    name: &'static str,
+    id: MutableHandleId,
+) -> Result<(), ()> {
+    // Index-like names must become integer ids, never atoms.
+    if name
+        .parse::<u32>()
+        .ok()
+        .map_or(false, |i| i.to_string() == name)
+    {
+        return PropertyKey::from(name).to_id(cx, id);
+    }
+    let rt = JS_GetRuntime(cx);
+    let mut atom = INTERNED_NAMES.with(|names| names.borrow().get(&(rt, name)).cloned());
+    if atom.is_none() {
+        rooted!(in(cx) let mut value = UndefinedValue());
+        name.to_jsval(cx, value.handle_mut());
+        rooted!(in(cx) let string = value.to_string());
+        let pinned = JS_AtomizeAndPinJSString(cx, string.handle().into());
+        if pinned.is_null() {
+            return Err(());
+        }
+        INTERNED_NAMES.with(|names| names.borrow_mut().insert((rt, name), pinned));
+        atom = Some(pinned);
+    }
+    RUST_INTERNED_STRING_TO_JSID(cx, atom.unwrap(), id.into());
+    Ok(())
+}
+
+/// Drops the atoms cached for `rt`, which is being destroyed.
+pub(crate) fn forget_interned_names(rt: *mut JSRuntime) {
+    INTERNED_NAMES.with(|names| names.borrow_mut().retain(|&(runtime, _), _| runtime != rt));
+}
+
+impl<'a> From<&'a str> for PropertyKey {
+    fn from(name: &'a str) -> PropertyKey {
+        PropertyKey::String(name.to_owned())
+    }
+}
+
+impl From<String> for PropertyKey {
+    fn from(name: String) -> PropertyKey {
+        PropertyKey::String(name)
+    }
+}
+
+impl From<u32> for PropertyKey {
+    fn from(index: u32) -> PropertyKey {
+        PropertyKey::Index(index)
+    }
+}
+
+/// A property descriptor, as returned by `Object.getOwnPropertyDescriptor`.
+///
+/// The values it holds are not rooted; use `auto_root!` to keep a descriptor
+/// alive across calls that can GC.
+#[derive(Clone, Copy)]
+pub enum PropertyDescriptor {
+    /// A data property.
+    Data {
+        /// The value of the property.
+        value: Value,
+        /// Whether the value can be changed by assignment.
+        writable: bool,
+        /// Whether the property shows up during enumeration.
+        enumerable: bool,
+        /// Whether the property can be deleted or redefined.
+        configurable: bool,
+    },
+    /// An accessor property. Missing accessors are null.
+    Accessor {
+        /// The getter function, or null.
+        get: *mut JSObject,
+        /// The setter function, or null.
+        set: *mut JSObject,
+        /// Whether the property shows up during enumeration.
+        enumerable: bool,
+        /// Whether the property can be deleted or redefined.
+        configurable: bool,
+    },
+}
+
+unsafe impl CustomTrace for PropertyDescriptor {
+    fn trace(&self, trc: *mut JSTracer) {
+        match *self {
+            PropertyDescriptor::Data { ref value, .. } => value.trace(trc),
+            PropertyDescriptor::Accessor {
+                ref get, ref set, ..
+            } => {
+                if !get.is_null() {
+                    get.trace(trc);
+                }
+                if !set.is_null() {
+                    set.trace(trc);
+                }
+            }
+        }
+    }
+}
+
+impl PropertyDescriptor {
+    /// Returns a writable, enumerable and configurable data descriptor, as
+    /// created by a plain assignment.
+    pub fn value(value: Value) -> PropertyDescriptor {
+        PropertyDescriptor::Data {
+            value,
+            writable: true,
+            enumerable: true,
+            configurable: true,
+        }
+    }
+
+    /// Whether the property shows up during enumeration.
+    pub fn enumerable(&self) -> bool {
+        match *self {
+            PropertyDescriptor::Data { enumerable, .. } => enumerable,
+            PropertyDescriptor::Accessor { enumerable, .. } => enumerable,
+        }
+    }
+
+    /// Whether the property can be deleted or redefined.
+    pub fn configurable(&self) -> bool {
+        match *self {
+            PropertyDescriptor::Data { configurable, .. } => configurable,
+            PropertyDescriptor::Accessor { configurable, .. } => configurable,
+        }
+    }
+
+    /// Converts a descriptor object such as `{ value: 1, writable: true }`
+    /// to a `PropertyDescriptor`, as `Object.defineProperty` interprets its
+    /// third argument. Missing fields default to `false` or `undefined`.
+    ///
+    /// # Failures
+    ///
+    /// Returns `Err` on JSAPI failure or an invalid descriptor, with a
+    /// pending exception.
+    pub unsafe fn from_object(
+        cx: *mut JSContext,
+        object: HandleValue,
+    ) -> Result<PropertyDescriptor, ()> {
+        // The holder only matters to accessors that need to wrap values.
+        rooted!(in(cx) let holder = JS_NewPlainObject(cx));
+        if holder.is_null() {
+            return Err(());
+        }
+        rooted!(in(cx) let mut raw = RawPropertyDescriptor::default());
+        if !ObjectToCompletePropertyDescriptor(
+            cx,
+            holder.handle().into(),
+            object.into(),
+            raw.handle_mut().into(),
+        ) {
+            return Err(());
+        }
+        Ok(PropertyDescriptor::from_raw(cx, raw.handle())?.unwrap())
+    }
+
+    /// Creates a complete descriptor object for this descriptor, as
+    /// `Object.getOwnPropertyDescriptor` returns.
+    ///
+    /// Returns a null pointer on JSAPI failure, with a pending exception.
+    pub unsafe fn to_object(&self, cx: *mut JSContext) -> *mut JSObject {
+        auto_root!(in(cx) let this = *self);
+        rooted!(in(cx) let object = JS_NewPlainObject(cx));
+        if object.is_null() {
+            return ptr::null_mut();
+        }
+        if this.fill_object(cx, object.handle()).is_err() {
+            return ptr::null_mut();
+        }
+        object.get()
+    }
+
+    unsafe fn fill_object(&self, cx: *mut JSContext, object: HandleObject) -> Result<(), ()> {
+        match *self {
+            PropertyDescriptor::Data {
+                value, writable, ..
+            } => {
+                set_field(cx, object, c_str!("value"), value)?;
+                set_field(cx, object, c_str!("writable"), BooleanValue(writable))?;
+            }
+            PropertyDescriptor::Accessor { get, set, .. } => {
+                set_field(cx, object, c_str!("get"), accessor_value(get))?;
+                set_field(cx, object, c_str!("set"), accessor_value(set))?;
+            }
+        }
+        set_field(
+            cx,
+            object,
+            c_str!("enumerable"),
+            BooleanValue(self.enumerable()),
+        )?;
+        set_field(
+            cx,
+            object,
+            c_str!("configurable"),
+            BooleanValue(self.configurable()),
+        )
+    }
+
+    /// Converts a raw descriptor to a `PropertyDescriptor`, or `None` if it
+    /// describes a missing property. Fields missing from a partial descriptor
+    /// are treated as `false` or `undefined`.
+    pub(crate) unsafe fn from_raw(
+        cx: *mut JSContext,
+        desc: Handle<RawPropertyDescriptor>,
+    ) -> Result<Option<PropertyDescriptor>, ()> {
+        rooted!(in(cx) let mut object = UndefinedValue());
+        if !FromPropertyDescriptor(cx, desc.into(), object.handle_mut().into()) {
+            return Err(());
+        }
+        if object.is_undefined() {
+            return Ok(None);
+        }
+        rooted!(in(cx) let object = object.to_object());
+
+        let enumerable = get_bool_field(cx, object.handle(), c_str!("enumerable"))?;
+        let configurable = get_bool_field(cx, object.handle(), c_str!("configurable"))?;
+
+        let mut is_accessor = false;
+        if !JS_HasProperty(cx, object.handle().into(), c_str!("get"), &mut is_accessor) {
+            return Err(());
+        }
+        if !is_accessor
+            && !JS_HasProperty(cx, object.handle().into(), c_str!("set"), &mut is_accessor)
+        {
+            return Err(());
+        }
+
+        if is_accessor {
+            rooted!(in(cx) let get = get_object_field(cx, object.handle(), c_str!("get"))?);
+            let set = get_object_field(cx, object.handle(), c_str!("set"))?;
+            Ok(Some(PropertyDescriptor::Accessor {
+                get: get.get(),
+                set,
+                enumerable,
+                configurable,
+            }))
+        } else {
+            let writable = get_bool_field(cx, object.handle(), c_str!("writable"))?;
+            rooted!(in(cx) let mut value = UndefinedValue());
+            if !JS_GetProperty(
+                cx,
+                object.handle().into(),
+                c_str!("value"),
+                value.handle_mut().into(),
+            ) {
+                return Err(());
+            }
+            Ok(Some(PropertyDescriptor::Data {
+                value: value.get(),
+                writable,
+                enumerable,
+                configurable,
+            }))
+        }
+    }
+
+    /// Fills `desc` with this descriptor, for a property of `obj`.
+    pub(crate) unsafe fn to_raw(
+        &self,
+        cx: *mut JSContext,
+        obj: HandleObject,
+        desc: MutableHandle<RawPropertyDescriptor>,
+    ) -> Result<(), ()> {
+        rooted!(in(cx) let object = self.to_object(cx));
+        if object.is_null() {
+            return Err(());
+        }
+        rooted!(in(cx) let object = ObjectValue(object.get()));
+        if ObjectToCompletePropertyDescriptor(cx, obj.into(), object.handle().into(), desc.into()) {
+            Ok(())
+        } else {
+            Err(())
+        }
+    }
+}
+
+/// A possibly partial property descriptor, as passed to
+/// `Object.defineProperty`. Fields missing from the definition are `None`;
+/// a present but undefined `get` or `set` is a null pointer.
+#[derive(Clone, Copy, Default)]
+pub(crate) struct PropertyDefinition {
+    value: Option<Value>,
+    writable: Option<bool>,
+    get: Option<*mut JSObject>,
+    set: Option<*mut JSObject>,
+    enumerable: Option<bool>,
+    configurable: Option<bool>,
+}
+
+unsafe impl CustomTrace for PropertyDefinition {
+    fn trace(&self, trc: *mut JSTracer) {
+        if let Some(ref value) = self.value {
+            value.trace(trc);
+        }
+        for accessor in &[&self.get, &self.set] {
+            if let Some(ref accessor) = **accessor {
+                if !accessor.is_null() {
+                    accessor.trace(trc);
+                }
+            }
+        }
+    }
+}
+
+impl PropertyDefinition {
+    /// Converts the descriptor passed to a `defineProperty` hook of `obj`,
+    /// keeping track of which fields it has.
+    pub(crate) unsafe fn from_raw(
+        cx: *mut JSContext,
+        obj: HandleObject,
+        desc: Handle<RawPropertyDescriptor>,
+    ) -> Result<PropertyDefinition, ()> {
+        // `FromPropertyDescriptor` only converts descriptors that record the
+        // object they belong to, and leaves out the fields they lack.
+        rooted!(in(cx) let mut owned = RawPropertyDescriptor::default());
+        owned.obj = obj.get();
+        owned.attrs = desc.attrs;
+        owned.getter = desc.getter;
+        owned.setter = desc.setter;
+        owned.value = desc.value;
+        rooted!(in(cx) let mut object = UndefinedValue());
+        if !FromPropertyDescriptor(cx, owned.handle().into(), object.handle_mut().into()) {
+            return Err(());
+        }
+        rooted!(in(cx) let object = object.to_object());
+
+        let writable = get_optional_bool_field(cx, object.handle(), c_str!("writable"))?;
+        let enumerable = get_optional_bool_field(cx, object.handle(), c_str!("enumerable"))?;
+        let configurable = get_optional_bool_field(cx, object.handle(), c_str!("configurable"))?;
+        rooted!(in(cx) let mut value = UndefinedValue());
+        let has_value =
+            get_optional_field(cx, object.handle(), c_str!("value"), value.handle_mut())?;
+        rooted!(in(cx) let mut get = UndefinedValue());
+        let has_get = get_optional_field(cx, object.handle(), c_str!("get"), get.handle_mut())?;
+        rooted!(in(cx) let mut set = UndefinedValue());
+        let has_set = get_optional_field(cx, object.handle(), c_str!("set"), set.handle_mut())?;
+
+        let accessor = |present: bool, value: Value| {
+            if !present {
+                None
+            } else if value.is_object() {
+                Some(value.to_object())
+            } else {
+                Some(ptr::null_mut())
+            }
+        };
+        Ok(PropertyDefinition {
+            value: if has_value { Some(value.get()) } else { None },
+            writable,
+            get: accessor(has_get, get.get()),
+            set: accessor(has_set, set.get()),
+            enumerable,
+            configurable,
+        })
+    }
+
+    /// Returns the descriptor a property ends up with when this definition
+    /// is applied to it, as `ValidateAndApplyPropertyDescriptor` does,
+    /// without validating the change. Missing fields keep the attributes of
+    /// the `current` property, or default to `false` and `undefined` for a
+    /// new one.
+    // https://tc39.es/ecma262/#sec-validateandapplypropertydescriptor
+    pub(crate) fn apply(&self, current: Option<&PropertyDescriptor>) -> PropertyDescriptor {
+        let enumerable = self
+            .enumerable
+            .or_else(|| current.map(PropertyDescriptor::enumerable))
+            .unwrap_or(false);
+        let configurable = self
+            .configurable
+            .or_else(|| current.map(PropertyDescriptor::configurable))
+            .unwrap_or(false);
+        let is_accessor = self.get.is_some() || self.set.is_some();
+        let is_data = self.value.is_some() || self.writable.is_some();
+        match current {
+            // Fields missing from a definition of the same kind, or from a
+            // generic one, keep their current values.
+            Some(&PropertyDescriptor::Accessor { get, set, .. }) if !is_data => {
+                PropertyDescriptor::Accessor {
+                    get: self.get.unwrap_or(get),
+                    set: self.set.unwrap_or(set),
+                    enumerable,
+                    configurable,
+                }
+            }
+            Some(&PropertyDescriptor::Data {
+                value, writable, ..
+            }) if !is_accessor => PropertyDescriptor::Data {
+                value: self.value.unwrap_or(value),
+                writable: self.writable.unwrap_or(writable),
+                enumerable,
+                configurable,
+            },
+            // New properties and converted ones get default values.
+            _ if is_accessor => PropertyDescriptor::Accessor {
+                get: self.get.unwrap_or(ptr::null_mut()),
+                set: self.set.unwrap_or(ptr::null_mut()),
+                enumerable,
+                configurable,
+            },
+            _ => PropertyDescriptor::Data {
+                value: self.value.unwrap_or_else(UndefinedValue),
+                writable: self.writable.unwrap_or(false),
+                enumerable,
+                configurable,
+            },
+        }
+    }
+}
+
+impl ToJSValConvertible for PropertyDescriptor {
+    unsafe fn to_jsval(&self, cx: *mut JSContext, mut rval: MutableHandleValue) {
+        let object = self.to_object(cx);
+        if object.is_null() {
+            panic!("creating a property descriptor object failed");
+        }
+        rval.set(ObjectValue(object));
+    }
+}
+
+impl FromJSValConvertible for PropertyDescriptor {
+    type Config = ();
+    unsafe fn from_jsval(
+        cx: *mut JSContext,
+        value: HandleValue,
+        _option: (),
+    ) -> Result<ConversionResult<PropertyDescriptor>, ()> {
+        PropertyDescriptor::from_object(cx, value).map(ConversionResult::Success)
+    }
+}
+
+fn accessor_value(accessor: *mut JSObject) -> Value {
+    if accessor.is_null() {
+        UndefinedValue()
+    } else {
+        ObjectOrNullValue(accessor)
+    }
+}
+
+unsafe fn set_field(
+    cx: *mut JSContext,
+    object: HandleObject,
+    name: *const c_char,
+    value: Value,
+) -> Result<(), ()> {
+    rooted!(in(cx) let value = value);
+    if JS_DefineProperty(
+        cx,
+        object.into(),
+        name,
+        value.handle().into(),
+        JSPROP_ENUMERATE as u32,
+    ) {
+        Ok(())
+    } else {
+        Err(())
+    }
+}
+
+unsafe fn get_bool_field(
+    cx: *mut JSContext,
+    object: HandleObject,
+    name: *const c_char,
+) -> Result<bool, ()> {
+    rooted!(in(cx) let mut value = UndefinedValue());
+    if !JS_GetProperty(cx, object.into(), name, value.handle_mut().into()) {
+        return Err(());
+    }
+    Ok(ToBoolean(value.handle()))
+}
+
+/// Reads the property `name` into `rval` and returns whether it exists.
+unsafe fn get_optional_field(
+    cx: *mut JSContext,
+    object: HandleObject,
+    name: *const c_char,
+    rval: MutableHandleValue,
+) -> Result<bool, ()> {
+    let mut found = false;
+    if !JS_HasProperty(cx, object.into(), name, &mut found) {
+        return Err(());
+    }
+    if found && !JS_GetProperty(cx, object.into(), name, rval.into()) {
+        return Err(());
+    }
+    Ok(found)
+}
+
+unsafe fn get_optional_bool_field(
+    cx: *mut JSContext,
+    object: HandleObject,
+    name: *const c_char,
+) -> Result<Option<bool>, ()> {
+    rooted!(in(cx) let mut value = UndefinedValue());
+    if get_optional_field(cx, object, name, value.handle_mut())? {
+        Ok(Some(ToBoolean(value.handle())))
+    } else {
+        Ok(None)
+    }
+}
+
+unsafe fn get_object_field(
+    cx: *mut JSContext,
+    object: HandleObject,
+    name: *const c_char,
+) -> Result<*mut JSObject, ()> {
+    rooted!(in(cx) let mut value = UndefinedValue());
+    if !JS_GetProperty(cx, object.into(), name, value.handle_mut().into()) {
+        return Err(());
+    }
+    if value.is_object() {
+        Ok(value.to_object())
+    } else {
+        Ok(ptr::null_mut())
+    }
+}
@@ -0,0 +1,513 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Exotic objects implemented in Rust.
+//!
+//! A type implementing [`ProxyHandler`] decides how a proxy object answers
+//! property lookups, assignments, deletions and enumeration. The handler is
+//! owned by the proxy and dropped when the proxy is finalized.
+//!
+//! [`ProxyHandler`]: trait.ProxyHandler.html
+
+#![deny(missing_docs)]
+
+use glue::{AppendToIdVector, CreateProxyHandler, GetProxyPrivate, NewProxyObject, ProxyTraps};
+use jsapi::Handle as RawHandle;
+use jsapi::PropertyDescriptor as RawPropertyDescriptor;
+use jsapi::SetPropertyIgnoringNamedGetter;
+use jsapi::{jsid, MutableHandleValue as RawMutableHandleValue};
+use jsapi::{Call, GetObjectProto, JS_ForwardGetPropertyTo, JS_HasPropertyById};
+use jsapi::{HandleId as RawHandleId, HandleObject as RawHandleObject};
+use jsapi::{HandleValue as RawHandleValue, MutableHandleIdVector as RawMutableHandleIdVector};
+use jsapi::{HandleValueArray, JSContext, JSFreeOp, JSObject, JSTracer, ObjectOpResult};
+use jsapi::{MutableHandle as RawMutableHandle, MutableHandleObject as RawMutableHandleObject};
+use jsval::{ObjectValue, PrivateValue, UndefinedValue};
+use panic::{abort_on_panic, wrap_panic};
+use property::{PropertyDefinition, PropertyDescriptor, PropertyKey};
+use rust::{Handle, HandleObject, HandleValue, MutableHandle, MutableHandleValue};
+
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::os::raw::c_void;
+use std::ptr;
+use std::sync::Mutex;
+
+/// The behaviour of a proxy object created by [`new_proxy_object`].
+///
+/// Only `get_own_property_descriptor`, `define_property`, `own_keys` and
+/// `delete` need to be implemented for most handlers; the remaining traps
+/// default to the behaviour of an ordinary object built on top of them.
+///
+/// Every method returns `Err(())` to signal that a JS exception is pending.
+/// A panic in a trap is caught before it reaches the engine: the trap fails
+/// without a pending exception, which stops the running script with an
+/// uncatchable error, and `panic::maybe_resume_unwind` resumes the panic
+/// once control is back in Rust. A panic in `trace` aborts the process.
+///
+/// [`new_proxy_object`]: fn.new_proxy_object.html
+pub trait ProxyHandler: 'static {
+    /// Returns the descriptor of the own property `key`, or `None` if the
+    /// proxy has no such property.
+    fn get_own_property_descriptor(
+        &self,
+        _cx: *mut JSContext,
+        _proxy: HandleObject,
+        _key: &PropertyKey,
+    ) -> Result<Option<PropertyDescriptor>, ()> {
+        Ok(None)
+    }
+
+    /// Defines or redefines the own property `key`. Returns `false` to
+    /// refuse the definition, which throws in strict mode.
+    ///
+    /// `desc` is the complete descriptor the property ends up with. Fields
+    /// missing from a partial definition, as in
+    /// `Object.defineProperty(proxy, key, { value: 1 })`, are taken from the
+    /// descriptor returned by `get_own_property_descriptor`, or default to
+    /// `false` and `undefined` for a new property.
+    fn define_property(
+        &self,
+        _cx: *mut JSContext,
+        _proxy: HandleObject,
+        _key: &PropertyKey,
+        _desc: &PropertyDescriptor,
+    ) -> Result<bool, ()> {
+        Ok(false)
+    }
+
+    /// Returns the keys of all own properties, in enumeration order.
+    fn own_keys(&self, _cx: *mut JSContext, _proxy: HandleObject) -> Result<Vec<PropertyKey>, ()> {
+        Ok(vec![])
+    }
+
+    /// Deletes the own property `key`. Returns `false` if the property
+    /// cannot be deleted, which throws in strict mode.
+    fn delete(
+        &self,
+        _cx: *mut JSContext,
+        _proxy: HandleObject,
+        _key: &PropertyKey,
+    ) -> Result<bool, ()> {
+        Ok(true)
+    }
+
+    /// Whether the proxy or its prototype chain has the property `key`.
+    fn has(&self, cx: *mut JSContext, proxy: HandleObject, key: &PropertyKey) -> Result<bool, ()> {
+        if self.get_own_property_descriptor(cx, proxy, key)?.is_some() {
+            return Ok(true);
+        }
+        unsafe {
+            rooted!(in(cx) let proto = prototype(cx, proxy)?);
+            if proto.is_null() {
+                return Ok(false);
+            }
+            rooted!(in(cx) let mut id: jsid);
+            key.to_id(cx, id.handle_mut())?;
+            let mut found = false;
+            if !JS_HasPropertyById(cx, proto.handle().into(), id.handle().into(), &mut found) {
+                return Err(());
+            }
+            Ok(found)
+        }
+    }
+
+    /// Reads the property `key` into `rval`. Own accessors are called with
+    /// `receiver` as `this`; missing properties are looked up on the
+    /// prototype.
+    fn get(
+        &self,
+        cx: *mut JSContext,
+        proxy: HandleObject,
+        receiver: HandleValue,
+        key: &PropertyKey,
+        mut rval: MutableHandleValue,
+    ) -> Result<(), ()> {
+        unsafe {
+            match self.get_own_property_descriptor(cx, proxy, key)? {
+                Some(PropertyDescriptor::Data { value, .. }) => {
+                    rval.set(value);
+                    Ok(())
+                }
+                Some(PropertyDescriptor::Accessor { get, .. }) => {
+                    if get.is_null() {
+                        rval.set(UndefinedValue());
+                        return Ok(());
+                    }
+                    rooted!(in(cx) let getter = ObjectValue(get));
+                    let args = HandleValueArray::new();
+                    if Call(
+                        cx,
+                        receiver.into(),
+                        getter.handle().into(),
+                        &args,
+                        rval.into(),
+                    ) {
+                        Ok(())
+                    } else {
+                        Err(())
+                    }
+                }
+                None => {
+                    rooted!(in(cx) let proto = prototype(cx, proxy)?);
+                    if proto.is_null() {
+                        rval.set(UndefinedValue());
+                        return Ok(());
+                    }
+                    rooted!(in(cx) let mut id: jsid);
+                    key.to_id(cx, id.handle_mut())?;
+                    if JS_ForwardGetPropertyTo(
+                        cx,
+                        proto.handle().into(),
+                        id.handle().into(),
+                        receiver.into(),
+                        rval.into(),
+                    ) {
+                        Ok(())
+                    } else {
+                        Err(())
+                    }
+                }
+            }
+        }
+    }
+
+    /// Assigns `value` to the property `key`, as `OrdinarySet` does, and
+    /// reports the outcome in `result`: `result.succeed()`, or a failure such
+    /// as `result.failReadOnly()` that throws in strict mode with the
+    /// matching message.
+    ///
+    /// Own setters are called with `receiver` as `this`. Writable data
+    /// properties are created or updated on `receiver`, which goes through
+    /// `define_property` when the receiver is the proxy itself. Properties
+    /// the proxy does not have are assigned through its prototype.
+    fn set(
+        &self,
+        cx: *mut JSContext,
+        proxy: HandleObject,
+        receiver: HandleValue,
+        key: &PropertyKey,
+        value: HandleValue,
+        result: &mut ObjectOpResult,
+    ) -> Result<(), ()> {
+        unsafe {
+            rooted!(in(cx) let mut id: jsid);
+            key.to_id(cx, id.handle_mut())?;
+            // A descriptor without an object stands for a missing property.
+            rooted!(in(cx) let mut own = RawPropertyDescriptor::default());
+            if let Some(desc) = self.get_own_property_descriptor(cx, proxy, key)? {
+                auto_root!(in(cx) let desc = desc);
+                desc.to_raw(cx, proxy, own.handle_mut())?;
+            }
+            if SetPropertyIgnoringNamedGetter(
+                cx,
+                proxy.into(),
+                id.handle().into(),
+                value.into(),
+                receiver.into(),
+                own.handle().into(),
+                result,
+            ) {
+                Ok(())
+            } else {
+                Err(())
+            }
+        }
+    }
+
+    /// Whether new properties can be added to the proxy.
+    fn is_extensible(&self, _cx: *mut JSContext, _proxy: HandleObject) -> Result<bool, ()> {
+        Ok(true)
+    }
+
+    /// Makes the proxy non-extensible. Returns `false` to refuse. A handler
+    /// that accepts must report `false` from `is_extensible` afterwards.
+    fn prevent_extensions(&self, _cx: *mut JSContext, _proxy: HandleObject) -> Result<bool, ()> {
+        Ok(false)
+    }
+
+    /// Traces the GC things held by the handler. This runs during garbage
+    /// collection, which cannot fail, so a panic here aborts the process.
+    fn trace(&self, _trc: *mut JSTracer) {}
+}
+
+lazy_static! {
+    /// The C++ handler shared by all proxies of a given `ProxyHandler` type.
+    static ref HANDLERS: Mutex<HashMap<TypeId, usize>> = Mutex::new(HashMap::new());
+}
+
+fn proxy_handler<H: ProxyHandler>() -> *const c_void {
+    let mut handlers = HANDLERS.lock().unwrap();
+    let handler = handlers.entry(TypeId::of::<H>()).or_insert_with(|| {
+        let traps = ProxyTraps {
+            getOwnPropertyDescriptor: Some(get_own_property_descriptor::<H>),
+            defineProperty: Some(define_property::<H>),
+            ownPropertyKeys: Some(own_property_keys::<H>),
+            delete_: Some(delete::<H>),
+            getPrototypeIfOrdinary: Some(get_prototype_if_ordinary),
+            preventExtensions: Some(prevent_extensions::<H>),
+            isExtensible: Some(is_extensible::<H>),
+            has: Some(has::<H>),
+            get: Some(get::<H>),
+            set: Some(set::<H>),
+            trace: Some(trace::<H>),
+            finalize: Some(finalize::<H>),
+            ..ProxyTraps::default()
+        };
+        unsafe { CreateProxyHandler(&traps, ptr::null()) as usize }
+    });
+    *handler as *const c_void
+}
+
+/// Creates a new proxy object whose behaviour is defined by `handler`, with
+/// `proto` as its prototype. The handler is dropped when the proxy is
+/// finalized.
+///
+/// Returns a null pointer on JSAPI failure.
+///
+/// # Safety
+///
+/// - `cx` must be valid and in a realm.
+pub unsafe fn new_proxy_object<H: ProxyHandler>(
+    cx: *mut JSContext,
+    handler: H,
+    proto: HandleObject,
+) -> *mut JSObject {
+    // The handler may be zero-sized or byte-aligned, but a private value
+    // must be 2-byte aligned, so store a pointer to a box instead.
+    let handler = Box::into_raw(Box::new(Box::new(handler)));
+    rooted!(in(cx) let private = PrivateValue(handler as *const c_void));
+    let obj = NewProxyObject(
+        cx,
+        proxy_handler::<H>(),
+        private.handle().into(),
+        proto.get(),
+        ptr::null(),
+        false,
+    );
+    if obj.is_null() {
+        drop(Box::from_raw(handler));
+    }
+    obj
+}
+
+/// Returns the handler of a proxy created by `new_proxy_object::<H>`.
+///
+/// # Safety
+///
+/// `proxy` must have been created by `new_proxy_object` with a handler of
+/// type `H`, and must outlive the returned reference.
+pub unsafe fn get_proxy_handler<'a, H: ProxyHandler>(proxy: *mut JSObject) -> &'a H {
+    let mut private = UndefinedValue();
+    GetProxyPrivate(proxy, &mut private);
+    &**(private.to_private() as *const Box<H>)
+}
+
+unsafe fn prototype(cx: *mut JSContext, proxy: HandleObject) -> Result<*mut JSObject, ()> {
+    rooted!(in(cx) let mut proto = ptr::null_mut::<JSObject>());
+    if GetObjectProto(cx, proxy.into(), proto.handle_mut().into()) {
+        Ok(proto.get())
+    } else {
+        Err(())
+    }
+}
+
+/// Runs a trap body, converting errors and panics to a `false` return.
+fn wrap_trap(body: &mut dyn FnMut() -> Result<(), ()>) -> bool {
+    let mut result = false;
+    wrap_panic(&mut || result = body().is_ok());
+    result
+}
+
+unsafe extern "C" fn get_own_property_descriptor<H: ProxyHandler>(
+    cx: *mut JSContext,
+    proxy: RawHandleObject,
+    id: RawHandleId,
+    desc: RawMutableHandle<RawPropertyDescriptor>,
+) -> bool {
+    wrap_trap(&mut || {
+        let proxy = Handle::from_raw(proxy);
+        let key = PropertyKey::from_id(cx, Handle::from_raw(id))?;
+        let handler = get_proxy_handler::<H>(proxy.get());
+        // The engine clears `desc` before calling us, so leaving it alone
+        // reports a missing property.
+        match handler.get_own_property_descriptor(cx, proxy, &key)? {
+            Some(found) => found.to_raw(cx, proxy, MutableHandle::from_raw(desc)),
+            None => Ok(()),
+        }
+    })
+}
+
+unsafe extern "C" fn define_property<H: ProxyHandler>(
+    cx: *mut JSContext,
+    proxy: RawHandleObject,
+    id: RawHandleId,
+    desc: RawHandle<RawPropertyDescriptor>,
+    result: *mut ObjectOpResult,
+) -> bool {
+    wrap_trap(&mut || {
+        let proxy = Handle::from_raw(proxy);
+        let key = PropertyKey::from_id(cx, Handle::from_raw(id))?;
+        let definition = PropertyDefinition::from_raw(cx, proxy, Handle::from_raw(desc))?;
+        auto_root!(in(cx) let definition = definition);
+        let handler = get_proxy_handler::<H>(proxy.get());
+        let current = handler.get_own_property_descriptor(cx, proxy, &key)?;
+        auto_root!(in(cx) let desc = definition.apply(current.as_ref()));
+        if handler.define_property(cx, proxy, &key, &*desc)? {
+            (*result).succeed();
+        } else {
+            (*result).failCantRedefineProp();
+        }
+        Ok(())
+    })
+}
+
+unsafe extern "C" fn own_property_keys<H: ProxyHandler>(
+    cx: *mut JSContext,
+    proxy: RawHandleObject,
+    props: RawMutableHandleIdVector,
+) -> bool {
+    wrap_trap(&mut || {
+        let proxy = Handle::from_raw(proxy);
+        let handler = get_proxy_handler::<H>(proxy.get());
+        rooted!(in(cx) let mut id: jsid);
+        for key in handler.own_keys(cx, proxy)? {
+            key.to_id(cx, id.handle_mut())?;
+            if !AppendToIdVector(props, id.handle().into()) {
+                return Err(());
+            }
+        }
+        Ok(())
+    })
+}
+
+unsafe extern "C" fn delete<H: ProxyHandler>(
+    cx: *mut JSContext,
+    proxy: RawHandleObject,
+    id: RawHandleId,
+    result: *mut ObjectOpResult,
+) -> bool {
+    wrap_trap(&mut || {
+        let proxy = Handle::from_raw(proxy);
+        let key = PropertyKey::from_id(cx, Handle::from_raw(id))?;
+        let handler = get_proxy_handler::<H>(proxy.get());
+        if handler.delete(cx, proxy, &key)? {
+            (*result).succeed();
+        } else {
+            (*result).failCantDelete();
+        }
+        Ok(())
+    })
+}
+
+unsafe extern "C" fn get_prototype_if_ordinary(
+    cx: *mut JSContext,
+    proxy: RawHandleObject,
+    is_ordinary: *mut bool,
+    protop: RawMutableHandleObject,
+) -> bool {
+    *is_ordinary = true;
+    GetObjectProto(cx, proxy, protop)
+}
+
+unsafe extern "C" fn prevent_extensions<H: ProxyHandler>(
+    cx: *mut JSContext,
+    proxy: RawHandleObject,
+    result: *mut ObjectOpResult,
+) -> bool {
+    wrap_trap(&mut || {
+        let proxy = Handle::from_raw(proxy);
+        let handler = get_proxy_handler::<H>(proxy.get());
+        if handler.prevent_extensions(cx, proxy)? {
+            (*result).succeed();
+        } else {
+            (*result).failCantPreventExtensions();
+        }
+        Ok(())
+    })
+}
+
+unsafe extern "C" fn is_extensible<H: ProxyHandler>(
+    cx: *mut JSContext,
+    proxy: RawHandleObject,
+    succeeded: *mut bool,
+) -> bool {
+    wrap_trap(&mut || {
+        let proxy = Handle::from_raw(proxy);
+        let handler = get_proxy_handler::<H>(proxy.get());
+        *succeeded = handler.is_extensible(cx, proxy)?;
+        Ok(())
+    })
+}
+
+unsafe extern "C" fn has<H: ProxyHandler>(
+    cx: *mut JSContext,
+    proxy: RawHandleObject,
+    id: RawHandleId,
+    bp: *mut bool,
+) -> bool {
+    wrap_trap(&mut || {
+        let proxy = Handle::from_raw(proxy);
+        let key = PropertyKey::from_id(cx, Handle::from_raw(id))?;
+        let handler = get_proxy_handler::<H>(proxy.get());
+        *bp = handler.has(cx, proxy, &key)?;
+        Ok(())
+    })
+}
+
+unsafe extern "C" fn get<H: ProxyHandler>(
+    cx: *mut JSContext,
+    proxy: RawHandleObject,
+    receiver: RawHandleValue,
+    id: RawHandleId,
+    vp: RawMutableHandleValue,
+) -> bool {
+    wrap_trap(&mut || {
+        let proxy = Handle::from_raw(proxy);
+        let key = PropertyKey::from_id(cx, Handle::from_raw(id))?;
+        let handler = get_proxy_handler::<H>(proxy.get());
+        handler.get(
+            cx,
+            proxy,
+            Handle::from_raw(receiver),
+            &key,
+            MutableHandle::from_raw(vp),
+        )
+    })
+}
+
+unsafe extern "C" fn set<H: ProxyHandler>(
+    cx: *mut JSContext,
+    proxy: RawHandleObject,
+    id: RawHandleId,
+    v: RawHandleValue,
+    receiver: RawHandleValue,
+    result: *mut ObjectOpResult,
+) -> bool {
+    wrap_trap(&mut || {
+        let proxy = Handle::from_raw(proxy);
+        let key = PropertyKey::from_id(cx, Handle::from_raw(id))?;
+        let handler = get_proxy_handler::<H>(proxy.get());
+        handler.set(
+            cx,
+            proxy,
+            Handle::from_raw(receiver),
+            &key,
+            Handle::from_raw(v),
+            &mut *result,
+        )
+    })
+}
+
+unsafe extern "C" fn trace<H: ProxyHandler>(trc: *mut JSTracer, proxy: *mut JSObject) {
+    abort_on_panic(&mut || get_proxy_handler::<H>(proxy).trace(trc));
+}
+
+unsafe extern "C" fn finalize<H: ProxyHandler>(_fop: *mut JSFreeOp, proxy: *mut JSObject) {
+    let mut private = UndefinedValue();
+    GetProxyPrivate(proxy, &mut private);
+    if !private.is_undefined() {
+        let handler = private.to_private() as *mut Box<H>;
+        wrap_panic(&mut || drop(Box::from_raw(handler)));
+    }
+}
@@ -0,0 +1,440 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Rooted JS `RegExp` objects, for matching with JS semantics from Rust.
+//!
+//! ```ignore
+//! let flags = "u".parse::<Flags>()?;
+//! if let Err(error) = JsRegExp::check_syntax(cx, pattern, flags)? {
+//!     return Err(format!("bad pattern: {}", error));
+//! }
+//! rooted!(in(cx) let mut re = ptr::null_mut::<JSObject>());
+//! let re = JsRegExp::new(cx, pattern, flags, re.handle_mut())?;
+//! if let Some(m) = re.exec(cx, input, 0)? {
+//!     println!("{:?} at {}", m.get(0), m.index);
+//! }
+//! ```
+//!
+//! Indices are in UTF-16 code units, as in JS.
+
+#![deny(missing_docs)]
+
+use array::JsArray;
+use conversions::{jsstr_to_string, ConversionBehavior, ConversionResult};
+use error::throw_type_error;
+use glue::UnwrapObjectStatic;
+use jsapi::JS::RegExpFlags as RawFlags;
+use jsapi::JS::{RegExpFlag_DotAll, RegExpFlag_Global, RegExpFlag_IgnoreCase};
+use jsapi::JS::{RegExpFlag_Multiline, RegExpFlag_Sticky, RegExpFlag_Unicode};
+use jsapi::{CheckRegExpSyntax, ExecuteRegExpNoStatics, NewUCRegExpObject, ObjectIsRegExp};
+use jsapi::{GetRegExpFlags, GetRegExpSource, JSAutoRealm, JSContext, JSObject, JS_WrapValue};
+use jsval::UndefinedValue;
+use object::{KeyFlags, Object};
+use property::PropertyKey;
+use rust::{Handle, HandleObject, HandleValue, MutableHandleObject, MutableHandleValue};
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::ops::BitOr;
+use std::str::FromStr;
+
+/// The flags of a regular expression.
+///
+/// They parse from and display as the flags of a regular expression literal,
+/// in the order of `RegExp.prototype.flags`.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Flags {
+    bits: u8,
+}
+
+const FLAGS: [(char, u8); 6] = [
+    ('g', RegExpFlag_Global),
+    ('i', RegExpFlag_IgnoreCase),
+    ('m', RegExpFlag_Multiline),
+    ('s', RegExpFlag_DotAll),
+    ('u', RegExpFlag_Unicode),
+    ('y', RegExpFlag_Sticky),
+];
+
+impl Flags {
+    /// No flags.
+    pub const NONE: Flags = Flags { bits: 0 };
+    /// `g`: find all matches.
+    pub const GLOBAL: Flags = Flags {
+        bits: RegExpFlag_Global,
+    };
+    /// `i`: match case-insensitively.
+    pub const IGNORE_CASE: Flags = Flags {
+        bits: RegExpFlag_IgnoreCase,
+    };
+    /// `m`: `^` and `$` match at line breaks.
+    pub const MULTILINE: Flags = Flags {
+        bits: RegExpFlag_Multiline,
+    };
+    /// `s`: `.` matches line terminators.
+    pub const DOT_ALL: Flags = Flags {
+        bits: RegExpFlag_DotAll,
+    };
+    /// `u`: the pattern is a sequence of code points.
+    pub const UNICODE: Flags = Flags {
+        bits: RegExpFlag_Unicode,
+    };
+    /// `y`: only match at the start index.
+    pub const STICKY: Flags = Flags {
+        bits: RegExpFlag_Sticky,
+    };
+
+    /// Returns whether all of `other` are set.
+    pub fn contains(self, other: Flags) -> bool {
+        self.bits & other.bits == other.bits
+    }
+
+    fn to_raw(self) -> RawFlags {
+        RawFlags { flags_: self.bits }
+    }
+
+    fn from_raw(flags: RawFlags) -> Flags {
+        Flags { bits: flags.flags_ }
+    }
+}
+
+impl BitOr for Flags {
+    type Output = Flags;
+
+    fn bitor(self, other: Flags) -> Flags {
+        Flags {
+            bits: self.bits | other.bits,
+        }
+    }
+}
+
+impl FromStr for Flags {
+    type Err = SyntaxError;
+
+    /// Parses flags like the `RegExp` constructor, rejecting unknown and
+    /// repeated flags.
+    fn from_str(s: &str) -> Result<Flags, SyntaxError> {
+        let mut flags = Flags::NONE;
+        for c in s.chars() {
+            match FLAGS.iter().find(|&&(flag, _)| flag == c) {
+                Some(&(_, bits)) if flags.bits & bits == 0 => flags.bits |= bits,
+                _ => {
+                    return Err(SyntaxError {
+                        message: format!("invalid regular expression flag {}", c),
+                    })
+                }
+            }
+        }
+        Ok(flags)
+    }
+}
+
+impl fmt::Display for Flags {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for &(flag, bits) in FLAGS.iter() {
+            if self.bits & bits != 0 {
+                write!(f, "{}", flag)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A syntax error in a regular expression, with the engine's message.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SyntaxError {
+    /// The message of the `SyntaxError` the engine would throw.
+    pub message: String,
+}
+
+impl fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl Error for SyntaxError {}
+
+/// A successful match, as returned by `RegExp.prototype.exec`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Match {
+    /// The index of the match in the input, in UTF-16 code units.
+    pub index: usize,
+    /// The matched text, followed by the text of each capture group, or
+    /// `None` for groups that did not participate in the match.
+    pub captures: Vec<Option<String>>,
+    /// The text of each named capture group.
+    pub groups: BTreeMap<String, Option<String>>,
+}
+
+impl Match {
+    /// Returns the matched text for `index` 0, or the text of a capture
+    /// group.
+    pub fn get(&self, index: usize) -> Option<&str> {
+        self.captures
+            .get(index)
+            .and_then(|capture| capture.as_ref().map(|s| &**s))
+    }
+
+    /// Returns the text of a named capture group.
+    pub fn name(&self, name: &str) -> Option<&str> {
+        self.groups
+            .get(name)
+            .and_then(|capture| capture.as_ref().map(|s| &**s))
+    }
+}
+
+/// A rooted `RegExp`, or a wrapper for one.
+#[derive(Clone, Copy)]
+pub struct JsRegExp<'a> {
+    handle: HandleObject<'a>,
+}
+
+impl<'a> JsRegExp<'a> {
+    /// Creates a new `RegExp`, as `new RegExp(pattern, flags)` does, stored
+    /// in `root`.
+    ///
+    /// # Failures
+    ///
+    /// Returns `Err` with a pending `SyntaxError` if the pattern is invalid,
+    /// and on JSAPI failure with a pending exception.
+    pub unsafe fn new(
+        cx: *mut JSContext,
+        pattern: &str,
+        flags: Flags,
+        mut root: MutableHandleObject<'a>,
+    ) -> Result<JsRegExp<'a>, ()> {
+        let pattern: Vec<u16> = pattern.encode_utf16().collect();
+        root.set(NewUCRegExpObject(
+            cx,
+            pattern.as_ptr(),
+            pattern.len(),
+            flags.to_raw(),
+        ));
+        if root.is_null() {
+            return Err(());
+        }
+        Ok(JsRegExp {
+            handle: Handle::from_raw(root.into()),
+        })
+    }
+
+    /// Checks the syntax of a pattern, without creating a `RegExp`.
+    ///
+    /// # Failures
+    ///
+    /// Returns `Err` on JSAPI failure, with a pending exception.
+    pub unsafe fn check_syntax(
+        cx: *mut JSContext,
+        pattern: &str,
+        flags: Flags,
+    ) -> Result<Result<(), SyntaxError>, ()> {
+        let pattern: Vec<u16> = pattern.encode_utf16().collect();
+        rooted!(in(cx) let mut error = UndefinedValue());
+        if !CheckRegExpSyntax(
+            cx,
+            pattern.as_ptr(),
+            pattern.len(),
+            flags.to_raw(),
+            error.handle_mut().into(),
+        ) {
+            return Err(());
+        }
+        if error.is_undefined() {
+            return Ok(Ok(()));
+        }
+        rooted!(in(cx) let error = error.to_object());
+        let message = match Object::new(error.handle()).get::<String, _>(cx, "message", ())? {
+            ConversionResult::Success(message) => message,
+            ConversionResult::Failure(message) => message.into_owned(),
+        };
+        Ok(Err(SyntaxError { message }))
+    }
+
+    /// Wraps a rooted object that is a `RegExp`, or a wrapper for one.
+    ///
+    /// # Failures
+    ///
+    /// Throws a `TypeError` and returns `Err` if the object is not a
+    /// `RegExp`, and returns `Err` on JSAPI failure, with a pending
+    /// exception.
+    pub unsafe fn from_handle(
+        cx: *mut JSContext,
+        handle: HandleObject<'a>,
+    ) -> Result<JsRegExp<'a>, ()> {
+        assert!(!handle.is_null());
+        let mut is_regexp = false;
+        if !ObjectIsRegExp(cx, handle.into(), &mut is_regexp) {
+            return Err(());
+        }
+        if !is_regexp {
+            throw_type_error(cx, "object is not a RegExp");
+            return Err(());
+        }
+        Ok(JsRegExp { handle })
+    }
+
+    /// Returns whether `value` is a `RegExp`, or a wrapper for one.
+    ///
+    /// # Failures
+    ///
+    /// Returns `Err` on JSAPI failure, with a pending exception.
+    pub unsafe fn is_regexp(cx: *mut JSContext, value: HandleValue) -> Result<bool, ()> {
+        if !value.is_object() {
+            return Ok(false);
+        }
+        rooted!(in(cx) let obj = value.to_object());
+        let mut is_regexp = false;
+        if ObjectIsRegExp(cx, obj.handle().into(), &mut is_regexp) {
+            Ok(is_regexp)
+        } else {
+            Err(())
+        }
+    }
+
+    /// Returns the root holding the regexp.
+    pub fn handle(&self) -> HandleObject<'a> {
+        self.handle
+    }
+
+    /// Returns the regexp.
+    pub fn get_object(&self) -> *mut JSObject {
+        *self.handle
+    }
+
+    /// Returns the flags of the regexp.
+    pub unsafe fn flags(&self, cx: *mut JSContext) -> Flags {
+        Flags::from_raw(GetRegExpFlags(cx, self.handle.into()))
+    }
+
+    /// Returns the pattern of the regexp, as `RegExp.prototype.source`
+    /// does.
+    ///
+    /// # Failures
+    ///
+    /// Returns `Err` on JSAPI failure, with a pending exception.
+    pub unsafe fn source(&self, cx: *mut JSContext) -> Result<String, ()> {
+        let source = GetRegExpSource(cx, self.handle.into());
+        if source.is_null() {
+            return Err(());
+        }
+        Ok(jsstr_to_string(cx, source))
+    }
+
+    /// Returns whether the regexp matches `input` at or after `start`, an
+    /// index in UTF-16 code units. Unlike `RegExp.prototype.test`, this
+    /// ignores and does not update `lastIndex`.
+    ///
+    /// # Failures
+    ///
+    /// Returns `Err` on JSAPI failure, with a pending exception.
+    pub unsafe fn test(&self, cx: *mut JSContext, input: &str, start: usize) -> Result<bool, ()> {
+        rooted!(in(cx) let mut result = UndefinedValue());
+        self.execute(cx, input, start, true, result.handle_mut())?;
+        Ok(result.is_boolean() && result.to_boolean())
+    }
+
+    /// Matches the regexp against `input` at or after `start`, an index in
+    /// UTF-16 code units, and returns the match if there is one. Unlike
+    /// `RegExp.prototype.exec`, this ignores and does not update
+    /// `lastIndex`.
+    ///
+    /// # Failures
+    ///
+    /// Returns `Err` on JSAPI failure, with a pending exception.
+    pub unsafe fn exec(
+        &self,
+        cx: *mut JSContext,
+        input: &str,
+        start: usize,
+    ) -> Result<Option<Match>, ()> {
+        rooted!(in(cx) let mut result = UndefinedValue());
+        self.execute(cx, input, start, false, result.handle_mut())?;
+        if !result.is_object() {
+            return Ok(None);
+        }
+
+        rooted!(in(cx) let result = result.to_object());
+        let array = JsArray::from_handle(cx, result.handle())?;
+        let mut captures = vec![];
+        for capture in array.iter::<Option<String>>(cx, ())? {
+            captures.push(success(capture?));
+        }
+
+        let result = Object::new(result.handle());
+        let index = result.get::<u32, _>(cx, "index", ConversionBehavior::Default)?;
+        let mut groups = BTreeMap::new();
+        rooted!(in(cx) let mut named = UndefinedValue());
+        result.get_value(cx, "groups", named.handle_mut())?;
+        if named.is_object() {
+            rooted!(in(cx) let named = named.to_object());
+            let named = Object::new(named.handle());
+            for key in named.keys(cx, KeyFlags::default())? {
+                let key = key?;
+                let capture = named.get::<Option<String>, _>(cx, key.clone(), ())?;
+                if let PropertyKey::String(name) = key {
+                    groups.insert(name, success(capture));
+                }
+            }
+        }
+
+        Ok(Some(Match {
+            index: success(index) as usize,
+            captures,
+            groups,
+        }))
+    }
+
+    unsafe fn execute(
+        &self,
+        cx: *mut JSContext,
+        input: &str,
+        start: usize,
+        test: bool,
+        rval: MutableHandleValue,
+    ) -> Result<(), ()> {
+        let input: Vec<u16> = input.encode_utf16().collect();
+        if start > input.len() {
+            // There is nothing to match, as when `lastIndex` is too large.
+            return Ok(());
+        }
+        // ExecuteRegExpNoStatics only accepts the RegExp itself, so unwrap
+        // it and match in its realm.
+        rooted!(in(cx) let target = UnwrapObjectStatic(self.handle.get()));
+        if target.is_null() {
+            throw_type_error(cx, "permission denied to access the RegExp");
+            return Err(());
+        }
+        let mut index = start;
+        {
+            let _ar = JSAutoRealm::new(cx, target.get());
+            if !ExecuteRegExpNoStatics(
+                cx,
+                target.handle().into(),
+                input.as_ptr(),
+                input.len(),
+                &mut index,
+                test,
+                rval.into(),
+            ) {
+                return Err(());
+            }
+        }
+        if JS_WrapValue(cx, rval.into()) {
+            Ok(())
+        } else {
+            Err(())
+        }
+    }
+}
+
+/// Unwraps the result of a conversion that cannot fail, because the match
+/// result was created by the engine.
+fn success<T>(result: ConversionResult<T>) -> T {
+    match result {
+        ConversionResult::Success(value) => value,
+        ConversionResult::Failure(e) => panic!("unexpected match result: {}", e),
+    }
+}
@@ -26,9 +26,10 @@ use std::sync::{Arc, Mutex};
 use std::u32;
 
 use consts::{JSCLASS_GLOBAL_SLOT_COUNT, JSCLASS_RESERVED_SLOTS_MASK};
-use consts::{JSCLASS_IS_DOMJSCLASS, JSCLASS_IS_GLOBAL};
+use consts::{JSCLASS_IS_DOMJSCLASS, JSCLASS_IS_GLOBAL, JSCLASS_IS_PROXY};
 
 use conversions::jsstr_to_string;
+use conversions::{ConversionResult, FromJSValConvertible, ToJSValConvertible};
 
 use jsapi;
 use jsapi::glue::{DeleteRealmOptions, JS_Init, JS_NewRealmOptions};
@@ -36,6 +37,7 @@ use jsapi::mozilla::Utf8Unit;
 use jsapi::Handle as RawHandle;
 use jsapi::HandleObjectVector as RawHandleObjectVector;
 use jsapi::HandleValue as RawHandleValue;
+use jsapi::JS_SetReservedSlot;
 use jsapi::MutableHandle as RawMutableHandle;
 use jsapi::MutableHandleIdVector as RawMutableHandleIdVector;
 use jsapi::JS::RegExpFlags;
@@ -47,7 +49,9 @@ use jsapi::{InitSelfHostedCode, IsWindowSlow};
 use jsapi::{JSAutoRealm, JS_SetGCParameter, JS_SetNativeStackQuota, JS_WrapValue};
 use jsapi::{JSClass, JSClassOps, JSContext, Realm, JSCLASS_RESERVED_SLOTS_SHIFT};
 use jsapi::{JSErrorReport, JSFunction, JSFunctionSpec, JSGCParamKey};
-use jsapi::{JSObject, JSPropertySpec, JSRuntime, JSScript};
+use jsapi::{JSNative, JSNativeWrapper, JSPropertySpec_Accessor, JSPropertySpec_Kind};
+use jsapi::{JSObject, JSPropertySpec, JSPropertySpec_Name, JSRuntime, JSScript};
+use jsapi::{JSPropertySpec_AccessorsOrValue, JSPropertySpec_AccessorsOrValue_Accessors};
 use jsapi::{JSString, JSTracer, Object, PersistentRootedIdVector};
 use jsapi::{JS_DefineFunctions, JS_DefineProperties, JS_DestroyContext, JS_ShutDown};
 use jsapi::{JS_EnumerateStandardClasses, JS_GetRuntime, JS_GlobalObjectTraceHook};
@@ -58,7 +62,7 @@ use jsapi::{SetWarningReporter, SourceText, Symbol, ToBooleanSlow};
 use jsapi::{ToInt32Slow, ToInt64Slow, ToNumberSlow, ToStringSlow, ToUint16Slow};
 use jsapi::{ToUint32Slow, ToUint64Slow, ToWindowProxyIfWindowSlow};
 
-use jsval::ObjectValue;
+use jsval::{ObjectValue, PrivateValue, UndefinedValue};
 
 use glue::{AppendToRootedObjectVector, CallFunctionTracer, CallIdTracer, CallObjectRootTracer};
 use glue::{CallObjectTracer, CallScriptTracer, CallStringTracer, CallValueRootTracer};
@@ -67,8 +71,11 @@ use glue::{
     DeleteCompileOptions, DeleteRootedObjectVector, DescribeScriptedCaller, DestroyRootedIdVector,
 };
 use glue::{GetIdVectorAddress, GetObjectVectorAddress, NewCompileOptions, SliceRootedIdVector};
+use glue::{GetProxyReservedSlot, JS_GetReservedSlot, SetProxyReservedSlot};
 
 use panic::maybe_resume_unwind;
+use property::forget_interned_names;
+use symbol::SymbolCode;
 
 use default_heapsize;
 
@@ -424,6 +431,7 @@ impl Drop for Runtime {
             "This runtime still has live children."
         );
         unsafe {
+            forget_interned_names(JS_GetRuntime(self.cx));
             JS_DestroyContext(self.cx);
 
             CONTEXT.with(|context| {
@@ -1188,6 +1196,185 @@ pub unsafe fn define_properties(
     JS_DefineProperties(cx, obj.into(), properties.as_ptr()).to_result()
 }
 
+/// An owned, zero-terminated array of `JSFunctionSpec`s built at runtime,
+/// for use when the set of methods is not known statically.
+///
+/// The builder keeps the method names alive for as long as the specs exist.
+pub struct FunctionSpecs {
+    names: Vec<ffi::CString>,
+    specs: Vec<JSFunctionSpec>,
+}
+
+impl FunctionSpecs {
+    /// Creates an empty list of function specs.
+    pub fn new() -> FunctionSpecs {
+        FunctionSpecs {
+            names: vec![],
+            specs: vec![JSFunctionSpec::ZERO],
+        }
+    }
+
+    /// Appends a method named `name`, implemented by `native`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `name` contains a NUL byte.
+    pub fn method(mut self, name: &str, native: JSNative, nargs: u16, flags: u16) -> Self {
+        let name = ffi::CString::new(name).unwrap();
+        let spec = JSFunctionSpec {
+            name: JSPropertySpec_Name {
+                string_: name.as_ptr(),
+            },
+            call: JSNativeWrapper {
+                op: native,
+                info: ptr::null(),
+            },
+            nargs,
+            flags,
+            selfHostedName: ptr::null(),
+        };
+        // The terminator always stays last.
+        let last = self.specs.len() - 1;
+        self.specs.insert(last, spec);
+        self.names.push(name);
+        self
+    }
+
+    /// Appends a method keyed by a well-known symbol, such as
+    /// `[Symbol.iterator]`, implemented by `native`.
+    pub fn symbol_method(
+        mut self,
+        code: SymbolCode,
+        native: JSNative,
+        nargs: u16,
+        flags: u16,
+    ) -> Self {
+        let spec = JSFunctionSpec {
+            name: JSPropertySpec_Name {
+                symbol_: code.to_raw() as usize + 1,
+            },
+            call: JSNativeWrapper {
+                op: native,
+                info: ptr::null(),
+            },
+            nargs,
+            flags,
+            selfHostedName: ptr::null(),
+        };
+        let last = self.specs.len() - 1;
+        self.specs.insert(last, spec);
+        self
+    }
+
+    /// Returns the specs, including the zeroed terminator.
+    pub fn as_slice(&self) -> &[JSFunctionSpec] {
+        &self.specs
+    }
+
+    /// Defines all methods on `obj`.
+    ///
+    /// # Failures
+    ///
+    /// Returns `Err` on JSAPI failure.
+    ///
+    /// # Safety
+    ///
+    /// - `cx` must be valid.
+    /// - This function calls into unaudited C++ code.
+    pub unsafe fn define(&self, cx: *mut JSContext, obj: HandleObject) -> Result<(), ()> {
+        JS_DefineFunctions(cx, obj.into(), self.specs.as_ptr()).to_result()
+    }
+}
+
+impl Default for FunctionSpecs {
+    fn default() -> FunctionSpecs {
+        FunctionSpecs::new()
+    }
+}
+
+/// An owned, zero-terminated array of `JSPropertySpec`s built at runtime,
+/// for use when the set of accessors is not known statically.
+///
+/// The builder keeps the property names alive for as long as the specs exist.
+pub struct PropertySpecs {
+    names: Vec<ffi::CString>,
+    specs: Vec<JSPropertySpec>,
+}
+
+impl PropertySpecs {
+    /// Creates an empty list of property specs.
+    pub fn new() -> PropertySpecs {
+        PropertySpecs {
+            names: vec![],
+            specs: vec![JSPropertySpec::ZERO],
+        }
+    }
+
+    /// Appends an accessor property named `name`, backed by the given native
+    /// getter and setter. `attrs` is a combination of `JSPROP_ENUMERATE` and
+    /// `JSPROP_PERMANENT`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `name` contains a NUL byte.
+    pub fn accessor(mut self, name: &str, getter: JSNative, setter: JSNative, attrs: u8) -> Self {
+        let name = ffi::CString::new(name).unwrap();
+        let spec = JSPropertySpec {
+            name: JSPropertySpec_Name {
+                string_: name.as_ptr(),
+            },
+            attributes_: attrs,
+            kind_: JSPropertySpec_Kind::NativeAccessor,
+            u: JSPropertySpec_AccessorsOrValue {
+                accessors: JSPropertySpec_AccessorsOrValue_Accessors {
+                    getter: JSPropertySpec_Accessor {
+                        native: JSNativeWrapper {
+                            op: getter,
+                            info: ptr::null(),
+                        },
+                    },
+                    setter: JSPropertySpec_Accessor {
+                        native: JSNativeWrapper {
+                            op: setter,
+                            info: ptr::null(),
+                        },
+                    },
+                },
+            },
+        };
+        // The terminator always stays last.
+        let last = self.specs.len() - 1;
+        self.specs.insert(last, spec);
+        self.names.push(name);
+        self
+    }
+
+    /// Returns the specs, including the zeroed terminator.
+    pub fn as_slice(&self) -> &[JSPropertySpec] {
+        &self.specs
+    }
+
+    /// Defines all properties on `obj`.
+    ///
+    /// # Failures
+    ///
+    /// Returns `Err` on JSAPI failure.
+    ///
+    /// # Safety
+    ///
+    /// - `cx` must be valid.
+    /// - This function calls into unaudited C++ code.
+    pub unsafe fn define(&self, cx: *mut JSContext, obj: HandleObject) -> Result<(), ()> {
+        JS_DefineProperties(cx, obj.into(), self.specs.as_ptr()).to_result()
+    }
+}
+
+impl Default for PropertySpecs {
+    fn default() -> PropertySpecs {
+        PropertySpecs::new()
+    }
+}
+
 static SIMPLE_GLOBAL_CLASS_OPS: JSClassOps = JSClassOps {
     addProperty: None,
     delProperty: None,
@@ -1237,6 +1424,116 @@ pub unsafe fn get_context_realm(cx: *mut JSContext) -> *mut Realm {
     (*cx).realm_
 }
 
+/// Returns the number of reserved slots of objects of `class`.
+#[inline]
+pub fn reserved_slot_count(class: &JSClass) -> u32 {
+    (class.flags >> JSCLASS_RESERVED_SLOTS_SHIFT) & JSCLASS_RESERVED_SLOTS_MASK
+}
+
+/// Panics if `obj` has no reserved slot `index`, and returns whether `obj`
+/// is a proxy, whose reserved slots are stored separately.
+unsafe fn check_reserved_slot(obj: *mut JSObject, index: u32) -> bool {
+    let class = &*get_object_class(obj);
+    let count = reserved_slot_count(class);
+    assert!(
+        index < count,
+        "reserved slot {} out of range for class {:?} with {} slots",
+        index,
+        CStr::from_ptr(class.name),
+        count
+    );
+    class.flags & JSCLASS_IS_PROXY != 0
+}
+
+unsafe fn read_reserved_slot(obj: *mut JSObject, index: u32) -> Value {
+    let mut value = UndefinedValue();
+    if check_reserved_slot(obj, index) {
+        GetProxyReservedSlot(obj, index, &mut value);
+    } else {
+        JS_GetReservedSlot(obj, index, &mut value);
+    }
+    value
+}
+
+unsafe fn write_reserved_slot(obj: *mut JSObject, index: u32, value: &Value) {
+    if check_reserved_slot(obj, index) {
+        SetProxyReservedSlot(obj, index, value);
+    } else {
+        JS_SetReservedSlot(obj, index, value);
+    }
+}
+
+/// Reads reserved slot `index` of `obj` and converts it to `T`.
+///
+/// # Panics
+///
+/// Panics if the class of `obj` has no reserved slot `index`.
+///
+/// # Failures
+///
+/// Returns `Err` on JSAPI failure, with a pending exception.
+pub unsafe fn get_reserved_slot<T: FromJSValConvertible>(
+    cx: *mut JSContext,
+    obj: HandleObject,
+    index: u32,
+    option: T::Config,
+) -> Result<ConversionResult<T>, ()> {
+    rooted!(in(cx) let value = read_reserved_slot(obj.get(), index));
+    T::from_jsval(cx, value.handle(), option)
+}
+
+/// Converts `value` to a JS value and stores it in reserved slot `index` of
+/// `obj`.
+///
+/// # Panics
+///
+/// Panics if the class of `obj` has no reserved slot `index`.
+pub unsafe fn set_reserved_slot<T: ToJSValConvertible + ?Sized>(
+    cx: *mut JSContext,
+    obj: HandleObject,
+    index: u32,
+    value: &T,
+) {
+    rooted!(in(cx) let mut jsval = UndefinedValue());
+    value.to_jsval(cx, jsval.handle_mut());
+    write_reserved_slot(obj.get(), index, &jsval);
+}
+
+/// Stores a Rust pointer in reserved slot `index` of `obj`, as a
+/// `PrivateValue`. The pointer must be at least 2-byte aligned.
+///
+/// # Panics
+///
+/// Panics if the class of `obj` has no reserved slot `index`, or, in debug
+/// builds, if `ptr` is misaligned.
+pub unsafe fn set_reserved_slot_private<T>(obj: *mut JSObject, index: u32, ptr: *const T) {
+    debug_assert!(
+        ptr as usize & 1 == 0,
+        "private pointers must be 2-byte aligned"
+    );
+    write_reserved_slot(obj, index, &PrivateValue(ptr as *const c_void));
+}
+
+/// Returns the Rust pointer stored in reserved slot `index` of `obj` by
+/// `set_reserved_slot_private`, or null if the slot was never set.
+///
+/// # Panics
+///
+/// Panics if the class of `obj` has no reserved slot `index`, or, in debug
+/// builds, if the slot holds something other than a private pointer.
+pub unsafe fn get_reserved_slot_private<T>(obj: *mut JSObject, index: u32) -> *mut T {
+    let value = read_reserved_slot(obj, index);
+    if value.is_undefined() {
+        return ptr::null_mut();
+    }
+    debug_assert!(
+        value.is_double(),
+        "reserved slot {} does not hold a private pointer",
+        index
+    );
+    value.to_private() as *mut T
+}
+
 #[inline]
 pub fn is_dom_class(class: &JSClass) -> bool {
     class.flags & JSCLASS_IS_DOMJSCLASS != 0
@@ -1491,6 +1788,7 @@ pub mod wrappers {
     use jsapi;
     use jsapi::jsid;
     use jsapi::mozilla::Utf8Unit;
+    use jsapi::BigInt;
     use jsapi::CallArgs;
     use jsapi::CloneDataPolicy;
     use jsapi::CompartmentTransplantCallback;
@@ -1637,6 +1935,7 @@ pub mod jsapi_wrapped {
     use glue;
     use jsapi;
     use jsapi::mozilla::Utf8Unit;
+    use jsapi::BigInt;
     use jsapi::CallArgs;
     use jsapi::CloneDataPolicy;
     use jsapi::CompartmentTransplantCallback;
@@ -0,0 +1,572 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Async iteration between JS and `futures` streams.
+//!
+//! `AsyncForOfIter` consumes a JS async iterable, such as an async generator,
+//! as a `Stream`, the way `for await` does. Like `for await`, it also accepts
+//! sync iterables and awaits each of their elements:
+//!
+//! ```ignore
+//! let mut lines = AsyncForOfIter::<String>::new(cx, iterable, ())?;
+//! while let Some(line) = lines.next().await {
+//!     ...
+//! }
+//! ```
+//!
+//! In the other direction, `new_async_iterator` exposes a Rust `Stream` to JS
+//! as an async iterator object, which is polled on a local executor:
+//!
+//! ```ignore
+//! rooted!(in(cx) let pages = new_async_iterator(cx, pager, pool.spawner()));
+//! ```
+//!
+//! Both directions make progress only while the embedder runs promise jobs,
+//! and everything must happen on the runtime's thread before the runtime is
+//! destroyed.
+
+#![deny(missing_docs)]
+
+use closure::{new_function_from_closure, NativeClosure};
+use conversions::{ConversionResult, FromJSValConvertible, ToJSValConvertible};
+use error::{throw_internal_error, throw_type_error, JSError};
+use function::call_value;
+use futures::future::poll_fn;
+use futures::stream::{Stream, StreamExt};
+use futures::task::{Context, LocalSpawn, LocalSpawnExt, Poll, Waker};
+use glue::{CreatePersistentRootedObject, DeletePersistentRootedObject, GetPersistentRootedObject};
+use glue::{CreatePersistentRootedValue, DeletePersistentRootedValue, GetPersistentRootedValue};
+use iterator::{call_return, iter_result_value, new_iter_result};
+use jsapi::{AddPromiseReactions, CallOriginalPromiseResolve, NewPromiseObject};
+use jsapi::{CallArgs, JSAutoRealm, JSContext, JSObject, Value};
+use jsapi::{ExceptionStackBehavior, PersistentRootedObject, PersistentRootedValue};
+use jsapi::{JS_ClearPendingException, JS_GetPendingException, JS_IsExceptionPending};
+use jsapi::{JS_SetPendingException, RejectPromise, ResolvePromise};
+use jsval::{ObjectValue, UndefinedValue};
+use object::Object;
+use owned::{get_object_data, new_object_with_data};
+use panic::wrap_panic;
+use rust::{FunctionSpecs, Handle, HandleObject, HandleValue};
+use symbol::{well_known_symbol, Symbol, SymbolCode};
+
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::ptr;
+use std::rc::{Rc, Weak};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum State {
+    /// The stream may yield more elements.
+    Running,
+    /// An element failed to convert; the JS iterator still has to be closed.
+    Failed,
+    /// The JS iterator is exhausted, rejected, or was closed.
+    Done,
+}
+
+/// The settled value of the promise returned by a `next()` call.
+struct Settled {
+    fulfilled: bool,
+    value: *mut PersistentRootedValue,
+}
+
+impl Drop for Settled {
+    fn drop(&mut self) {
+        unsafe { DeletePersistentRootedValue(self.value) }
+    }
+}
+
+/// A `next()` call whose promise has not been consumed yet.
+struct Request {
+    settled: RefCell<Option<Settled>>,
+    waker: RefCell<Option<Waker>>,
+    /// For a sync iterator, whether its result was done. The promise then
+    /// settles with the awaited element rather than an iterator result.
+    done: Option<bool>,
+}
+
+/// Returns a promise reaction that records the settled value in `request`,
+/// unless the stream has been dropped since.
+fn reaction(request: Weak<Request>, fulfilled: bool) -> Box<NativeClosure> {
+    Box::new(move |cx: *mut JSContext, args: &CallArgs| {
+        if let Some(request) = request.upgrade() {
+            let value = unsafe { Handle::from_raw(args.get(0)) };
+            let value = unsafe { CreatePersistentRootedValue(cx, &*value) };
+            *request.settled.borrow_mut() = Some(Settled { fulfilled, value });
+            if let Some(waker) = request.waker.borrow_mut().take() {
+                waker.wake();
+            }
+        }
+        args.rval().set(UndefinedValue());
+        Ok(())
+    })
+}
+
+/// A `Stream` over the elements of a JS async iterable, converted to `T`.
+///
+/// The JS iterator is kept alive through persistent roots. `cx` must be in a
+/// realm whenever the stream is polled or dropped.
+///
+/// Yields `Err(JSError::Exception)` if the iteration throws or a promise
+/// rejects, with the exception pending on `cx`, and
+/// `Err(JSError::Conversion)` if an element does not convert; either ends the
+/// stream. Dropping the stream early calls the iterator's `return()` method.
+pub struct AsyncForOfIter<T: FromJSValConvertible> {
+    cx: *mut JSContext,
+    iterator: *mut PersistentRootedObject,
+    next_method: *mut PersistentRootedObject,
+    request: Option<Rc<Request>>,
+    /// Whether the iterator is a sync one, from `[Symbol.iterator]`.
+    sync: bool,
+    state: State,
+    option: T::Config,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T: FromJSValConvertible> AsyncForOfIter<T> {
+    /// Starts iterating `iterable` through its `[Symbol.asyncIterator]`
+    /// method or, if it has none, through its `[Symbol.iterator]` method,
+    /// awaiting each element as `CreateAsyncFromSyncIterator` does.
+    ///
+    /// # Failures
+    ///
+    /// Throws a `TypeError` and returns `Err` if the value is neither async
+    /// nor sync iterable, and returns `Err` if getting the iterator throws.
+    pub unsafe fn new(
+        cx: *mut JSContext,
+        iterable: HandleValue,
+        option: T::Config,
+    ) -> Result<AsyncForOfIter<T>, JSError> {
+        if !iterable.is_object() {
+            throw_type_error(cx, "value is not async iterable");
+            return Err(JSError::Exception);
+        }
+        rooted!(in(cx) let obj = iterable.to_object());
+        rooted!(in(cx) let mut method = UndefinedValue());
+        let mut sync = false;
+        for &code in &[SymbolCode::AsyncIterator, SymbolCode::Iterator] {
+            rooted!(in(cx) let symbol = well_known_symbol(cx, code));
+            let key = Symbol::new(symbol.handle()).to_property_key();
+            Object::new(obj.handle()).get_value(cx, key, method.handle_mut())?;
+            if !method.is_null_or_undefined() {
+                break;
+            }
+            sync = true;
+        }
+        if method.is_null_or_undefined() {
+            throw_type_error(cx, "value is not async iterable");
+            return Err(JSError::Exception);
+        }
+
+        rooted!(in(cx) let mut iterator = UndefinedValue());
+        call_value(cx, iterable, method.handle(), (), iterator.handle_mut())?;
+        if !iterator.is_object() {
+            throw_type_error(cx, "async iterator is not an object");
+            return Err(JSError::Exception);
+        }
+        rooted!(in(cx) let iterator = iterator.to_object());
+        rooted!(in(cx) let mut next = UndefinedValue());
+        Object::new(iterator.handle()).get_value(cx, "next", next.handle_mut())?;
+        if !next.is_object() {
+            throw_type_error(cx, "iterator's next method is not callable");
+            return Err(JSError::Exception);
+        }
+
+        Ok(AsyncForOfIter {
+            cx,
+            iterator: CreatePersistentRootedObject(cx, iterator.get()),
+            next_method: CreatePersistentRootedObject(cx, next.to_object()),
+            request: None,
+            sync,
+            state: State::Running,
+            option,
+            marker: PhantomData,
+        })
+    }
+
+    /// Calls `next()` and subscribes to the promise it returns.
+    unsafe fn send_request(&mut self) -> Result<(), ()> {
+        let cx = self.cx;
+        rooted!(in(cx) let this = ObjectValue(GetPersistentRootedObject(self.iterator)));
+        rooted!(in(cx) let next = ObjectValue(GetPersistentRootedObject(self.next_method)));
+        rooted!(in(cx) let mut result = UndefinedValue());
+        call_value(cx, this.handle(), next.handle(), (), result.handle_mut())?;
+        let mut done = None;
+        if self.sync {
+            // Await the element rather than the iterator result.
+            rooted!(in(cx) let mut value = UndefinedValue());
+            done = Some(!iter_result_value(cx, result.handle(), value.handle_mut())?);
+            result.set(value.get());
+        }
+        rooted!(in(cx) let promise = CallOriginalPromiseResolve(cx, result.handle().into()));
+        if promise.is_null() {
+            return Err(());
+        }
+
+        let request = Rc::new(Request {
+            settled: RefCell::new(None),
+            waker: RefCell::new(None),
+            done,
+        });
+        let on_fulfilled = reaction(Rc::downgrade(&request), true);
+        rooted!(in(cx) let on_fulfilled = new_function_from_closure(cx, "", 1, on_fulfilled));
+        if on_fulfilled.is_null() {
+            return Err(());
+        }
+        let on_rejected = reaction(Rc::downgrade(&request), false);
+        rooted!(in(cx) let on_rejected = new_function_from_closure(cx, "", 1, on_rejected));
+        if on_rejected.is_null() {
+            return Err(());
+        }
+        if !AddPromiseReactions(
+            cx,
+            promise.handle().into(),
+            on_fulfilled.handle().into(),
+            on_rejected.handle().into(),
+        ) {
+            return Err(());
+        }
+        self.request = Some(request);
+        Ok(())
+    }
+
+    unsafe fn poll_step(&mut self, task_cx: &mut Context) -> Poll<Option<Result<T, JSError>>>
+    where
+        T::Config: Clone,
+    {
+        let cx = self.cx;
+        if self.request.is_none() && self.send_request().is_err() {
+            self.state = State::Done;
+            return Poll::Ready(Some(Err(JSError::Exception)));
+        }
+
+        let (settled, done) = {
+            let request = self.request.as_ref().unwrap();
+            let settled = request.settled.borrow_mut().take();
+            match settled {
+                Some(settled) => (settled, request.done),
+                None => {
+                    *request.waker.borrow_mut() = Some(task_cx.waker().clone());
+                    return Poll::Pending;
+                }
+            }
+        };
+        self.request = None;
+
+        rooted!(in(cx) let mut settled_value = UndefinedValue());
+        GetPersistentRootedValue(settled.value, &mut *settled_value.handle_mut());
+        if !settled.fulfilled {
+            JS_SetPendingException(
+                cx,
+                settled_value.handle().into(),
+                ExceptionStackBehavior::DoNotCapture,
+            );
+            self.state = State::Done;
+            return Poll::Ready(Some(Err(JSError::Exception)));
+        }
+        drop(settled);
+
+        rooted!(in(cx) let mut value = UndefinedValue());
+        let has_value = match done {
+            Some(done) => {
+                value.set(settled_value.get());
+                Ok(!done)
+            }
+            None => iter_result_value(cx, settled_value.handle(), value.handle_mut()),
+        };
+        match has_value {
+            Ok(true) => {}
+            Ok(false) => {
+                self.state = State::Done;
+                return Poll::Ready(None);
+            }
+            Err(()) => {
+                self.state = State::Done;
+                return Poll::Ready(Some(Err(JSError::Exception)));
+            }
+        }
+        Poll::Ready(Some(
+            match T::from_jsval(cx, value.handle(), self.option.clone()) {
+                Ok(ConversionResult::Success(value)) => Ok(value),
+                Ok(ConversionResult::Failure(message)) => {
+                    self.state = State::Failed;
+                    Err(JSError::Conversion(message))
+                }
+                Err(()) => {
+                    self.state = State::Failed;
+                    Err(JSError::Exception)
+                }
+            },
+        ))
+    }
+}
+
+// The fields are never pinned.
+impl<T: FromJSValConvertible> Unpin for AsyncForOfIter<T> {}
+
+impl<T: FromJSValConvertible> Stream for AsyncForOfIter<T>
+where
+    T::Config: Clone,
+{
+    type Item = Result<T, JSError>;
+
+    fn poll_next(self: Pin<&mut Self>, task_cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.state != State::Running {
+            return Poll::Ready(None);
+        }
+        unsafe { this.poll_step(task_cx) }
+    }
+}
+
+impl<T: FromJSValConvertible> Drop for AsyncForOfIter<T> {
+    fn drop(&mut self) {
+        let cx = self.cx;
+        unsafe {
+            if self.state != State::Done && !JS_IsExceptionPending(cx) {
+                rooted!(in(cx) let iterator = GetPersistentRootedObject(self.iterator));
+                if call_return(cx, iterator.handle()).is_err() {
+                    // There is nobody to report the exception to.
+                    JS_ClearPendingException(cx);
+                }
+            }
+            DeletePersistentRootedObject(self.iterator);
+            DeletePersistentRootedObject(self.next_method);
+        }
+    }
+}
+
+/// The type-erased state of an async iterator object created by
+/// `new_async_iterator`.
+trait AsyncSource {
+    /// Queues `promise` to be resolved with the next element.
+    unsafe fn next(self: Rc<Self>, cx: *mut JSContext, promise: HandleObject) -> Result<(), ()>;
+
+    /// Drops the stream. Queued promises resolve as done.
+    unsafe fn close(&self, cx: *mut JSContext) -> Result<(), ()>;
+}
+
+struct StreamSource<S: Stream> {
+    cx: *mut JSContext,
+    stream: RefCell<Option<S>>,
+    /// Promises returned by `next()`, in call order.
+    pending: RefCell<VecDeque<*mut PersistentRootedObject>>,
+    /// Whether a task polling the stream is spawned.
+    driving: Cell<bool>,
+    spawner: Box<dyn LocalSpawn>,
+}
+
+impl<S> StreamSource<S>
+where
+    S: Stream + Unpin,
+    S::Item: ToJSValConvertible,
+{
+    /// Resolves the queued promises in order, as the stream yields elements.
+    fn poll_drive(&self, task_cx: &mut Context) -> Poll<()> {
+        loop {
+            let promise = match self.pending.borrow().front() {
+                Some(&promise) => promise,
+                None => {
+                    self.driving.set(false);
+                    return Poll::Ready(());
+                }
+            };
+            let item = match *self.stream.borrow_mut() {
+                Some(ref mut stream) => match stream.poll_next_unpin(task_cx) {
+                    Poll::Ready(item) => item,
+                    Poll::Pending => return Poll::Pending,
+                },
+                None => None,
+            };
+            if item.is_none() {
+                *self.stream.borrow_mut() = None;
+            }
+            self.pending.borrow_mut().pop_front();
+            unsafe { self.settle(promise, item) };
+        }
+    }
+
+    /// Resolves `promise` with an iterator result for `item`, and deletes
+    /// its root.
+    unsafe fn settle(&self, promise: *mut PersistentRootedObject, item: Option<S::Item>) {
+        let cx = self.cx;
+        rooted!(in(cx) let promise_obj = GetPersistentRootedObject(promise));
+        DeletePersistentRootedObject(promise);
+        let _ar = JSAutoRealm::new(cx, promise_obj.get());
+
+        rooted!(in(cx) let mut value = UndefinedValue());
+        if let Some(ref item) = item {
+            item.to_jsval(cx, value.handle_mut());
+        }
+        rooted!(in(cx) let result = new_iter_result(cx, value.handle(), item.is_none()));
+        if !result.is_null() {
+            rooted!(in(cx) let result = ObjectValue(result.get()));
+            if ResolvePromise(cx, promise_obj.handle().into(), result.handle().into()) {
+                return;
+            }
+        }
+
+        rooted!(in(cx) let mut exception = UndefinedValue());
+        if JS_GetPendingException(cx, exception.handle_mut().into()) {
+            JS_ClearPendingException(cx);
+            if !RejectPromise(cx, promise_obj.handle().into(), exception.handle().into()) {
+                JS_ClearPendingException(cx);
+            }
+        }
+    }
+}
+
+impl<S> AsyncSource for StreamSource<S>
+where
+    S: Stream + Unpin + 'static,
+    S::Item: ToJSValConvertible,
+{
+    unsafe fn next(self: Rc<Self>, cx: *mut JSContext, promise: HandleObject) -> Result<(), ()> {
+        self.pending
+            .borrow_mut()
+            .push_back(CreatePersistentRootedObject(cx, promise.get()));
+        if self.driving.get() {
+            return Ok(());
+        }
+        self.driving.set(true);
+        let source = self.clone();
+        let task = poll_fn(move |task_cx| source.poll_drive(task_cx));
+        if self.spawner.spawn_local(task).is_err() {
+            self.driving.set(false);
+            if let Some(promise) = self.pending.borrow_mut().pop_back() {
+                DeletePersistentRootedObject(promise);
+            }
+            throw_internal_error(cx, "the executor has shut down");
+            return Err(());
+        }
+        Ok(())
+    }
+
+    unsafe fn close(&self, cx: *mut JSContext) -> Result<(), ()> {
+        match self.stream.try_borrow_mut() {
+            Ok(mut stream) => {
+                *stream = None;
+                Ok(())
+            }
+            Err(_) => {
+                throw_type_error(cx, "stream is already running");
+                Err(())
+            }
+        }
+    }
+}
+
+impl<S: Stream> Drop for StreamSource<S> {
+    fn drop(&mut self) {
+        for promise in self.pending.borrow_mut().drain(..) {
+            unsafe { DeletePersistentRootedObject(promise) };
+        }
+    }
+}
+
+/// Creates a JS async iterator object that yields the elements of `stream`.
+///
+/// Each `next()` call returns a promise that resolves once the stream yields
+/// an element; the stream is polled by a task spawned on `spawner`, which
+/// must run on the runtime's thread. The object has a
+/// `[Symbol.asyncIterator]()` method, so it works with `for await`.
+///
+/// The stream is dropped when it ends, when JS stops iterating early through
+/// `return()`, or when the object and all pending tasks are gone.
+///
+/// Returns a null pointer on JSAPI failure.
+///
+/// # Safety
+///
+/// - `cx` must be valid and in a realm, and must outlive the spawned tasks.
+pub unsafe fn new_async_iterator<S, P>(cx: *mut JSContext, stream: S, spawner: P) -> *mut JSObject
+where
+    S: Stream + Unpin + 'static,
+    S::Item: ToJSValConvertible,
+    P: LocalSpawn + 'static,
+{
+    let source: Rc<dyn AsyncSource> = Rc::new(StreamSource {
+        cx,
+        stream: RefCell::new(Some(stream)),
+        pending: RefCell::new(VecDeque::new()),
+        driving: Cell::new(false),
+        spawner: Box::new(spawner),
+    });
+    rooted!(in(cx) let obj = new_object_with_data(cx, Box::new(source)));
+    if obj.is_null() {
+        return ptr::null_mut();
+    }
+    let specs = FunctionSpecs::new()
+        .method("next", Some(async_iterator_next), 0, 0)
+        .method("return", Some(async_iterator_return), 0, 0)
+        .symbol_method(SymbolCode::AsyncIterator, Some(async_iterator_self), 0, 0);
+    if specs.define(cx, obj.handle()).is_err() {
+        return ptr::null_mut();
+    }
+    obj.get()
+}
+
+unsafe extern "C" fn async_iterator_next(cx: *mut JSContext, argc: u32, vp: *mut Value) -> bool {
+    let args = CallArgs::from_vp(vp, argc);
+    let mut result = false;
+    wrap_panic(&mut || result = next_impl(cx, &args).is_ok());
+    result
+}
+
+unsafe extern "C" fn async_iterator_return(cx: *mut JSContext, argc: u32, vp: *mut Value) -> bool {
+    let args = CallArgs::from_vp(vp, argc);
+    let mut result = false;
+    wrap_panic(&mut || result = return_impl(cx, &args).is_ok());
+    result
+}
+
+unsafe extern "C" fn async_iterator_self(_cx: *mut JSContext, argc: u32, vp: *mut Value) -> bool {
+    let args = CallArgs::from_vp(vp, argc);
+    args.rval().set(args.thisv().get());
+    true
+}
+
+/// Returns the source of the `this` object, throwing a `TypeError` if it is
+/// not an async iterator created by `new_async_iterator`.
+unsafe fn this_source<'a>(
+    cx: *mut JSContext,
+    args: &CallArgs,
+) -> Result<&'a Rc<dyn AsyncSource>, ()> {
+    let thisv = args.thisv();
+    let source = if thisv.is_object() {
+        get_object_data::<Rc<dyn AsyncSource>>(thisv.to_object())
+    } else {
+        None
+    };
+    source.ok_or_else(|| throw_type_error(cx, "not a Rust async iterator"))
+}
+
+unsafe fn next_impl(cx: *mut JSContext, args: &CallArgs) -> Result<(), ()> {
+    let source = this_source(cx, args)?.clone();
+    rooted!(in(cx) let promise = NewPromiseObject(cx, HandleObject::null().into()));
+    if promise.is_null() {
+        return Err(());
+    }
+    source.next(cx, promise.handle())?;
+    args.rval().set(ObjectValue(promise.get()));
+    Ok(())
+}
+
+unsafe fn return_impl(cx: *mut JSContext, args: &CallArgs) -> Result<(), ()> {
+    this_source(cx, args)?.close(cx)?;
+    rooted!(in(cx) let value = UndefinedValue());
+    rooted!(in(cx) let result = new_iter_result(cx, value.handle(), true));
+    if result.is_null() {
+        return Err(());
+    }
+    rooted!(in(cx) let result = ObjectValue(result.get()));
+    rooted!(in(cx) let promise = CallOriginalPromiseResolve(cx, result.handle().into()));
+    if promise.is_null() {
+        return Err(());
+    }
+    args.rval().set(ObjectValue(promise.get()));
+    Ok(())
+}
@@ -0,0 +1,189 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Rooted symbols, the symbol registry and the well-known symbols.
+//!
+//! ```ignore
+//! rooted!(in(cx) let iterator = well_known_symbol(cx, SymbolCode::Iterator));
+//! let key = unsafe { Symbol::new(iterator.handle()).to_property_key() };
+//! ```
+
+#![deny(missing_docs)]
+
+use conversions::{jsstr_to_string, ToJSValConvertible};
+use jsapi::Symbol as RawSymbol;
+use jsapi::SymbolCode as RawSymbolCode;
+use jsapi::{GetSymbolCode, GetSymbolDescription, GetSymbolFor, GetWellKnownSymbol, NewSymbol};
+use jsapi::{JSContext, JSString};
+use jsval::{SymbolValue, UndefinedValue};
+use property::PropertyKey;
+use rust::{HandleSymbol, MutableHandleValue};
+
+use std::ptr;
+
+/// The well-known symbols, such as `Symbol.iterator`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum SymbolCode {
+    /// `Symbol.isConcatSpreadable`.
+    IsConcatSpreadable,
+    /// `Symbol.iterator`.
+    Iterator,
+    /// `Symbol.match`.
+    Match,
+    /// `Symbol.replace`.
+    Replace,
+    /// `Symbol.search`.
+    Search,
+    /// `Symbol.species`.
+    Species,
+    /// `Symbol.hasInstance`.
+    HasInstance,
+    /// `Symbol.split`.
+    Split,
+    /// `Symbol.toPrimitive`.
+    ToPrimitive,
+    /// `Symbol.toStringTag`.
+    ToStringTag,
+    /// `Symbol.unscopables`.
+    Unscopables,
+    /// `Symbol.asyncIterator`.
+    AsyncIterator,
+    /// `Symbol.matchAll`.
+    MatchAll,
+}
+
+impl SymbolCode {
+    /// Returns the corresponding JSAPI symbol code.
+    pub fn to_raw(self) -> RawSymbolCode {
+        match self {
+            SymbolCode::IsConcatSpreadable => RawSymbolCode::isConcatSpreadable,
+            SymbolCode::Iterator => RawSymbolCode::iterator,
+            SymbolCode::Match => RawSymbolCode::match_,
+            SymbolCode::Replace => RawSymbolCode::replace,
+            SymbolCode::Search => RawSymbolCode::search,
+            SymbolCode::Species => RawSymbolCode::species,
+            SymbolCode::HasInstance => RawSymbolCode::hasInstance,
+            SymbolCode::Split => RawSymbolCode::split,
+            SymbolCode::ToPrimitive => RawSymbolCode::toPrimitive,
+            SymbolCode::ToStringTag => RawSymbolCode::toStringTag,
+            SymbolCode::Unscopables => RawSymbolCode::unscopables,
+            SymbolCode::AsyncIterator => RawSymbolCode::asyncIterator,
+            SymbolCode::MatchAll => RawSymbolCode::matchAll,
+        }
+    }
+
+    /// Returns the well-known symbol for a JSAPI symbol code, or `None` for
+    /// registered and unique symbols.
+    pub fn from_raw(code: RawSymbolCode) -> Option<SymbolCode> {
+        Some(match code {
+            RawSymbolCode::isConcatSpreadable => SymbolCode::IsConcatSpreadable,
+            RawSymbolCode::iterator => SymbolCode::Iterator,
+            RawSymbolCode::match_ => SymbolCode::Match,
+            RawSymbolCode::replace => SymbolCode::Replace,
+            RawSymbolCode::search => SymbolCode::Search,
+            RawSymbolCode::species => SymbolCode::Species,
+            RawSymbolCode::hasInstance => SymbolCode::HasInstance,
+            RawSymbolCode::split => SymbolCode::Split,
+            RawSymbolCode::toPrimitive => SymbolCode::ToPrimitive,
+            RawSymbolCode::toStringTag => SymbolCode::ToStringTag,
+            RawSymbolCode::unscopables => SymbolCode::Unscopables,
+            RawSymbolCode::asyncIterator => SymbolCode::AsyncIterator,
+            RawSymbolCode::matchAll => SymbolCode::MatchAll,
+            _ => return None,
+        })
+    }
+}
+
+/// A rooted symbol.
+#[derive(Clone, Copy)]
+pub struct Symbol<'a> {
+    handle: HandleSymbol<'a>,
+}
+
+impl<'a> Symbol<'a> {
+    /// Wraps a rooted, non-null symbol.
+    pub fn new(handle: HandleSymbol<'a>) -> Symbol<'a> {
+        assert!(!handle.is_null());
+        Symbol { handle }
+    }
+
+    /// Returns the root holding the symbol.
+    pub fn handle(&self) -> HandleSymbol<'a> {
+        self.handle
+    }
+
+    /// Returns the symbol.
+    pub fn get(&self) -> *mut RawSymbol {
+        *self.handle
+    }
+
+    /// Returns the description passed to `Symbol()`, the key of a registered
+    /// symbol, or `None` for a symbol created without a description.
+    pub unsafe fn description(&self, cx: *mut JSContext) -> Option<String> {
+        let description = GetSymbolDescription(self.handle.into());
+        if description.is_null() {
+            None
+        } else {
+            Some(jsstr_to_string(cx, description))
+        }
+    }
+
+    /// Returns which well-known symbol this is, if any.
+    pub unsafe fn well_known(&self) -> Option<SymbolCode> {
+        SymbolCode::from_raw(GetSymbolCode(self.handle.into()))
+    }
+
+    /// Returns a property key for the symbol.
+    ///
+    /// # Safety
+    ///
+    /// The key does not root the symbol, which must be kept alive for as long
+    /// as the key is used.
+    pub unsafe fn to_property_key(&self) -> PropertyKey {
+        PropertyKey::Symbol(self.get())
+    }
+
+    /// Returns whether the symbol was created through `Symbol.for`.
+    pub unsafe fn is_registered(&self) -> bool {
+        GetSymbolCode(self.handle.into()) == RawSymbolCode::InSymbolRegistry
+    }
+}
+
+impl<'a> ToJSValConvertible for Symbol<'a> {
+    #[inline]
+    unsafe fn to_jsval(&self, _cx: *mut JSContext, mut rval: MutableHandleValue) {
+        rval.set(SymbolValue(&*self.get()));
+    }
+}
+
+/// Creates a new unique symbol, as `Symbol(description)` does.
+///
+/// Returns a null pointer on JSAPI failure, with a pending exception.
+pub unsafe fn new_symbol(cx: *mut JSContext, description: Option<&str>) -> *mut RawSymbol {
+    rooted!(in(cx) let mut string = ptr::null_mut::<JSString>());
+    if let Some(description) = description {
+        string.set(new_string(cx, description));
+    }
+    NewSymbol(cx, string.handle().into())
+}
+
+/// Returns the symbol registered under `key`, creating it if needed, as
+/// `Symbol.for(key)` does.
+///
+/// Returns a null pointer on JSAPI failure, with a pending exception.
+pub unsafe fn symbol_for(cx: *mut JSContext, key: &str) -> *mut RawSymbol {
+    rooted!(in(cx) let string = new_string(cx, key));
+    GetSymbolFor(cx, string.handle().into())
+}
+
+/// Returns a well-known symbol such as `Symbol.iterator`.
+pub unsafe fn well_known_symbol(cx: *mut JSContext, code: SymbolCode) -> *mut RawSymbol {
+    GetWellKnownSymbol(cx, code.to_raw())
+}
+
+unsafe fn new_string(cx: *mut JSContext, s: &str) -> *mut JSString {
+    rooted!(in(cx) let mut value = UndefinedValue());
+    s.to_jsval(cx, value.handle_mut());
+    value.to_string()
+}
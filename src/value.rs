@@ -0,0 +1,146 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! A pattern-matchable view of rooted JS values.
+//!
+//! ```ignore
+//! match ValueRef::new(value) {
+//!     ValueRef::Int32(i) => ...,
+//!     ValueRef::String(s) => jsstr_to_string(cx, s.get()),
+//!     ValueRef::Object(o) => ...,
+//!     ...
+//! }
+//! ```
+
+#![deny(missing_docs)]
+
+use jsapi::{BigInt, JSObject, JSString, Symbol};
+use rust::HandleValue;
+
+use std::marker::PhantomData;
+
+/// A GC thing of type `T` held by a rooted value.
+///
+/// The pointer is read from the value on every call to `get`, so it stays
+/// valid across moving GCs for as long as the root is alive.
+pub struct ValueHandle<'a, T: 'a> {
+    value: HandleValue<'a>,
+    marker: PhantomData<&'a T>,
+}
+
+impl<'a, T> Clone for ValueHandle<'a, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T> Copy for ValueHandle<'a, T> {}
+
+impl<'a, T> ValueHandle<'a, T> {
+    fn new(value: HandleValue<'a>) -> ValueHandle<'a, T> {
+        ValueHandle {
+            value,
+            marker: PhantomData,
+        }
+    }
+
+    /// Returns the rooted value that holds the GC thing.
+    pub fn value(&self) -> HandleValue<'a> {
+        self.value
+    }
+}
+
+impl<'a> ValueHandle<'a, JSString> {
+    /// Returns the string.
+    pub fn get(&self) -> *mut JSString {
+        self.value.to_string()
+    }
+}
+
+impl<'a> ValueHandle<'a, Symbol> {
+    /// Returns the symbol.
+    pub fn get(&self) -> *mut Symbol {
+        self.value.to_symbol()
+    }
+}
+
+impl<'a> ValueHandle<'a, BigInt> {
+    /// Returns the BigInt.
+    pub fn get(&self) -> *mut BigInt {
+        self.value.to_bigint()
+    }
+}
+
+impl<'a> ValueHandle<'a, JSObject> {
+    /// Returns the object.
+    pub fn get(&self) -> *mut JSObject {
+        self.value.to_object()
+    }
+}
+
+/// The type and contents of a rooted JS value, borrowed from its root.
+#[derive(Clone, Copy)]
+pub enum ValueRef<'a> {
+    /// `undefined`.
+    Undefined,
+    /// `null`.
+    Null,
+    /// A boolean.
+    Boolean(bool),
+    /// A number stored as an integer.
+    Int32(i32),
+    /// A number stored as a double. Integral numbers may be stored either
+    /// way, so use `as_number` to treat both alike.
+    Double(f64),
+    /// A string.
+    String(ValueHandle<'a, JSString>),
+    /// A symbol.
+    Symbol(ValueHandle<'a, Symbol>),
+    /// A BigInt.
+    BigInt(ValueHandle<'a, BigInt>),
+    /// An object.
+    Object(ValueHandle<'a, JSObject>),
+}
+
+impl<'a> ValueRef<'a> {
+    /// Inspects the rooted `value`.
+    pub fn new(value: HandleValue<'a>) -> ValueRef<'a> {
+        if value.is_undefined() {
+            ValueRef::Undefined
+        } else if value.is_null() {
+            ValueRef::Null
+        } else if value.is_boolean() {
+            ValueRef::Boolean(value.to_boolean())
+        } else if value.is_int32() {
+            ValueRef::Int32(value.to_int32())
+        } else if value.is_double() {
+            ValueRef::Double(value.to_double())
+        } else if value.is_string() {
+            ValueRef::String(ValueHandle::new(value))
+        } else if value.is_symbol() {
+            ValueRef::Symbol(ValueHandle::new(value))
+        } else if value.is_bigint() {
+            ValueRef::BigInt(ValueHandle::new(value))
+        } else if value.is_object() {
+            ValueRef::Object(ValueHandle::new(value))
+        } else {
+            unreachable!("magic values cannot be inspected")
+        }
+    }
+
+    /// Returns the number for `Int32` and `Double` values.
+    pub fn as_number(&self) -> Option<f64> {
+        match *self {
+            ValueRef::Int32(i) => Some(i as f64),
+            ValueRef::Double(d) => Some(d),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> From<HandleValue<'a>> for ValueRef<'a> {
+    fn from(value: HandleValue<'a>) -> ValueRef<'a> {
+        ValueRef::new(value)
+    }
+}
@@ -0,0 +1,200 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+#[macro_use]
+extern crate mozjs;
+
+use mozjs::closure::{define_accessor, new_function_from_closure};
+use mozjs::jsapi::CallArgs;
+use mozjs::jsapi::GCReason;
+use mozjs::jsapi::JSAutoRealm;
+use mozjs::jsapi::JSContext;
+use mozjs::jsapi::JS_ClearPendingException;
+use mozjs::jsapi::JS_IsExceptionPending;
+use mozjs::jsapi::JS_NewGlobalObject;
+use mozjs::jsapi::OnNewGlobalHookOption;
+use mozjs::jsapi::Value;
+use mozjs::jsapi::JS_GC;
+use mozjs::jsapi::{JSPROP_ENUMERATE, JSPROP_PERMANENT};
+use mozjs::jsval::{Int32Value, UndefinedValue};
+use mozjs::rust::{FunctionSpecs, PropertySpecs};
+use mozjs::rust::{JSEngine, RealmOptions, Runtime, SIMPLE_GLOBAL_CLASS};
+
+use std::cell::Cell;
+use std::ptr;
+use std::rc::Rc;
+
+struct DropFlag(Rc<Cell<bool>>);
+
+impl Drop for DropFlag {
+    fn drop(&mut self) {
+        self.0.set(true);
+    }
+}
+
+unsafe extern "C" fn answer(_cx: *mut JSContext, argc: u32, vp: *mut Value) -> bool {
+    let args = CallArgs::from_vp(vp, argc);
+    args.rval().set(Int32Value(42));
+    true
+}
+
+unsafe extern "C" fn count_args(_cx: *mut JSContext, argc: u32, vp: *mut Value) -> bool {
+    let args = CallArgs::from_vp(vp, argc);
+    args.rval().set(Int32Value(argc as i32));
+    true
+}
+
+#[test]
+fn accessor() {
+    let engine = JSEngine::init().unwrap();
+    let rt = Runtime::new(engine.handle());
+    let cx = rt.cx();
+    let h_option = OnNewGlobalHookOption::FireOnNewGlobalHook;
+    let c_option = RealmOptions::default();
+
+    unsafe {
+        rooted!(in(cx) let global = JS_NewGlobalObject(
+            cx,
+            &SIMPLE_GLOBAL_CLASS,
+            ptr::null_mut(),
+            h_option,
+            &*c_option,
+        ));
+        let _ac = JSAutoRealm::new(cx, global.get());
+
+        let counter = Rc::new(Cell::new(7));
+        let getter_counter = counter.clone();
+        let setter_counter = counter.clone();
+        define_accessor(
+            cx,
+            global.handle(),
+            "counter",
+            Some(Box::new(move |_cx, _this, mut rval| {
+                rval.set(Int32Value(getter_counter.get()));
+                Ok(())
+            })),
+            Some(Box::new(move |_cx, _this, value| {
+                setter_counter.set(value.to_int32());
+                Ok(())
+            })),
+            JSPROP_ENUMERATE as u32,
+        )
+        .unwrap();
+
+        rooted!(in(cx) let mut rval = UndefinedValue());
+        assert!(rt
+            .evaluate_script(global.handle(), "counter", "test", 1, rval.handle_mut())
+            .is_ok());
+        assert_eq!(rval.get().to_int32(), 7);
+
+        assert!(rt
+            .evaluate_script(
+                global.handle(),
+                "counter = 42",
+                "test",
+                1,
+                rval.handle_mut()
+            )
+            .is_ok());
+        assert_eq!(counter.get(), 42);
+
+        assert!(rt
+            .evaluate_script(
+                global.handle(),
+                "Object.keys(this).indexOf('counter') != -1",
+                "test",
+                1,
+                rval.handle_mut()
+            )
+            .is_ok());
+        assert!(rval.get().to_boolean());
+
+        define_accessor(
+            cx,
+            global.handle(),
+            "fixed",
+            Some(Box::new(|_cx, _this, mut rval| {
+                rval.set(Int32Value(1));
+                Ok(())
+            })),
+            None,
+            JSPROP_PERMANENT as u32,
+        )
+        .unwrap();
+        assert!(rt
+            .evaluate_script(
+                global.handle(),
+                "var desc = Object.getOwnPropertyDescriptor(this, 'fixed');
+                 !desc.configurable && !desc.enumerable && desc.set === undefined &&
+                 !delete this.fixed && fixed === 1",
+                "test",
+                1,
+                rval.handle_mut()
+            )
+            .is_ok());
+        assert!(rval.get().to_boolean());
+
+        FunctionSpecs::new()
+            .method("answer", Some(answer), 0, JSPROP_ENUMERATE as u16)
+            .method("argc", Some(count_args), 2, 0)
+            .define(cx, global.handle())
+            .unwrap();
+        assert!(rt
+            .evaluate_script(
+                global.handle(),
+                "answer() === 42 && argc(1, 2, 3) === 3 && argc.length === 2 &&
+                 Object.keys(this).indexOf('answer') != -1 &&
+                 Object.keys(this).indexOf('argc') == -1",
+                "test",
+                1,
+                rval.handle_mut()
+            )
+            .is_ok());
+        assert!(rval.get().to_boolean());
+
+        let specs = PropertySpecs::new().accessor(
+            "answerProp",
+            Some(answer),
+            None,
+            (JSPROP_ENUMERATE | JSPROP_PERMANENT) as u8,
+        );
+        assert_eq!(specs.as_slice().len(), 2);
+        specs.define(cx, global.handle()).unwrap();
+        assert!(rt
+            .evaluate_script(
+                global.handle(),
+                "var desc = Object.getOwnPropertyDescriptor(this, 'answerProp');
+                 answerProp === 42 && desc.enumerable && !desc.configurable",
+                "test",
+                1,
+                rval.handle_mut()
+            )
+            .is_ok());
+        assert!(rval.get().to_boolean());
+
+        let fun = new_function_from_closure(cx, "bad\0name", 0, Box::new(|_, _| Ok(())));
+        assert!(fun.is_null());
+        assert!(JS_IsExceptionPending(cx));
+        JS_ClearPendingException(cx);
+
+        let dropped = Rc::new(Cell::new(false));
+        {
+            let flag = DropFlag(dropped.clone());
+            rooted!(in(cx) let fun = new_function_from_closure(
+                cx,
+                "f",
+                0,
+                Box::new(move |_cx, _args| {
+                    let _ = &flag;
+                    Ok(())
+                }),
+            ));
+            assert!(!fun.is_null());
+            JS_GC(cx, GCReason::API);
+            assert!(!dropped.get());
+        }
+        JS_GC(cx, GCReason::API);
+        assert!(dropped.get());
+    }
+}
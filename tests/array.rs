@@ -0,0 +1,89 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+#[macro_use]
+extern crate mozjs;
+
+use mozjs::array::JsArray;
+use mozjs::conversions::{ConversionBehavior, ConversionResult};
+use mozjs::jsapi::JSAutoRealm;
+use mozjs::jsapi::JSObject;
+use mozjs::jsapi::JS_ClearPendingException;
+use mozjs::jsapi::JS_NewGlobalObject;
+use mozjs::jsapi::JS_NewPlainObject;
+use mozjs::jsapi::OnNewGlobalHookOption;
+use mozjs::jsval::{Int32Value, ObjectValue, UndefinedValue};
+use mozjs::rust::{JSEngine, RealmOptions, Runtime, SIMPLE_GLOBAL_CLASS};
+
+use std::ptr;
+
+#[test]
+fn array() {
+    let engine = JSEngine::init().unwrap();
+    let rt = Runtime::new(engine.handle());
+    let cx = rt.cx();
+    let h_option = OnNewGlobalHookOption::FireOnNewGlobalHook;
+    let c_option = RealmOptions::default();
+
+    unsafe {
+        rooted!(in(cx) let global = JS_NewGlobalObject(
+            cx,
+            &SIMPLE_GLOBAL_CLASS,
+            ptr::null_mut(),
+            h_option,
+            &*c_option,
+        ));
+        let _ac = JSAutoRealm::new(cx, global.get());
+
+        rooted!(in(cx) let mut array = ptr::null_mut::<JSObject>());
+        let array = JsArray::new(cx, array.handle_mut()).unwrap();
+        assert_eq!(array.len(cx).unwrap(), 0);
+        for i in 0..5 {
+            array.push(cx, &(i * 10)).unwrap();
+        }
+        array.set(cx, 1, &String::from("one")).unwrap();
+        assert_eq!(array.len(cx).unwrap(), 5);
+        match array.get::<String>(cx, 1, ()) {
+            Ok(ConversionResult::Success(one)) => assert_eq!(one, "one"),
+            _ => panic!("reading element 1 failed"),
+        }
+
+        array.truncate(cx, 3).unwrap();
+        array.truncate(cx, 10).unwrap();
+        assert_eq!(array.len(cx).unwrap(), 3);
+        let values: Vec<_> = array
+            .iter::<i32>(cx, ConversionBehavior::Default)
+            .unwrap()
+            .map(|value| match value {
+                Ok(ConversionResult::Success(value)) => value,
+                _ => panic!("reading an element failed"),
+            })
+            .collect();
+        assert_eq!(values, vec![0, 0, 20]);
+
+        rooted!(in(cx) let mut rval = UndefinedValue());
+        assert!(rt
+            .evaluate_script(
+                global.handle(),
+                "new Proxy([1, 2], {})",
+                "test",
+                1,
+                rval.handle_mut()
+            )
+            .is_ok());
+        assert!(JsArray::is_array(cx, rval.handle()).unwrap());
+        rooted!(in(cx) let proxy = rval.to_object());
+        let proxy = JsArray::from_handle(cx, proxy.handle()).unwrap();
+        proxy.push(cx, &3).unwrap();
+        assert_eq!(proxy.len(cx).unwrap(), 3);
+
+        rooted!(in(cx) let plain = JS_NewPlainObject(cx));
+        assert!(JsArray::from_handle(cx, plain.handle()).is_err());
+        JS_ClearPendingException(cx);
+        rval.set(ObjectValue(plain.get()));
+        assert!(!JsArray::is_array(cx, rval.handle()).unwrap());
+        rval.set(Int32Value(1));
+        assert!(!JsArray::is_array(cx, rval.handle()).unwrap());
+    }
+}
@@ -0,0 +1,111 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+#[macro_use]
+extern crate mozjs;
+
+use mozjs::conversions::{AsBigInt, ConversionResult, FromJSValConvertible, ToJSValConvertible};
+use mozjs::jsapi::JSAutoRealm;
+use mozjs::jsapi::JS_ClearPendingException;
+use mozjs::jsapi::JS_IsExceptionPending;
+use mozjs::jsapi::JS_NewGlobalObject;
+use mozjs::jsapi::JS_SetProperty;
+use mozjs::jsapi::OnNewGlobalHookOption;
+use mozjs::jsval::UndefinedValue;
+use mozjs::rust::{JSEngine, RealmOptions, Runtime, SIMPLE_GLOBAL_CLASS};
+
+use std::ptr;
+
+#[test]
+fn bigint() {
+    let engine = JSEngine::init().unwrap();
+    let rt = Runtime::new(engine.handle());
+    let cx = rt.cx();
+    let h_option = OnNewGlobalHookOption::FireOnNewGlobalHook;
+    let c_option = RealmOptions::default();
+
+    unsafe {
+        rooted!(in(cx) let global = JS_NewGlobalObject(
+            cx,
+            &SIMPLE_GLOBAL_CLASS,
+            ptr::null_mut(),
+            h_option,
+            &*c_option,
+        ));
+        let _ac = JSAutoRealm::new(cx, global.get());
+
+        rooted!(in(cx) let mut rval = UndefinedValue());
+        let check = |name: &str, value: &dyn ToJSValConvertible, expected: &str| {
+            rooted!(in(cx) let mut val = UndefinedValue());
+            value.to_jsval(cx, val.handle_mut());
+            assert!(val.is_bigint());
+            let name = format!("{}\0", name);
+            assert!(JS_SetProperty(
+                cx,
+                global.handle().into(),
+                name.as_ptr() as *const _,
+                val.handle().into(),
+            ));
+            rooted!(in(cx) let mut result = UndefinedValue());
+            let script = format!("{} === {}", &name[..name.len() - 1], expected);
+            assert!(rt
+                .evaluate_script(global.handle(), &script, "test", 1, result.handle_mut())
+                .is_ok());
+            assert!(result.to_boolean(), "{}", script);
+        };
+
+        check("a", &AsBigInt(9007199254740993i64), "9007199254740993n");
+        check("b", &AsBigInt(u64::max_value()), "18446744073709551615n");
+        check("c", &i128::min_value(), "-(2n ** 127n)");
+        check("d", &u128::max_value(), "2n ** 128n - 1n");
+
+        assert!(rt
+            .evaluate_script(
+                global.handle(),
+                "2n ** 63n - 1n",
+                "test",
+                1,
+                rval.handle_mut()
+            )
+            .is_ok());
+        match AsBigInt::<i64>::from_jsval(cx, rval.handle(), ()) {
+            Ok(ConversionResult::Success(AsBigInt(value))) => assert_eq!(value, i64::max_value()),
+            _ => panic!("converting 2n ** 63n - 1n failed"),
+        }
+        match i128::from_jsval(cx, rval.handle(), ()) {
+            Ok(ConversionResult::Success(value)) => assert_eq!(value, i64::max_value() as i128),
+            _ => panic!("converting 2n ** 63n - 1n failed"),
+        }
+
+        assert!(rt
+            .evaluate_script(global.handle(), "2n ** 64n", "test", 1, rval.handle_mut())
+            .is_ok());
+        assert!(AsBigInt::<u64>::from_jsval(cx, rval.handle(), ()).is_err());
+        assert!(JS_IsExceptionPending(cx));
+        JS_ClearPendingException(cx);
+
+        assert!(rt
+            .evaluate_script(
+                global.handle(),
+                "-(2n ** 100n)",
+                "test",
+                1,
+                rval.handle_mut()
+            )
+            .is_ok());
+        match i128::from_jsval(cx, rval.handle(), ()) {
+            Ok(ConversionResult::Success(value)) => assert_eq!(value, -(1i128 << 100)),
+            _ => panic!("converting -(2n ** 100n) failed"),
+        }
+        assert!(u128::from_jsval(cx, rval.handle(), ()).is_err());
+        JS_ClearPendingException(cx);
+
+        // Numbers are not implicitly converted to BigInts.
+        assert!(rt
+            .evaluate_script(global.handle(), "1", "test", 1, rval.handle_mut())
+            .is_ok());
+        assert!(i128::from_jsval(cx, rval.handle(), ()).is_err());
+        JS_ClearPendingException(cx);
+    }
+}
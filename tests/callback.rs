@@ -6,20 +6,28 @@
 extern crate mozjs;
 extern crate libc;
 
+use mozjs::callback::{incumbent_global, Callback, ExceptionSink};
+use mozjs::conversions::{ConversionBehavior, ConversionResult};
 use mozjs::glue::EncodeStringToUTF8;
 use mozjs::jsapi::CallArgs;
+use mozjs::jsapi::GCReason;
 use mozjs::jsapi::JSAutoRealm;
 use mozjs::jsapi::JSContext;
+use mozjs::jsapi::JS_ClearPendingException;
 use mozjs::jsapi::JS_DefineFunction;
+use mozjs::jsapi::JS_IsExceptionPending;
 use mozjs::jsapi::JS_NewGlobalObject;
 use mozjs::jsapi::JS_ReportErrorASCII;
 use mozjs::jsapi::OnNewGlobalHookOption;
 use mozjs::jsapi::Value;
+use mozjs::jsapi::JS_GC;
 use mozjs::jsval::UndefinedValue;
-use mozjs::rust::{JSEngine, RealmOptions, Runtime, SIMPLE_GLOBAL_CLASS};
+use mozjs::rust::{HandleValue, JSEngine, RealmOptions, Runtime, SIMPLE_GLOBAL_CLASS};
 
+use std::cell::Cell;
 use std::ffi::CStr;
 use std::ptr;
+use std::rc::Rc;
 use std::str;
 
 #[test]
@@ -56,6 +64,76 @@ fn callback() {
     }
 }
 
+fn exception_sink<F: Fn(*mut JSContext, HandleValue) + 'static>(sink: F) -> Rc<ExceptionSink> {
+    Rc::new(sink)
+}
+
+#[test]
+fn persistent_callback() {
+    let engine = JSEngine::init().unwrap();
+    let runtime = Runtime::new(engine.handle());
+    let cx = runtime.cx();
+    let h_option = OnNewGlobalHookOption::FireOnNewGlobalHook;
+    let c_option = RealmOptions::default();
+
+    unsafe {
+        rooted!(in(cx) let global = JS_NewGlobalObject(
+            cx,
+            &SIMPLE_GLOBAL_CLASS,
+            ptr::null_mut(),
+            h_option,
+            &*c_option,
+        ));
+        let _ac = JSAutoRealm::new(cx, global.get());
+
+        let mut listeners = vec![];
+        for source in &[
+            "(function (a, b) { return a * b; })",
+            "(function () { throw 7; })",
+        ] {
+            rooted!(in(cx) let mut rval = UndefinedValue());
+            assert!(runtime
+                .evaluate_script(global.handle(), source, "test", 1, rval.handle_mut())
+                .is_ok());
+            rooted!(in(cx) let func = rval.to_object());
+            listeners.push(Callback::new(cx, func.handle()).unwrap());
+        }
+        assert_eq!(listeners[0].incumbent(), global.get());
+        JS_GC(cx, GCReason::API);
+
+        match listeners[0].invoke::<i32, _>(
+            cx,
+            HandleValue::undefined(),
+            (6, 7),
+            ConversionBehavior::Default,
+        ) {
+            Ok(ConversionResult::Success(product)) => assert_eq!(product, 42),
+            _ => panic!("invoking the callback failed"),
+        }
+        assert!(incumbent_global().is_none());
+
+        assert!(listeners[1]
+            .invoke::<(), _>(cx, HandleValue::undefined(), (), ())
+            .is_err());
+        assert!(JS_IsExceptionPending(cx));
+        JS_ClearPendingException(cx);
+
+        let reported = Rc::new(Cell::new(0));
+        let counter = reported.clone();
+        let sink = exception_sink(move |_cx, exception| counter.set(exception.to_int32()));
+        listeners[1].set_exception_sink(Some(sink));
+        assert!(listeners[1]
+            .invoke::<(), _>(cx, HandleValue::undefined(), (), ())
+            .is_err());
+        assert!(!JS_IsExceptionPending(cx));
+        assert_eq!(reported.get(), 7);
+
+        rooted!(in(cx) let not_callable = global.get());
+        assert!(Callback::new(cx, not_callable.handle()).is_err());
+        JS_ClearPendingException(cx);
+    }
+}
+
 unsafe extern "C" fn puts(context: *mut JSContext, argc: u32, vp: *mut Value) -> bool {
     let args = CallArgs::from_vp(vp, argc);
 
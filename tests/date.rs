@@ -0,0 +1,111 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+#[cfg(feature = "chrono")]
+extern crate chrono;
+#[macro_use]
+extern crate mozjs;
+
+#[cfg(feature = "chrono")]
+use chrono::{DateTime, TimeZone, Utc};
+use mozjs::conversions::{ConversionResult, FromJSValConvertible, ToJSValConvertible};
+use mozjs::date::JsDate;
+use mozjs::jsapi::JSAutoRealm;
+use mozjs::jsapi::JSObject;
+use mozjs::jsapi::JS_ClearPendingException;
+use mozjs::jsapi::JS_NewGlobalObject;
+use mozjs::jsapi::OnNewGlobalHookOption;
+use mozjs::jsval::UndefinedValue;
+use mozjs::rust::{JSEngine, RealmOptions, Runtime, SIMPLE_GLOBAL_CLASS};
+
+use std::f64;
+use std::ptr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[test]
+fn date() {
+    let engine = JSEngine::init().unwrap();
+    let rt = Runtime::new(engine.handle());
+    let cx = rt.cx();
+    let h_option = OnNewGlobalHookOption::FireOnNewGlobalHook;
+    let c_option = RealmOptions::default();
+
+    unsafe {
+        rooted!(in(cx) let global = JS_NewGlobalObject(
+            cx,
+            &SIMPLE_GLOBAL_CLASS,
+            ptr::null_mut(),
+            h_option,
+            &*c_option,
+        ));
+        let _ac = JSAutoRealm::new(cx, global.get());
+
+        rooted!(in(cx) let mut obj = ptr::null_mut::<JSObject>());
+        let date = JsDate::new(cx, 1234.9, obj.handle_mut()).unwrap();
+        assert!(date.is_valid(cx).unwrap());
+        assert_eq!(date.millis_since_epoch(cx).unwrap(), 1234.0);
+        assert_eq!(
+            date.to_system_time(cx).unwrap(),
+            Some(UNIX_EPOCH + Duration::from_millis(1234))
+        );
+
+        rooted!(in(cx) let mut obj = ptr::null_mut::<JSObject>());
+        let date = JsDate::new(cx, 8.64e15 + 1.0, obj.handle_mut()).unwrap();
+        assert!(!date.is_valid(cx).unwrap());
+        assert!(date.millis_since_epoch(cx).unwrap().is_nan());
+        assert_eq!(date.to_system_time(cx).unwrap(), None);
+
+        rooted!(in(cx) let mut obj = ptr::null_mut::<JSObject>());
+        let before = UNIX_EPOCH - Duration::from_millis(1500);
+        let date = JsDate::from_system_time(cx, before, obj.handle_mut()).unwrap();
+        assert_eq!(date.millis_since_epoch(cx).unwrap(), -1500.0);
+        assert_eq!(date.to_system_time(cx).unwrap(), Some(before));
+
+        rooted!(in(cx) let mut obj = ptr::null_mut::<JSObject>());
+        let date = JsDate::new(cx, f64::NAN, obj.handle_mut()).unwrap();
+        rooted!(in(cx) let mut rval = UndefinedValue());
+        date.to_jsval(cx, rval.handle_mut());
+        match SystemTime::from_jsval(cx, rval.handle(), ()) {
+            Ok(ConversionResult::Failure(_)) => {}
+            _ => panic!("an invalid Date is not a SystemTime"),
+        }
+
+        assert!(rt
+            .evaluate_script(global.handle(), "({})", "test", 1, rval.handle_mut())
+            .is_ok());
+        assert!(!JsDate::is_date(cx, rval.handle()).unwrap());
+        rooted!(in(cx) let obj = rval.to_object());
+        assert!(JsDate::from_handle(cx, obj.handle()).is_err());
+        JS_ClearPendingException(cx);
+
+        assert!(rt
+            .evaluate_script(
+                global.handle(),
+                "new Date(Date.UTC(2020, 0, 1))",
+                "test",
+                1,
+                rval.handle_mut()
+            )
+            .is_ok());
+        assert!(JsDate::is_date(cx, rval.handle()).unwrap());
+        assert_eq!(
+            SystemTime::from_jsval(cx, rval.handle(), ()).unwrap(),
+            ConversionResult::Success(UNIX_EPOCH + Duration::from_secs(1_577_836_800))
+        );
+
+        #[cfg(feature = "chrono")]
+        {
+            assert_eq!(
+                DateTime::<Utc>::from_jsval(cx, rval.handle(), ()).unwrap(),
+                ConversionResult::Success(Utc.timestamp_millis_opt(1_577_836_800_000).unwrap())
+            );
+            Utc.timestamp_millis_opt(-500)
+                .unwrap()
+                .to_jsval(cx, rval.handle_mut());
+            rooted!(in(cx) let obj = rval.to_object());
+            let date = JsDate::from_handle(cx, obj.handle()).unwrap();
+            assert_eq!(date.millis_since_epoch(cx).unwrap(), -500.0);
+        }
+    }
+}
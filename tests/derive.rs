@@ -0,0 +1,228 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+#[macro_use]
+extern crate mozjs;
+
+use mozjs::conversions::{ConversionBehavior, ConversionResult, FromJSVal, ToJSVal};
+use mozjs::conversions::{FromJSValConvertible, ToJSValConvertible};
+use mozjs::jsapi::JSAutoRealm;
+use mozjs::jsapi::JS_NewGlobalObject;
+use mozjs::jsapi::JS_SetProperty;
+use mozjs::jsapi::OnNewGlobalHookOption;
+use mozjs::jsval::UndefinedValue;
+use mozjs::rust::{JSEngine, RealmOptions, Runtime, SIMPLE_GLOBAL_CLASS};
+
+use std::ptr;
+
+fn default_timeout() -> u32 {
+    30
+}
+
+#[derive(Debug, PartialEq, ToJSVal, FromJSVal)]
+#[jsval(rename_all = "camelCase")]
+struct RequestInit {
+    method: String,
+    #[jsval(default)]
+    keep_alive: bool,
+    #[jsval(default = "default_timeout")]
+    timeout: u32,
+    #[jsval(config = "ConversionBehavior::EnforceRange", rename = "prio")]
+    priority: Option<u8>,
+    #[jsval(default)]
+    referrer: Option<String>,
+}
+
+#[derive(Debug, PartialEq, ToJSVal, FromJSVal)]
+#[jsval(rename_all = "kebab-case")]
+enum RequestMode {
+    SameOrigin,
+    NoCors,
+    Cors,
+}
+
+#[derive(Debug, PartialEq, ToJSVal, FromJSVal)]
+#[jsval(tag = "type", rename_all = "lowercase")]
+enum Message {
+    Ping,
+    Resize { width: u32, height: u32 },
+    Fetch(RequestInit),
+}
+
+#[test]
+fn derive() {
+    let engine = JSEngine::init().unwrap();
+    let rt = Runtime::new(engine.handle());
+    let cx = rt.cx();
+    let h_option = OnNewGlobalHookOption::FireOnNewGlobalHook;
+    let c_option = RealmOptions::default();
+
+    unsafe {
+        rooted!(in(cx) let global = JS_NewGlobalObject(
+            cx,
+            &SIMPLE_GLOBAL_CLASS,
+            ptr::null_mut(),
+            h_option,
+            &*c_option,
+        ));
+        let _ac = JSAutoRealm::new(cx, global.get());
+
+        rooted!(in(cx) let mut rval = UndefinedValue());
+        assert!(rt
+            .evaluate_script(
+                global.handle(),
+                "({ method: 'POST', keepAlive: true, prio: 3, ignored: 1 })",
+                "test",
+                1,
+                rval.handle_mut()
+            )
+            .is_ok());
+        let init = RequestInit {
+            method: "POST".to_owned(),
+            keep_alive: true,
+            timeout: 30,
+            priority: Some(3),
+            referrer: None,
+        };
+        match RequestInit::from_jsval(cx, rval.handle(), ()) {
+            Ok(ConversionResult::Success(converted)) => assert_eq!(converted, init),
+            _ => panic!("converting the dictionary failed"),
+        }
+
+        init.to_jsval(cx, rval.handle_mut());
+        assert!(JS_SetProperty(
+            cx,
+            global.handle().into(),
+            b"init\0".as_ptr() as *const _,
+            rval.handle().into(),
+        ));
+        assert!(rt
+            .evaluate_script(
+                global.handle(),
+                "Object.keys(init).join() === 'keepAlive,method,prio,timeout'",
+                "test",
+                1,
+                rval.handle_mut()
+            )
+            .is_ok());
+        assert!(rval.to_boolean());
+
+        assert!(rt
+            .evaluate_script(global.handle(), "({})", "test", 1, rval.handle_mut())
+            .is_ok());
+        match RequestInit::from_jsval(cx, rval.handle(), ()) {
+            Ok(ConversionResult::Failure(e)) => {
+                assert_eq!(e, "missing required member 'method'")
+            }
+            _ => panic!("a required member was missing"),
+        }
+
+        assert!(rt
+            .evaluate_script(
+                global.handle(),
+                "({ type: 'fetch', method: 'GET', keepAlive: {} })",
+                "test",
+                1,
+                rval.handle_mut()
+            )
+            .is_ok());
+        match Message::from_jsval(cx, rval.handle(), ()) {
+            Ok(ConversionResult::Success(Message::Fetch(init))) => {
+                assert_eq!(init.method, "GET");
+                assert!(init.keep_alive);
+                assert_eq!(init.priority, None);
+            }
+            _ => panic!("converting the tagged union failed"),
+        }
+
+        assert!(rt
+            .evaluate_script(
+                global.handle(),
+                "({ type: 'resize', width: 640, height: [] })",
+                "test",
+                1,
+                rval.handle_mut()
+            )
+            .is_ok());
+        match Message::from_jsval(cx, rval.handle(), ()) {
+            Ok(ConversionResult::Success(Message::Resize { width, height })) => {
+                assert_eq!((width, height), (640, 0))
+            }
+            _ => panic!("converting the tagged union failed"),
+        }
+
+        Message::Ping.to_jsval(cx, rval.handle_mut());
+        match Message::from_jsval(cx, rval.handle(), ()) {
+            Ok(ConversionResult::Success(Message::Ping)) => {}
+            _ => panic!("round-tripping a unit variant failed"),
+        }
+
+        assert!(rt
+            .evaluate_script(
+                global.handle(),
+                "({ type: 'close' })",
+                "test",
+                1,
+                rval.handle_mut()
+            )
+            .is_ok());
+        match Message::from_jsval(cx, rval.handle(), ()) {
+            Ok(ConversionResult::Failure(e)) => {
+                assert_eq!(e, "'close' is not a valid value for member 'type'")
+            }
+            _ => panic!("the tag was not valid"),
+        }
+
+        let fetch = Message::Fetch(RequestInit {
+            method: "GET".to_owned(),
+            keep_alive: false,
+            timeout: 5,
+            priority: None,
+            referrer: Some("about:blank".to_owned()),
+        });
+        fetch.to_jsval(cx, rval.handle_mut());
+        assert!(JS_SetProperty(
+            cx,
+            global.handle().into(),
+            b"fetch\0".as_ptr() as *const _,
+            rval.handle().into(),
+        ));
+        assert!(rt
+            .evaluate_script(
+                global.handle(),
+                "Object.keys(fetch).join() === 'keepAlive,method,referrer,timeout,type' && \
+                 fetch.type === 'fetch'",
+                "test",
+                1,
+                rval.handle_mut()
+            )
+            .is_ok());
+        assert!(rval.to_boolean());
+        fetch.to_jsval(cx, rval.handle_mut());
+        assert_eq!(
+            Message::from_jsval(cx, rval.handle(), ()).unwrap(),
+            ConversionResult::Success(fetch)
+        );
+
+        RequestMode::SameOrigin.to_jsval(cx, rval.handle_mut());
+        assert_eq!(
+            String::from_jsval(cx, rval.handle(), ()).unwrap(),
+            ConversionResult::Success("same-origin".to_owned())
+        );
+        assert!(rt
+            .evaluate_script(global.handle(), "'no-cors'", "test", 1, rval.handle_mut())
+            .is_ok());
+        assert_eq!(
+            RequestMode::from_jsval(cx, rval.handle(), ()).unwrap(),
+            ConversionResult::Success(RequestMode::NoCors)
+        );
+        assert!(rt
+            .evaluate_script(global.handle(), "'navigate'", "test", 1, rval.handle_mut())
+            .is_ok());
+        match RequestMode::from_jsval(cx, rval.handle(), ()) {
+            Ok(ConversionResult::Failure(_)) => {}
+            _ => panic!("'navigate' is not a RequestMode"),
+        }
+    }
+}
@@ -5,14 +5,18 @@
 #[macro_use]
 extern crate mozjs;
 
+use mozjs::conversions::{ConversionBehavior, ConversionResult};
 use mozjs::glue::RUST_JSID_IS_STRING;
 use mozjs::glue::RUST_JSID_TO_STRING;
 use mozjs::jsapi::GetPropertyKeys;
+use mozjs::jsapi::JSAutoRealm;
 use mozjs::jsapi::JS_NewGlobalObject;
 use mozjs::jsapi::JS_StringEqualsAscii;
 use mozjs::jsapi::OnNewGlobalHookOption;
 use mozjs::jsapi::JSITER_OWNONLY;
 use mozjs::jsval::UndefinedValue;
+use mozjs::object::{KeyFlags, Keys, Object};
+use mozjs::property::PropertyKey;
 use mozjs::rust::IdVector;
 use mozjs::rust::JSEngine;
 use mozjs::rust::RealmOptions;
@@ -71,3 +75,74 @@ fn enumerate() {
         assert!(matches);
     }
 }
+
+#[test]
+fn object_keys() {
+    let engine = JSEngine::init().unwrap();
+    let rt = Runtime::new(engine.handle());
+    let cx = rt.cx();
+    let options = RealmOptions::default();
+
+    unsafe {
+        rooted!(in(cx) let global =
+            JS_NewGlobalObject(cx, &SIMPLE_GLOBAL_CLASS, ptr::null_mut(),
+                               OnNewGlobalHookOption::FireOnNewGlobalHook,
+                               &*options)
+        );
+        let _ac = JSAutoRealm::new(cx, global.get());
+
+        rooted!(in(cx) let mut rval = UndefinedValue());
+        assert!(rt
+            .evaluate_script(
+                global.handle(),
+                "var o = Object.create({ inherited: 0 }, { hidden: { value: 1 } });
+                 o.a = 7; o[2] = 8; o[Symbol.iterator] = null; o",
+                "test",
+                1,
+                rval.handle_mut()
+            )
+            .is_ok());
+        rooted!(in(cx) let object = rval.to_object());
+        let object = Object::new(object.handle());
+
+        let names = |keys: Keys| -> Vec<String> {
+            keys.map(|key| match key.unwrap() {
+                PropertyKey::Index(index) => index.to_string(),
+                PropertyKey::String(name) => name,
+                PropertyKey::Symbol(_) => "@@symbol".to_owned(),
+            })
+            .collect()
+        };
+
+        let keys = object.keys(cx, KeyFlags::default()).unwrap();
+        assert_eq!(keys.len(), 2);
+        assert_eq!(names(keys), ["2", "a"]);
+
+        let all = KeyFlags {
+            inherited: false,
+            symbols: true,
+            non_enumerable: true,
+        };
+        assert_eq!(
+            names(object.keys(cx, all).unwrap()),
+            ["2", "hidden", "a", "@@symbol"]
+        );
+        assert_eq!(names(object.for_in(cx).unwrap()), ["2", "a", "inherited"]);
+
+        let entries = object
+            .own_entries::<i32>(cx, ConversionBehavior::Default)
+            .unwrap()
+            .map(|entry| match entry.unwrap() {
+                (key, ConversionResult::Success(value)) => (key, value),
+                _ => panic!("converting an entry failed"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            entries,
+            [
+                (PropertyKey::Index(2), 8),
+                (PropertyKey::String("a".to_owned()), 7)
+            ]
+        );
+    }
+}
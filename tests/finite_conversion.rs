@@ -0,0 +1,71 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+#[macro_use]
+extern crate mozjs;
+
+use mozjs::conversions::{ConversionResult, Finite};
+use mozjs::conversions::{FromJSValConvertible, ToJSValConvertible};
+use mozjs::jsapi::JSAutoRealm;
+use mozjs::jsapi::JS_ClearPendingException;
+use mozjs::jsapi::JS_IsExceptionPending;
+use mozjs::jsapi::JS_NewGlobalObject;
+use mozjs::jsapi::OnNewGlobalHookOption;
+use mozjs::jsval::{DoubleValue, UndefinedValue};
+use mozjs::rust::{JSEngine, RealmOptions, Runtime, SIMPLE_GLOBAL_CLASS};
+
+use std::f64;
+use std::ptr;
+
+#[test]
+fn finite_conversion() {
+    let engine = JSEngine::init().unwrap();
+    let rt = Runtime::new(engine.handle());
+    let cx = rt.cx();
+    let h_option = OnNewGlobalHookOption::FireOnNewGlobalHook;
+    let c_option = RealmOptions::default();
+
+    unsafe {
+        rooted!(in(cx) let global = JS_NewGlobalObject(
+            cx,
+            &SIMPLE_GLOBAL_CLASS,
+            ptr::null_mut(),
+            h_option,
+            &*c_option,
+        ));
+        let _ac = JSAutoRealm::new(cx, global.get());
+
+        assert_eq!(Finite::new(f64::NAN), None);
+        assert_eq!(Finite::new(f64::INFINITY), None);
+        assert_eq!(Finite::new(1.5f32).map(Finite::get), Some(1.5));
+
+        rooted!(in(cx) let mut rval = UndefinedValue());
+        Finite::wrap(2.5f64).to_jsval(cx, rval.handle_mut());
+        assert_eq!(rval.to_number(), 2.5);
+        assert_eq!(
+            Finite::<f64>::from_jsval(cx, rval.handle(), ()).unwrap(),
+            ConversionResult::Success(Finite::wrap(2.5))
+        );
+        assert_eq!(*Finite::<f32>::wrap(2.5), 2.5);
+
+        for &value in &[f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            rval.set(DoubleValue(value));
+            assert!(Finite::<f64>::from_jsval(cx, rval.handle(), ()).is_err());
+            assert!(JS_IsExceptionPending(cx));
+            JS_ClearPendingException(cx);
+        }
+
+        // Finite as a double, but not as a float.
+        rval.set(DoubleValue(1e300));
+        assert!(Finite::<f64>::from_jsval(cx, rval.handle(), ()).is_ok());
+        assert!(Finite::<f32>::from_jsval(cx, rval.handle(), ()).is_err());
+        assert!(JS_IsExceptionPending(cx));
+        JS_ClearPendingException(cx);
+
+        // `undefined` converts to NaN.
+        rval.set(UndefinedValue());
+        assert!(Finite::<f32>::from_jsval(cx, rval.handle(), ()).is_err());
+        JS_ClearPendingException(cx);
+    }
+}
@@ -0,0 +1,89 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+#[macro_use]
+extern crate mozjs;
+
+use mozjs::conversions::{ConversionBehavior, ConversionResult};
+use mozjs::function::{call, call_method, construct};
+use mozjs::jsapi::JSAutoRealm;
+use mozjs::jsapi::JS_ClearPendingException;
+use mozjs::jsapi::JS_NewGlobalObject;
+use mozjs::jsapi::OnNewGlobalHookOption;
+use mozjs::jsval::UndefinedValue;
+use mozjs::object::Object;
+use mozjs::rust::{HandleValue, JSEngine, RealmOptions, Runtime, SIMPLE_GLOBAL_CLASS};
+
+use std::ptr;
+
+#[test]
+fn function() {
+    let engine = JSEngine::init().unwrap();
+    let rt = Runtime::new(engine.handle());
+    let cx = rt.cx();
+    let h_option = OnNewGlobalHookOption::FireOnNewGlobalHook;
+    let c_option = RealmOptions::default();
+
+    unsafe {
+        rooted!(in(cx) let global = JS_NewGlobalObject(
+            cx,
+            &SIMPLE_GLOBAL_CLASS,
+            ptr::null_mut(),
+            h_option,
+            &*c_option,
+        ));
+        let _ac = JSAutoRealm::new(cx, global.get());
+
+        rooted!(in(cx) let mut func = UndefinedValue());
+        assert!(rt
+            .evaluate_script(
+                global.handle(),
+                "function Point(x, y) { this.x = x; this.y = y; }
+                 Point.prototype.norm1 = function() { return Math.abs(this.x) + Math.abs(this.y); };
+                 (function(a, b, c) { return `${a}:${b}:${c}:${arguments.length}`; })",
+                "test",
+                1,
+                func.handle_mut()
+            )
+            .is_ok());
+
+        match call::<String, _>(
+            cx,
+            HandleValue::undefined(),
+            func.handle(),
+            (1, true, "x".to_owned()),
+            (),
+        ) {
+            Ok(ConversionResult::Success(result)) => assert_eq!(result, "1:true:x:3"),
+            _ => panic!("calling the function failed"),
+        }
+        match call::<String, _>(cx, HandleValue::undefined(), func.handle(), &[7u32][..], ()) {
+            Ok(ConversionResult::Success(result)) => {
+                assert_eq!(result, "7:undefined:undefined:1")
+            }
+            _ => panic!("calling the function with a slice failed"),
+        }
+
+        rooted!(in(cx) let mut point = UndefinedValue());
+        Object::new(global.handle())
+            .get_value(cx, "Point", point.handle_mut())
+            .unwrap();
+        rooted!(in(cx) let ctor = point.to_object());
+        rooted!(in(cx) let instance = construct(cx, ctor.handle(), (-3, 4)));
+        assert!(!instance.is_null());
+        match call_method::<i32, _, _>(
+            cx,
+            instance.handle(),
+            "norm1",
+            (),
+            ConversionBehavior::Default,
+        ) {
+            Ok(ConversionResult::Success(norm)) => assert_eq!(norm, 7),
+            _ => panic!("calling norm1 failed"),
+        }
+
+        assert!(call_method::<(), _, _>(cx, instance.handle(), "missing", (), ()).is_err());
+        JS_ClearPendingException(cx);
+    }
+}
@@ -0,0 +1,202 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+#[macro_use]
+extern crate mozjs;
+
+use mozjs::conversions::ConversionBehavior;
+use mozjs::error::JSError;
+use mozjs::iterator::{new_iterator, ForOfIter};
+use mozjs::jsapi::JSAutoRealm;
+use mozjs::jsapi::JS_ClearPendingException;
+use mozjs::jsapi::JS_IsExceptionPending;
+use mozjs::jsapi::JS_NewGlobalObject;
+use mozjs::jsapi::JS_SetProperty;
+use mozjs::jsapi::OnNewGlobalHookOption;
+use mozjs::jsval::{ObjectValue, UndefinedValue};
+use mozjs::rust::{JSEngine, RealmOptions, Runtime, SIMPLE_GLOBAL_CLASS};
+
+use std::cell::Cell;
+use std::ptr;
+use std::rc::Rc;
+
+/// Counts the elements taken from it, and records when it is dropped.
+struct Cursor {
+    next: u32,
+    dropped: Rc<Cell<bool>>,
+}
+
+impl Iterator for Cursor {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<u32> {
+        self.next += 1;
+        Some(self.next)
+    }
+}
+
+impl Drop for Cursor {
+    fn drop(&mut self) {
+        self.dropped.set(true);
+    }
+}
+
+#[test]
+fn for_of_iter() {
+    let engine = JSEngine::init().unwrap();
+    let rt = Runtime::new(engine.handle());
+    let cx = rt.cx();
+    let h_option = OnNewGlobalHookOption::FireOnNewGlobalHook;
+    let c_option = RealmOptions::default();
+
+    unsafe {
+        rooted!(in(cx) let global = JS_NewGlobalObject(
+            cx,
+            &SIMPLE_GLOBAL_CLASS,
+            ptr::null_mut(),
+            h_option,
+            &*c_option,
+        ));
+        let _ac = JSAutoRealm::new(cx, global.get());
+
+        rooted!(in(cx) let mut rval = UndefinedValue());
+        assert!(rt
+            .evaluate_script(
+                global.handle(),
+                "var closed = false;
+                 (function* () {
+                     try { for (let i = 0; ; i++) yield i; } finally { closed = true; }
+                 })()",
+                "test",
+                1,
+                rval.handle_mut()
+            )
+            .is_ok());
+        let first: Result<Vec<u32>, JSError> =
+            ForOfIter::<u32>::new(cx, rval.handle(), ConversionBehavior::Default)
+                .unwrap()
+                .take(3)
+                .collect();
+        assert_eq!(first, Ok(vec![0, 1, 2]));
+        assert!(rt
+            .evaluate_script(global.handle(), "closed", "test", 1, rval.handle_mut())
+            .is_ok());
+        assert!(rval.to_boolean());
+
+        assert!(rt
+            .evaluate_script(global.handle(), "[1, 2, 3]", "test", 1, rval.handle_mut())
+            .is_ok());
+        let all: Result<Vec<i32>, JSError> =
+            ForOfIter::new(cx, rval.handle(), ConversionBehavior::Default)
+                .unwrap()
+                .collect();
+        assert_eq!(all, Ok(vec![1, 2, 3]));
+
+        assert!(rt
+            .evaluate_script(
+                global.handle(),
+                "new Set(['a', 'b'])",
+                "test",
+                1,
+                rval.handle_mut()
+            )
+            .is_ok());
+        let mut strings = ForOfIter::<String>::new(cx, rval.handle(), ()).unwrap();
+        assert_eq!(strings.next(), Some(Ok("a".to_owned())));
+        assert!(strings.close().is_ok());
+
+        assert!(rt
+            .evaluate_script(
+                global.handle(),
+                "(function* () { yield 1; throw new Error('boom'); })()",
+                "test",
+                1,
+                rval.handle_mut()
+            )
+            .is_ok());
+        let mut failing =
+            ForOfIter::<i32>::new(cx, rval.handle(), ConversionBehavior::Default).unwrap();
+        assert_eq!(failing.next(), Some(Ok(1)));
+        assert_eq!(failing.next(), Some(Err(JSError::Exception)));
+        assert_eq!(failing.next(), None);
+        drop(failing);
+        assert!(JS_IsExceptionPending(cx));
+        JS_ClearPendingException(cx);
+
+        rooted!(in(cx) let number = UndefinedValue());
+        assert!(ForOfIter::<i32>::new(cx, number.handle(), ConversionBehavior::Default).is_err());
+        JS_ClearPendingException(cx);
+    }
+}
+
+#[test]
+fn rust_iterator() {
+    let engine = JSEngine::init().unwrap();
+    let rt = Runtime::new(engine.handle());
+    let cx = rt.cx();
+    let h_option = OnNewGlobalHookOption::FireOnNewGlobalHook;
+    let c_option = RealmOptions::default();
+
+    unsafe {
+        rooted!(in(cx) let global = JS_NewGlobalObject(
+            cx,
+            &SIMPLE_GLOBAL_CLASS,
+            ptr::null_mut(),
+            h_option,
+            &*c_option,
+        ));
+        let _ac = JSAutoRealm::new(cx, global.get());
+
+        rooted!(in(cx) let names = new_iterator(cx, vec!["a".to_owned(), "b".to_owned()]));
+        rooted!(in(cx) let names = ObjectValue(names.get()));
+        assert!(JS_SetProperty(
+            cx,
+            global.handle().into(),
+            b"names\0".as_ptr() as *const _,
+            names.handle().into(),
+        ));
+        rooted!(in(cx) let mut rval = UndefinedValue());
+        assert!(rt
+            .evaluate_script(
+                global.handle(),
+                "var all = [...names]; all.join() === 'a,b' && names.next().done",
+                "test",
+                1,
+                rval.handle_mut()
+            )
+            .is_ok());
+        assert!(rval.to_boolean());
+
+        let dropped = Rc::new(Cell::new(false));
+        let cursor = Cursor {
+            next: 0,
+            dropped: dropped.clone(),
+        };
+        rooted!(in(cx) let cursor = new_iterator(cx, cursor));
+        rooted!(in(cx) let cursor = ObjectValue(cursor.get()));
+        assert!(JS_SetProperty(
+            cx,
+            global.handle().into(),
+            b"cursor\0".as_ptr() as *const _,
+            cursor.handle().into(),
+        ));
+        assert!(rt
+            .evaluate_script(
+                global.handle(),
+                "var sum = 0; for (var n of cursor) { if (n > 3) break; sum += n; } sum",
+                "test",
+                1,
+                rval.handle_mut()
+            )
+            .is_ok());
+        assert_eq!(rval.to_int32(), 6);
+        assert!(dropped.get());
+
+        let values: Result<Vec<u32>, JSError> =
+            ForOfIter::new(cx, cursor.handle(), ConversionBehavior::Default)
+                .unwrap()
+                .collect();
+        assert_eq!(values, Ok(vec![]));
+    }
+}
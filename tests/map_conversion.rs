@@ -0,0 +1,149 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+#[macro_use]
+extern crate mozjs;
+
+use mozjs::conversions::{AsMap, AsSet, ConversionBehavior, ConversionResult};
+use mozjs::conversions::{FromJSValConvertible, ToJSValConvertible};
+use mozjs::jsapi::JSAutoRealm;
+use mozjs::jsapi::JS_ClearPendingException;
+use mozjs::jsapi::JS_NewGlobalObject;
+use mozjs::jsapi::JS_SetProperty;
+use mozjs::jsapi::OnNewGlobalHookOption;
+use mozjs::jsval::UndefinedValue;
+use mozjs::rust::{JSEngine, RealmOptions, Runtime, SIMPLE_GLOBAL_CLASS};
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::ptr;
+
+#[test]
+fn map_conversion() {
+    let engine = JSEngine::init().unwrap();
+    let rt = Runtime::new(engine.handle());
+    let cx = rt.cx();
+    let h_option = OnNewGlobalHookOption::FireOnNewGlobalHook;
+    let c_option = RealmOptions::default();
+
+    unsafe {
+        rooted!(in(cx) let global = JS_NewGlobalObject(
+            cx,
+            &SIMPLE_GLOBAL_CLASS,
+            ptr::null_mut(),
+            h_option,
+            &*c_option,
+        ));
+        let _ac = JSAutoRealm::new(cx, global.get());
+
+        rooted!(in(cx) let mut rval = UndefinedValue());
+        assert!(rt
+            .evaluate_script(
+                global.handle(),
+                "var config = Object.create({ inherited: 1 }, { hidden: { value: 2 } });
+                 config.port = 80; config[3] = 4; config;",
+                "test",
+                1,
+                rval.handle_mut()
+            )
+            .is_ok());
+        match HashMap::<String, u16>::from_jsval(cx, rval.handle(), ConversionBehavior::Default) {
+            Ok(ConversionResult::Success(config)) => {
+                assert_eq!(config.len(), 2);
+                assert_eq!(config["port"], 80);
+                assert_eq!(config["3"], 4);
+            }
+            _ => panic!("converting the record failed"),
+        }
+
+        let mut record = BTreeMap::new();
+        record.insert("a".to_owned(), 1);
+        record.insert("b".to_owned(), 2);
+        record.to_jsval(cx, rval.handle_mut());
+        assert!(JS_SetProperty(
+            cx,
+            global.handle().into(),
+            b"record\0".as_ptr() as *const _,
+            rval.handle().into(),
+        ));
+        assert!(rt
+            .evaluate_script(
+                global.handle(),
+                "Object.keys(record).join() === 'a,b' && record.b === 2",
+                "test",
+                1,
+                rval.handle_mut()
+            )
+            .is_ok());
+        assert!(rval.to_boolean());
+
+        let mut map = BTreeMap::new();
+        map.insert(1, "one".to_owned());
+        map.insert(2, "two".to_owned());
+        AsMap(map.clone()).to_jsval(cx, rval.handle_mut());
+        assert!(JS_SetProperty(
+            cx,
+            global.handle().into(),
+            b"map\0".as_ptr() as *const _,
+            rval.handle().into(),
+        ));
+        assert!(rt
+            .evaluate_script(
+                global.handle(),
+                "map instanceof Map && map.get(2) === 'two' && map.size === 2 ? map : null",
+                "test",
+                1,
+                rval.handle_mut()
+            )
+            .is_ok());
+        match AsMap::<BTreeMap<i32, String>>::from_jsval(
+            cx,
+            rval.handle(),
+            (ConversionBehavior::Default, ()),
+        ) {
+            Ok(ConversionResult::Success(AsMap(round_trip))) => assert_eq!(round_trip, map),
+            _ => panic!("converting the Map failed"),
+        }
+
+        assert!(rt
+            .evaluate_script(
+                global.handle(),
+                "new Set(['x', 'y', 'x'])",
+                "test",
+                1,
+                rval.handle_mut()
+            )
+            .is_ok());
+        match AsSet::<BTreeSet<String>>::from_jsval(cx, rval.handle(), ()) {
+            Ok(ConversionResult::Success(AsSet(set))) => {
+                assert_eq!(set.into_iter().collect::<Vec<_>>(), vec!["x", "y"])
+            }
+            _ => panic!("converting the Set failed"),
+        }
+
+        assert!(rt
+            .evaluate_script(
+                global.handle(),
+                "new Map([['k', 'not a number']])",
+                "test",
+                1,
+                rval.handle_mut()
+            )
+            .is_ok());
+        assert!(AsMap::<HashMap<String, i32>>::from_jsval(
+            cx,
+            rval.handle(),
+            ((), ConversionBehavior::EnforceRange),
+        )
+        .is_err());
+        JS_ClearPendingException(cx);
+
+        assert!(rt
+            .evaluate_script(global.handle(), "[1, 2]", "test", 1, rval.handle_mut())
+            .is_ok());
+        match AsSet::<BTreeSet<String>>::from_jsval(cx, rval.handle(), ()) {
+            Ok(ConversionResult::Failure(_)) => {}
+            _ => panic!("an array is not a Set"),
+        }
+    }
+}
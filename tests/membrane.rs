@@ -0,0 +1,154 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+#[macro_use]
+extern crate mozjs;
+
+use mozjs::jsapi::JSAutoRealm;
+use mozjs::jsapi::JSContext;
+use mozjs::jsapi::JS_DefineProperty;
+use mozjs::jsapi::JS_NewGlobalObject;
+use mozjs::jsapi::OnNewGlobalHookOption;
+use mozjs::jsval::UndefinedValue;
+use mozjs::membrane::{Access, Membrane, MembranePolicy};
+use mozjs::property::PropertyKey;
+use mozjs::rust::{HandleObject, JSEngine, RealmOptions, Runtime, SIMPLE_GLOBAL_CLASS};
+
+use std::ptr;
+
+/// Hides `secret` and makes everything else read-only.
+struct ReadOnlyPolicy;
+
+impl MembranePolicy for ReadOnlyPolicy {
+    fn allow_property(
+        &self,
+        _cx: *mut JSContext,
+        _target: HandleObject,
+        key: &PropertyKey,
+        access: Access,
+    ) -> bool {
+        access == Access::Read && *key != PropertyKey::from("secret")
+    }
+
+    fn allow_call(&self, _cx: *mut JSContext, _target: HandleObject) -> bool {
+        true
+    }
+}
+
+/// Allows everything.
+struct OpenPolicy;
+
+impl MembranePolicy for OpenPolicy {
+    fn allow_property(
+        &self,
+        _cx: *mut JSContext,
+        _target: HandleObject,
+        _key: &PropertyKey,
+        _access: Access,
+    ) -> bool {
+        true
+    }
+
+    fn allow_call(&self, _cx: *mut JSContext, _target: HandleObject) -> bool {
+        true
+    }
+}
+
+#[test]
+fn membrane() {
+    let engine = JSEngine::init().unwrap();
+    let rt = Runtime::new(engine.handle());
+    let cx = rt.cx();
+    let h_option = OnNewGlobalHookOption::FireOnNewGlobalHook;
+    let c_option = RealmOptions::default();
+
+    unsafe {
+        rooted!(in(cx) let global = JS_NewGlobalObject(
+            cx,
+            &SIMPLE_GLOBAL_CLASS,
+            ptr::null_mut(),
+            h_option,
+            &*c_option,
+        ));
+        let _ac = JSAutoRealm::new(cx, global.get());
+
+        let check = |script: &str| {
+            rooted!(in(cx) let mut rval = UndefinedValue());
+            assert!(rt
+                .evaluate_script(global.handle(), script, "test", 1, rval.handle_mut())
+                .is_ok());
+            assert!(rval.get().to_boolean(), "{}", script);
+        };
+
+        rooted!(in(cx) let mut host = UndefinedValue());
+        assert!(rt
+            .evaluate_script(
+                global.handle(),
+                "({ secret: 1, visible: 2, nested: { x: 3, secret: 4 }, \
+                    f(o) { return { arg: o, self: this }; } })",
+                "test",
+                1,
+                host.handle_mut(),
+            )
+            .is_ok());
+
+        let membrane = Membrane::new(ReadOnlyPolicy);
+        rooted!(in(cx) let mut plugin = host.get());
+        membrane.wrap_value(cx, plugin.handle_mut()).unwrap();
+        assert!(JS_DefineProperty(
+            cx,
+            global.handle().into(),
+            b"plugin\0".as_ptr() as *const _,
+            plugin.handle().into(),
+            0,
+        ));
+
+        check("plugin.visible === 2 && plugin.nested.x === 3");
+        check("plugin.secret === undefined && plugin.nested.secret === undefined");
+        check("!('secret' in plugin) && Object.keys(plugin).join() === 'visible,nested,f'");
+        check(
+            "(function () { 'use strict'; \
+               try { plugin.visible = 5; return false; } catch (e) { return e instanceof TypeError; } \
+             })() && plugin.visible === 2",
+        );
+        check("var r = plugin.f(plugin.nested); r.arg.x === 3 && r.self.visible === 2");
+        check("plugin.nested === plugin.nested && r.self === plugin && r.arg === plugin.nested");
+
+        rooted!(in(cx) let mut again = host.get());
+        membrane.wrap_value(cx, again.handle_mut()).unwrap();
+        assert_eq!(again.get().to_object(), plugin.get().to_object());
+
+        let open = Membrane::new(OpenPolicy);
+        rooted!(in(cx) let mut other = host.get());
+        open.wrap_value(cx, other.handle_mut()).unwrap();
+        assert!(other.get().to_object() != plugin.get().to_object());
+        assert!(JS_DefineProperty(
+            cx,
+            global.handle().into(),
+            b"other\0".as_ptr() as *const _,
+            other.handle().into(),
+            0,
+        ));
+        check(
+            "Object.defineProperty(other, 'visible', { enumerable: false }); \
+             var d = Object.getOwnPropertyDescriptor(other, 'visible'); \
+             d.value === 2 && d.writable && d.configurable && !d.enumerable",
+        );
+        check(
+            "Object.defineProperty(other, 'ro', { value: 1, writable: false }); \
+             other.ro = 2; \
+             other.ro === 1 && (function () { 'use strict'; \
+               try { other.ro = 3; return false; } catch (e) { return e instanceof TypeError; } \
+             })()",
+        );
+        check(
+            "var child = Object.create(other); child.visible = 9; \
+             child.hasOwnProperty('visible') && child.visible === 9 && other.visible === 2",
+        );
+
+        membrane.revoke();
+        check("try { plugin.visible; false } catch (e) { e instanceof TypeError }");
+        check("try { r.arg.x; false } catch (e) { e instanceof TypeError }");
+    }
+}
@@ -0,0 +1,193 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+#[macro_use]
+extern crate mozjs;
+
+use mozjs::conversions::{ConversionBehavior, ConversionResult};
+use mozjs::jsapi::JSAutoRealm;
+use mozjs::jsapi::JS_ClearPendingException;
+use mozjs::jsapi::JS_IsExceptionPending;
+use mozjs::jsapi::JS_NewGlobalObject;
+use mozjs::jsapi::OnNewGlobalHookOption;
+use mozjs::jsapi::{JSPROP_ENUMERATE, JSPROP_PERMANENT, JSPROP_READONLY};
+use mozjs::jsval::{Int32Value, UndefinedValue};
+use mozjs::object::Object;
+use mozjs::property::{PropertyDescriptor, PropertyKey};
+use mozjs::rust::{JSEngine, RealmOptions, Runtime, SIMPLE_GLOBAL_CLASS};
+
+use std::ptr;
+
+#[test]
+fn object() {
+    let engine = JSEngine::init().unwrap();
+    let rt = Runtime::new(engine.handle());
+    let cx = rt.cx();
+    let h_option = OnNewGlobalHookOption::FireOnNewGlobalHook;
+    let c_option = RealmOptions::default();
+
+    unsafe {
+        rooted!(in(cx) let global = JS_NewGlobalObject(
+            cx,
+            &SIMPLE_GLOBAL_CLASS,
+            ptr::null_mut(),
+            h_option,
+            &*c_option,
+        ));
+        let _ac = JSAutoRealm::new(cx, global.get());
+
+        rooted!(in(cx) let mut rval = UndefinedValue());
+        assert!(rt
+            .evaluate_script(
+                global.handle(),
+                "({ port: 8080, get doubled() { return this.port * 2; }, __proto__: { inherited: 1 } })",
+                "test",
+                1,
+                rval.handle_mut()
+            )
+            .is_ok());
+        rooted!(in(cx) let obj = rval.to_object());
+        let obj = Object::new(obj.handle());
+
+        match obj.get::<u16, _>(cx, "port", ConversionBehavior::EnforceRange) {
+            Ok(ConversionResult::Success(port)) => assert_eq!(port, 8080),
+            _ => panic!("reading port failed"),
+        }
+        match obj.get::<i32, _>(cx, "doubled", ConversionBehavior::Default) {
+            Ok(ConversionResult::Success(doubled)) => assert_eq!(doubled, 16160),
+            _ => panic!("reading doubled failed"),
+        }
+
+        assert!(obj.has(cx, "inherited").unwrap());
+        assert!(!obj.has_own(cx, "inherited").unwrap());
+        assert!(!obj.has(cx, "missing").unwrap());
+
+        obj.set(cx, "name", "server").unwrap();
+        obj.set(cx, 3u32, &true).unwrap();
+        assert!(obj.has_own(cx, PropertyKey::Index(3)).unwrap());
+        match obj.get::<String, _>(cx, "name", ()) {
+            Ok(ConversionResult::Success(name)) => assert_eq!(name, "server"),
+            _ => panic!("reading name failed"),
+        }
+
+        assert!(obj.delete(cx, "name").unwrap());
+        assert!(!obj.has_own(cx, "name").unwrap());
+
+        obj.define(
+            cx,
+            "fixed",
+            &1i32,
+            (JSPROP_ENUMERATE | JSPROP_READONLY | JSPROP_PERMANENT) as u32,
+        )
+        .unwrap();
+        assert!(!obj.delete(cx, "fixed").unwrap());
+        assert!(obj
+            .define(cx, "fixed", &2i32, JSPROP_ENUMERATE as u32)
+            .is_err());
+        assert!(JS_IsExceptionPending(cx));
+        JS_ClearPendingException(cx);
+    }
+}
+
+#[test]
+fn property_descriptor() {
+    let engine = JSEngine::init().unwrap();
+    let rt = Runtime::new(engine.handle());
+    let cx = rt.cx();
+    let h_option = OnNewGlobalHookOption::FireOnNewGlobalHook;
+    let c_option = RealmOptions::default();
+
+    unsafe {
+        rooted!(in(cx) let global = JS_NewGlobalObject(
+            cx,
+            &SIMPLE_GLOBAL_CLASS,
+            ptr::null_mut(),
+            h_option,
+            &*c_option,
+        ));
+        let _ac = JSAutoRealm::new(cx, global.get());
+        let global_obj = Object::new(global.handle());
+
+        global_obj
+            .define_property(
+                cx,
+                "answer",
+                &PropertyDescriptor::Data {
+                    value: Int32Value(42),
+                    writable: false,
+                    enumerable: true,
+                    configurable: false,
+                },
+            )
+            .unwrap();
+
+        rooted!(in(cx) let mut rval = UndefinedValue());
+        assert!(rt
+            .evaluate_script(
+                global.handle(),
+                "var d = Object.getOwnPropertyDescriptor(this, 'answer');
+                 d.value === 42 && !d.writable && d.enumerable && !d.configurable",
+                "test",
+                1,
+                rval.handle_mut()
+            )
+            .is_ok());
+        assert!(rval.to_boolean());
+
+        assert!(rt
+            .evaluate_script(
+                global.handle(),
+                "Object.defineProperty(this, 'computed', { get() { return 1; } }); \
+                 ({ value: 'x', enumerable: true })",
+                "test",
+                1,
+                rval.handle_mut()
+            )
+            .is_ok());
+        match PropertyDescriptor::from_object(cx, rval.handle()).unwrap() {
+            PropertyDescriptor::Data {
+                value,
+                writable,
+                enumerable,
+                configurable,
+            } => {
+                assert!(value.is_string());
+                assert!(!writable && enumerable && !configurable);
+            }
+            PropertyDescriptor::Accessor { .. } => panic!("expected a data descriptor"),
+        }
+
+        match global_obj
+            .get_own_property_descriptor(cx, "computed")
+            .unwrap()
+        {
+            Some(PropertyDescriptor::Accessor {
+                get,
+                set,
+                enumerable,
+                configurable,
+            }) => {
+                assert!(!get.is_null() && set.is_null());
+                assert!(!enumerable && !configurable);
+            }
+            _ => panic!("expected an accessor descriptor"),
+        }
+        assert!(global_obj
+            .get_own_property_descriptor(cx, "missing")
+            .unwrap()
+            .is_none());
+
+        assert!(rt
+            .evaluate_script(
+                global.handle(),
+                "({ value: 1, get() {} })",
+                "test",
+                1,
+                rval.handle_mut()
+            )
+            .is_ok());
+        assert!(PropertyDescriptor::from_object(cx, rval.handle()).is_err());
+        JS_ClearPendingException(cx);
+    }
+}
@@ -0,0 +1,94 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+#[macro_use]
+extern crate mozjs;
+
+use mozjs::jsapi::GCReason;
+use mozjs::jsapi::JSAutoRealm;
+use mozjs::jsapi::JSObject;
+use mozjs::jsapi::JSTracer;
+use mozjs::jsapi::JS_NewGlobalObject;
+use mozjs::jsapi::JS_NewPlainObject;
+use mozjs::jsapi::OnNewGlobalHookOption;
+use mozjs::jsapi::JS_GC;
+use mozjs::owned::new_object_with_traced_data;
+use mozjs::owned::{get_object_data, new_object_with_data, new_object_with_data_and_size};
+use mozjs::rust::{CustomTrace, JSEngine, RealmOptions, Runtime, SIMPLE_GLOBAL_CLASS};
+
+use std::cell::Cell;
+use std::ptr;
+use std::rc::Rc;
+
+struct DropFlag(Rc<Cell<bool>>);
+
+impl Drop for DropFlag {
+    fn drop(&mut self) {
+        self.0.set(true);
+    }
+}
+
+struct Traced {
+    obj: *mut JSObject,
+}
+
+unsafe impl CustomTrace for Traced {
+    fn trace(&self, trc: *mut JSTracer) {
+        self.obj.trace(trc);
+    }
+}
+
+#[test]
+fn owned() {
+    let engine = JSEngine::init().unwrap();
+    let rt = Runtime::new(engine.handle());
+    let cx = rt.cx();
+    let h_option = OnNewGlobalHookOption::FireOnNewGlobalHook;
+    let c_option = RealmOptions::default();
+
+    unsafe {
+        rooted!(in(cx) let global = JS_NewGlobalObject(
+            cx,
+            &SIMPLE_GLOBAL_CLASS,
+            ptr::null_mut(),
+            h_option,
+            &*c_option,
+        ));
+        let _ac = JSAutoRealm::new(cx, global.get());
+
+        let dropped = Rc::new(Cell::new(false));
+        {
+            rooted!(in(cx) let obj = new_object_with_data(cx, Box::new(DropFlag(dropped.clone()))));
+            assert!(!obj.is_null());
+            assert!(get_object_data::<DropFlag>(obj.get()).is_some());
+            assert!(get_object_data::<u32>(obj.get()).is_none());
+
+            rooted!(in(cx) let plain = JS_NewPlainObject(cx));
+            assert!(get_object_data::<DropFlag>(plain.get()).is_none());
+
+            JS_GC(cx, GCReason::API);
+            assert!(!dropped.get());
+        }
+        JS_GC(cx, GCReason::API);
+        assert!(dropped.get());
+
+        let buffer = vec![7u8; 1 << 16];
+        let size = buffer.capacity();
+        rooted!(in(cx) let sized = new_object_with_data_and_size(cx, Box::new(buffer), size));
+        assert_eq!(
+            get_object_data::<Vec<u8>>(sized.get()).unwrap().len(),
+            1 << 16
+        );
+
+        rooted!(in(cx) let inner = JS_NewPlainObject(cx));
+        rooted!(in(cx) let obj = new_object_with_traced_data(
+            cx,
+            Box::new(Traced { obj: inner.get() }),
+        ));
+        JS_GC(cx, GCReason::API);
+        let traced = get_object_data::<Traced>(obj.get()).unwrap();
+        assert!(!traced.obj.is_null());
+        assert!(get_object_data::<Traced>(traced.obj).is_none());
+    }
+}
@@ -0,0 +1,93 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+#[macro_use]
+extern crate mozjs;
+
+use mozjs::jsapi::jsid;
+use mozjs::jsapi::JSAutoRealm;
+use mozjs::jsapi::JS_GetPropertyById;
+use mozjs::jsapi::JS_NewGlobalObject;
+use mozjs::jsapi::OnNewGlobalHookOption;
+use mozjs::jsval::UndefinedValue;
+use mozjs::property::{interned_id, PropertyKey};
+use mozjs::rust::{JSEngine, RealmOptions, Runtime, SIMPLE_GLOBAL_CLASS};
+use mozjs::symbol::{symbol_for, Symbol};
+
+use std::ptr;
+
+#[test]
+fn property_key() {
+    let engine = JSEngine::init().unwrap();
+    let rt = Runtime::new(engine.handle());
+    let cx = rt.cx();
+    let h_option = OnNewGlobalHookOption::FireOnNewGlobalHook;
+    let c_option = RealmOptions::default();
+
+    unsafe {
+        rooted!(in(cx) let global = JS_NewGlobalObject(
+            cx,
+            &SIMPLE_GLOBAL_CLASS,
+            ptr::null_mut(),
+            h_option,
+            &*c_option,
+        ));
+        let _ac = JSAutoRealm::new(cx, global.get());
+
+        rooted!(in(cx) let symbol = symbol_for(cx, "key"));
+        let keys = vec![
+            PropertyKey::Index(0),
+            PropertyKey::Index(i32::max_value() as u32 + 1),
+            PropertyKey::Index(u32::max_value() - 1),
+            PropertyKey::from("name"),
+            PropertyKey::from("4294967295"),
+            PropertyKey::from("01"),
+            Symbol::new(symbol.handle()).to_property_key(),
+        ];
+        for key in keys {
+            rooted!(in(cx) let mut id: jsid);
+            key.to_id(cx, id.handle_mut()).unwrap();
+            assert_eq!(PropertyKey::from_id(cx, id.handle()).unwrap(), key);
+        }
+
+        rooted!(in(cx) let mut id: jsid);
+        PropertyKey::from("7").to_id(cx, id.handle_mut()).unwrap();
+        assert_eq!(
+            PropertyKey::from_id(cx, id.handle()).unwrap(),
+            PropertyKey::Index(7)
+        );
+
+        rooted!(in(cx) let mut rval = UndefinedValue());
+        assert!(rt
+            .evaluate_script(
+                global.handle(),
+                "['a', 'b', 'c']",
+                "test",
+                1,
+                rval.handle_mut()
+            )
+            .is_ok());
+        rooted!(in(cx) let array = rval.to_object());
+        for &(name, expected) in &[("length", 3), ("length", 3), ("1", -1)] {
+            rooted!(in(cx) let mut id: jsid);
+            interned_id(cx, name, id.handle_mut()).unwrap();
+            rooted!(in(cx) let mut value = UndefinedValue());
+            assert!(JS_GetPropertyById(
+                cx,
+                array.handle().into(),
+                id.handle().into(),
+                value.handle_mut().into(),
+            ));
+            if expected < 0 {
+                assert_eq!(
+                    PropertyKey::from_id(cx, id.handle()).unwrap(),
+                    PropertyKey::Index(1)
+                );
+                assert!(value.is_string());
+            } else {
+                assert_eq!(value.to_int32(), expected);
+            }
+        }
+    }
+}
@@ -0,0 +1,268 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+#[macro_use]
+extern crate mozjs;
+
+use mozjs::jsapi::JSAutoRealm;
+use mozjs::jsapi::JSContext;
+use mozjs::jsapi::JSObject;
+use mozjs::jsapi::JSTracer;
+use mozjs::jsapi::JS_DefineProperty;
+use mozjs::jsapi::JS_NewGlobalObject;
+use mozjs::jsapi::OnNewGlobalHookOption;
+use mozjs::jsval::{Int32Value, ObjectValue, UndefinedValue};
+use mozjs::property::{PropertyDescriptor, PropertyKey};
+use mozjs::proxy::{get_proxy_handler, new_proxy_object, ProxyHandler};
+use mozjs::rust::SIMPLE_GLOBAL_CLASS;
+use mozjs::rust::{CustomTrace, HandleObject, JSEngine, RealmOptions, Runtime};
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ptr;
+
+/// Exposes a list of integers as indexed properties.
+struct ListHandler {
+    items: RefCell<Vec<i32>>,
+}
+
+impl ProxyHandler for ListHandler {
+    fn get_own_property_descriptor(
+        &self,
+        _cx: *mut JSContext,
+        _proxy: HandleObject,
+        key: &PropertyKey,
+    ) -> Result<Option<PropertyDescriptor>, ()> {
+        Ok(match *key {
+            PropertyKey::Index(i) => self
+                .items
+                .borrow()
+                .get(i as usize)
+                .map(|&item| PropertyDescriptor::value(Int32Value(item))),
+            _ => None,
+        })
+    }
+
+    fn define_property(
+        &self,
+        _cx: *mut JSContext,
+        _proxy: HandleObject,
+        key: &PropertyKey,
+        desc: &PropertyDescriptor,
+    ) -> Result<bool, ()> {
+        let mut items = self.items.borrow_mut();
+        match (key, desc) {
+            (&PropertyKey::Index(i), &PropertyDescriptor::Data { value, .. })
+                if (i as usize) <= items.len() && value.is_int32() =>
+            {
+                if i as usize == items.len() {
+                    items.push(value.to_int32());
+                } else {
+                    items[i as usize] = value.to_int32();
+                }
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    fn own_keys(&self, _cx: *mut JSContext, _proxy: HandleObject) -> Result<Vec<PropertyKey>, ()> {
+        Ok((0..self.items.borrow().len() as u32)
+            .map(PropertyKey::Index)
+            .collect())
+    }
+
+    fn delete(
+        &self,
+        _cx: *mut JSContext,
+        _proxy: HandleObject,
+        key: &PropertyKey,
+    ) -> Result<bool, ()> {
+        let mut items = self.items.borrow_mut();
+        match *key {
+            PropertyKey::Index(i) if i as usize + 1 == items.len() => {
+                items.pop();
+                Ok(true)
+            }
+            PropertyKey::Index(i) => Ok(i as usize >= items.len()),
+            _ => Ok(true),
+        }
+    }
+}
+
+/// Has no properties and refuses new ones.
+struct EmptyHandler;
+
+impl ProxyHandler for EmptyHandler {}
+
+/// Stores string-keyed properties, as an ordinary object would.
+struct MapHandler {
+    props: RefCell<HashMap<String, PropertyDescriptor>>,
+}
+
+impl ProxyHandler for MapHandler {
+    fn get_own_property_descriptor(
+        &self,
+        _cx: *mut JSContext,
+        _proxy: HandleObject,
+        key: &PropertyKey,
+    ) -> Result<Option<PropertyDescriptor>, ()> {
+        Ok(match *key {
+            PropertyKey::String(ref name) => self.props.borrow().get(name).cloned(),
+            _ => None,
+        })
+    }
+
+    fn define_property(
+        &self,
+        _cx: *mut JSContext,
+        _proxy: HandleObject,
+        key: &PropertyKey,
+        desc: &PropertyDescriptor,
+    ) -> Result<bool, ()> {
+        match *key {
+            PropertyKey::String(ref name) => {
+                self.props.borrow_mut().insert(name.clone(), *desc);
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    fn own_keys(&self, _cx: *mut JSContext, _proxy: HandleObject) -> Result<Vec<PropertyKey>, ()> {
+        Ok(self
+            .props
+            .borrow()
+            .keys()
+            .map(|name| PropertyKey::String(name.clone()))
+            .collect())
+    }
+
+    fn trace(&self, trc: *mut JSTracer) {
+        for desc in self.props.borrow().values() {
+            desc.trace(trc);
+        }
+    }
+}
+
+#[test]
+fn proxy() {
+    let engine = JSEngine::init().unwrap();
+    let rt = Runtime::new(engine.handle());
+    let cx = rt.cx();
+    let h_option = OnNewGlobalHookOption::FireOnNewGlobalHook;
+    let c_option = RealmOptions::default();
+
+    unsafe {
+        rooted!(in(cx) let global = JS_NewGlobalObject(
+            cx,
+            &SIMPLE_GLOBAL_CLASS,
+            ptr::null_mut(),
+            h_option,
+            &*c_option,
+        ));
+        let _ac = JSAutoRealm::new(cx, global.get());
+
+        let handler = ListHandler {
+            items: RefCell::new(vec![1, 2, 3]),
+        };
+        rooted!(in(cx) let proto = ptr::null_mut::<JSObject>());
+        rooted!(in(cx) let list = new_proxy_object(cx, handler, proto.handle()));
+        assert!(!list.is_null());
+        rooted!(in(cx) let list = ObjectValue(list.get()));
+        assert!(JS_DefineProperty(
+            cx,
+            global.handle().into(),
+            b"list\0".as_ptr() as *const _,
+            list.handle().into(),
+            0,
+        ));
+
+        let check = |script: &str| {
+            rooted!(in(cx) let mut rval = UndefinedValue());
+            assert!(rt
+                .evaluate_script(global.handle(), script, "test", 1, rval.handle_mut())
+                .is_ok());
+            assert!(rval.get().to_boolean(), "{}", script);
+        };
+
+        check("list[0] === 1 && list[2] === 3 && list[3] === undefined");
+        check("1 in list && !(5 in list) && !('foo' in list)");
+        check("list[3] = 4; list[3] === 4");
+        check("list[1] = 20; list[1] === 20");
+        check("Object.keys(list).join() === '0,1,2,3'");
+        check("delete list[3] && !(3 in list)");
+        check(
+            "(function () { 'use strict'; \
+               try { list[7] = 1; return false; } catch (e) { return e instanceof TypeError; } \
+             })()",
+        );
+
+        rooted!(in(cx) let empty = new_proxy_object(cx, EmptyHandler, proto.handle()));
+        assert!(!empty.is_null());
+        get_proxy_handler::<EmptyHandler>(empty.get());
+        rooted!(in(cx) let empty = ObjectValue(empty.get()));
+        assert!(JS_DefineProperty(
+            cx,
+            global.handle().into(),
+            b"empty\0".as_ptr() as *const _,
+            empty.handle().into(),
+            0,
+        ));
+        check("empty.a = 1; !('a' in empty) && Object.keys(empty).length === 0");
+
+        let mut props = HashMap::new();
+        props.insert("x".to_owned(), PropertyDescriptor::value(Int32Value(0)));
+        let handler = MapHandler {
+            props: RefCell::new(props),
+        };
+        rooted!(in(cx) let map = new_proxy_object(cx, handler, proto.handle()));
+        assert!(!map.is_null());
+        rooted!(in(cx) let map = ObjectValue(map.get()));
+        assert!(JS_DefineProperty(
+            cx,
+            global.handle().into(),
+            b"map\0".as_ptr() as *const _,
+            map.handle().into(),
+            0,
+        ));
+
+        check(
+            "Object.defineProperty(map, 'x', { value: 1 }); \
+             var desc = Object.getOwnPropertyDescriptor(map, 'x'); \
+             desc.value === 1 && desc.writable && desc.enumerable && desc.configurable",
+        );
+        check(
+            "Object.defineProperty(map, 'x', { enumerable: false }); \
+             var desc = Object.getOwnPropertyDescriptor(map, 'x'); \
+             desc.value === 1 && desc.writable && !desc.enumerable && desc.configurable",
+        );
+        check(
+            "Object.defineProperty(map, 'y', { value: 2 }); \
+             var desc = Object.getOwnPropertyDescriptor(map, 'y'); \
+             desc.value === 2 && !desc.writable && !desc.enumerable && !desc.configurable",
+        );
+        check(
+            "map.x = 3; var desc = Object.getOwnPropertyDescriptor(map, 'x'); \
+             desc.value === 3 && desc.writable && !desc.enumerable && desc.configurable",
+        );
+        check(
+            "var child = Object.create(map); child.x = 4; child.z = 5; \
+             child.hasOwnProperty('x') && child.x === 4 && map.x === 3 && \
+             child.hasOwnProperty('z') && !('z' in map)",
+        );
+        check("var child = Object.create(map); child.y = 6; !child.hasOwnProperty('y')");
+        check(
+            "(function () { 'use strict'; \
+               try { map.y = 7; return false; } catch (e) { return /read-only/.test(e.message); } \
+             })() && map.y === 2",
+        );
+        check(
+            "Object.defineProperty(map, 'g', { get() { return 8; } }); \
+             (function () { 'use strict'; \
+               try { map.g = 9; return false; } catch (e) { return /getter-only/.test(e.message); } \
+             })() && map.g === 8",
+        );
+    }
+}
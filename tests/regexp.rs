@@ -0,0 +1,127 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+#[macro_use]
+extern crate mozjs;
+
+use mozjs::jsapi::JSAutoRealm;
+use mozjs::jsapi::JSObject;
+use mozjs::jsapi::JS_ClearPendingException;
+use mozjs::jsapi::JS_IsExceptionPending;
+use mozjs::jsapi::JS_NewGlobalObject;
+use mozjs::jsapi::JS_WrapObject;
+use mozjs::jsapi::OnNewGlobalHookOption;
+use mozjs::jsval::UndefinedValue;
+use mozjs::regexp::{Flags, JsRegExp};
+use mozjs::rust::{JSEngine, RealmOptions, Runtime, SIMPLE_GLOBAL_CLASS};
+
+use std::ptr;
+
+#[test]
+fn regexp() {
+    let engine = JSEngine::init().unwrap();
+    let rt = Runtime::new(engine.handle());
+    let cx = rt.cx();
+    let h_option = OnNewGlobalHookOption::FireOnNewGlobalHook;
+    let c_option = RealmOptions::default();
+
+    unsafe {
+        rooted!(in(cx) let global = JS_NewGlobalObject(
+            cx,
+            &SIMPLE_GLOBAL_CLASS,
+            ptr::null_mut(),
+            h_option,
+            &*c_option,
+        ));
+        let _ac = JSAutoRealm::new(cx, global.get());
+
+        let flags = "ug".parse::<Flags>().unwrap();
+        assert!(flags.contains(Flags::GLOBAL | Flags::UNICODE));
+        assert!(!flags.contains(Flags::STICKY));
+        assert_eq!(flags.to_string(), "gu");
+        assert!("gg".parse::<Flags>().is_err());
+        assert!("x".parse::<Flags>().is_err());
+
+        assert_eq!(
+            JsRegExp::check_syntax(cx, "a(b", Flags::NONE)
+                .unwrap()
+                .is_err(),
+            true
+        );
+        assert_eq!(
+            JsRegExp::check_syntax(cx, "a(b)", Flags::NONE).unwrap(),
+            Ok(())
+        );
+        assert!(!JS_IsExceptionPending(cx));
+
+        rooted!(in(cx) let mut re = ptr::null_mut::<JSObject>());
+        assert!(JsRegExp::new(cx, "a(b", Flags::NONE, re.handle_mut()).is_err());
+        assert!(JS_IsExceptionPending(cx));
+        JS_ClearPendingException(cx);
+
+        let re = JsRegExp::new(
+            cx,
+            r"(?<year>\d{4})-(?<month>\d{2})(-(\d{2}))?",
+            Flags::UNICODE | Flags::IGNORE_CASE,
+            re.handle_mut(),
+        )
+        .unwrap();
+        assert_eq!(re.flags(cx), Flags::IGNORE_CASE | Flags::UNICODE);
+        assert_eq!(
+            re.source(cx).unwrap(),
+            r"(?<year>\d{4})-(?<month>\d{2})(-(\d{2}))?"
+        );
+
+        assert!(re.test(cx, "on 2020-06", 0).unwrap());
+        assert!(!re.test(cx, "on 2020-06", 4).unwrap());
+        assert!(!re.test(cx, "on 2020-06", 100).unwrap());
+
+        let m = re.exec(cx, "🎉 2020-06!", 0).unwrap().unwrap();
+        assert_eq!(m.index, 3);
+        assert_eq!(m.get(0), Some("2020-06"));
+        assert_eq!(m.captures.len(), 5);
+        assert_eq!(m.get(3), None);
+        assert_eq!(m.name("year"), Some("2020"));
+        assert_eq!(m.name("month"), Some("06"));
+        assert_eq!(m.groups.len(), 2);
+        assert_eq!(re.exec(cx, "no dates", 0).unwrap(), None);
+
+        rooted!(in(cx) let mut rval = UndefinedValue());
+        assert!(rt
+            .evaluate_script(global.handle(), "/x/y", "test", 1, rval.handle_mut())
+            .is_ok());
+        assert!(JsRegExp::is_regexp(cx, rval.handle()).unwrap());
+        rooted!(in(cx) let obj = rval.to_object());
+        let re = JsRegExp::from_handle(cx, obj.handle()).unwrap();
+        assert_eq!(re.flags(cx), Flags::STICKY);
+        assert!(re.test(cx, "ax", 1).unwrap());
+        assert!(!re.test(cx, "ax", 0).unwrap());
+
+        assert!(rt
+            .evaluate_script(global.handle(), "({})", "test", 1, rval.handle_mut())
+            .is_ok());
+        assert!(!JsRegExp::is_regexp(cx, rval.handle()).unwrap());
+
+        rooted!(in(cx) let other = JS_NewGlobalObject(
+            cx,
+            &SIMPLE_GLOBAL_CLASS,
+            ptr::null_mut(),
+            OnNewGlobalHookOption::FireOnNewGlobalHook,
+            &*RealmOptions::default(),
+        ));
+        rooted!(in(cx) let mut wrapper = ptr::null_mut::<JSObject>());
+        {
+            let _ac = JSAutoRealm::new(cx, other.get());
+            assert!(rt
+                .evaluate_script(other.handle(), "/b+/", "test", 1, rval.handle_mut())
+                .is_ok());
+            wrapper.set(rval.to_object());
+        }
+        assert!(JS_WrapObject(cx, wrapper.handle_mut().into()));
+        let re = JsRegExp::from_handle(cx, wrapper.handle()).unwrap();
+        let m = re.exec(cx, "abbc", 0).unwrap().unwrap();
+        assert_eq!((m.index, m.get(0)), (1, Some("bb")));
+        assert!(!re.test(cx, "ac", 0).unwrap());
+    }
+}
@@ -0,0 +1,89 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+#[macro_use]
+extern crate mozjs;
+
+use mozjs::conversions::ConversionBehavior;
+use mozjs::conversions::ConversionResult;
+use mozjs::jsapi::JSAutoRealm;
+use mozjs::jsapi::JSClass;
+use mozjs::jsapi::JS_NewGlobalObject;
+use mozjs::jsapi::JS_NewObject;
+use mozjs::jsapi::OnNewGlobalHookOption;
+use mozjs::jsapi::JSCLASS_RESERVED_SLOTS_SHIFT;
+use mozjs::rust::{get_reserved_slot, get_reserved_slot_private, reserved_slot_count};
+use mozjs::rust::{set_reserved_slot, set_reserved_slot_private};
+use mozjs::rust::{JSEngine, RealmOptions, Runtime, SIMPLE_GLOBAL_CLASS};
+use mozjs::JSCLASS_RESERVED_SLOTS_MASK;
+
+use std::ptr;
+
+static TWO_SLOT_CLASS: JSClass = JSClass {
+    name: b"TwoSlots\0" as *const u8 as *const _,
+    flags: (2 & JSCLASS_RESERVED_SLOTS_MASK) << JSCLASS_RESERVED_SLOTS_SHIFT,
+    cOps: ptr::null(),
+    spec: ptr::null(),
+    ext: ptr::null(),
+    oOps: ptr::null(),
+};
+
+#[test]
+fn reserved_slot() {
+    let engine = JSEngine::init().unwrap();
+    let rt = Runtime::new(engine.handle());
+    let cx = rt.cx();
+    let h_option = OnNewGlobalHookOption::FireOnNewGlobalHook;
+    let c_option = RealmOptions::default();
+
+    unsafe {
+        rooted!(in(cx) let global = JS_NewGlobalObject(
+            cx,
+            &SIMPLE_GLOBAL_CLASS,
+            ptr::null_mut(),
+            h_option,
+            &*c_option,
+        ));
+        let _ac = JSAutoRealm::new(cx, global.get());
+
+        assert_eq!(reserved_slot_count(&TWO_SLOT_CLASS), 2);
+        rooted!(in(cx) let obj = JS_NewObject(cx, &TWO_SLOT_CLASS));
+
+        set_reserved_slot(cx, obj.handle(), 0, &42i32);
+        match get_reserved_slot::<i32>(cx, obj.handle(), 0, ConversionBehavior::Default) {
+            Ok(ConversionResult::Success(value)) => assert_eq!(value, 42),
+            _ => panic!("reading reserved slot 0 failed"),
+        }
+
+        let data = Box::into_raw(Box::new(7u64));
+        assert!(get_reserved_slot_private::<u64>(obj.get(), 1).is_null());
+        set_reserved_slot_private(obj.get(), 1, data);
+        assert_eq!(get_reserved_slot_private::<u64>(obj.get(), 1), data);
+        drop(Box::from_raw(data));
+    }
+}
+
+#[test]
+#[should_panic]
+fn reserved_slot_out_of_range() {
+    let engine = JSEngine::init().unwrap();
+    let rt = Runtime::new(engine.handle());
+    let cx = rt.cx();
+    let h_option = OnNewGlobalHookOption::FireOnNewGlobalHook;
+    let c_option = RealmOptions::default();
+
+    unsafe {
+        rooted!(in(cx) let global = JS_NewGlobalObject(
+            cx,
+            &SIMPLE_GLOBAL_CLASS,
+            ptr::null_mut(),
+            h_option,
+            &*c_option,
+        ));
+        let _ac = JSAutoRealm::new(cx, global.get());
+
+        rooted!(in(cx) let obj = JS_NewObject(cx, &TWO_SLOT_CLASS));
+        set_reserved_slot(cx, obj.handle(), 2, &42i32);
+    }
+}
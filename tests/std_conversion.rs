@@ -0,0 +1,131 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+#[macro_use]
+extern crate mozjs;
+
+use mozjs::conversions::{ConversionBehavior, ConversionResult};
+use mozjs::conversions::{FromJSValConvertible, ToJSValConvertible};
+use mozjs::jsapi::JSAutoRealm;
+use mozjs::jsapi::JS_NewGlobalObject;
+use mozjs::jsapi::JS_SetProperty;
+use mozjs::jsapi::OnNewGlobalHookOption;
+use mozjs::jsval::UndefinedValue;
+use mozjs::rust::{JSEngine, RealmOptions, Runtime, SIMPLE_GLOBAL_CLASS};
+
+use std::borrow::Cow;
+use std::num::NonZeroU32;
+use std::path::PathBuf;
+use std::ptr;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[test]
+fn std_conversion() {
+    let engine = JSEngine::init().unwrap();
+    let rt = Runtime::new(engine.handle());
+    let cx = rt.cx();
+    let h_option = OnNewGlobalHookOption::FireOnNewGlobalHook;
+    let c_option = RealmOptions::default();
+
+    unsafe {
+        rooted!(in(cx) let global = JS_NewGlobalObject(
+            cx,
+            &SIMPLE_GLOBAL_CLASS,
+            ptr::null_mut(),
+            h_option,
+            &*c_option,
+        ));
+        let _ac = JSAutoRealm::new(cx, global.get());
+
+        rooted!(in(cx) let mut rval = UndefinedValue());
+
+        Arc::<str>::from("shared").to_jsval(cx, rval.handle_mut());
+        assert_eq!(
+            Box::<str>::from_jsval(cx, rval.handle(), ()).unwrap(),
+            ConversionResult::Success("shared".into())
+        );
+        Cow::Borrowed("borrowed").to_jsval(cx, rval.handle_mut());
+        assert_eq!(
+            Cow::<str>::from_jsval(cx, rval.handle(), ()).unwrap(),
+            ConversionResult::Success(Cow::Borrowed("borrowed"))
+        );
+
+        '😀'.to_jsval(cx, rval.handle_mut());
+        assert_eq!(
+            char::from_jsval(cx, rval.handle(), ()).unwrap(),
+            ConversionResult::Success('😀')
+        );
+        "ab".to_jsval(cx, rval.handle_mut());
+        match char::from_jsval(cx, rval.handle(), ()) {
+            Ok(ConversionResult::Failure(_)) => {}
+            _ => panic!("'ab' is not a single character"),
+        }
+
+        0u32.to_jsval(cx, rval.handle_mut());
+        match NonZeroU32::from_jsval(cx, rval.handle(), ConversionBehavior::Default) {
+            Ok(ConversionResult::Failure(e)) => assert_eq!(e, "Value is zero"),
+            _ => panic!("zero is not a NonZeroU32"),
+        }
+        NonZeroU32::new(7).to_jsval(cx, rval.handle_mut());
+        assert_eq!(
+            NonZeroU32::from_jsval(cx, rval.handle(), ConversionBehavior::Default).unwrap(),
+            ConversionResult::Success(NonZeroU32::new(7).unwrap())
+        );
+
+        Duration::from_micros(1500).to_jsval(cx, rval.handle_mut());
+        assert_eq!(rval.to_number(), 1.5);
+        assert_eq!(
+            Duration::from_jsval(cx, rval.handle(), ()).unwrap(),
+            ConversionResult::Success(Duration::from_micros(1500))
+        );
+        (-1.0f64).to_jsval(cx, rval.handle_mut());
+        match Duration::from_jsval(cx, rval.handle(), ()) {
+            Ok(ConversionResult::Failure(_)) => {}
+            _ => panic!("a negative number is not a Duration"),
+        }
+
+        let time = UNIX_EPOCH + Duration::from_millis(1_600_000_000_000);
+        time.to_jsval(cx, rval.handle_mut());
+        assert!(JS_SetProperty(
+            cx,
+            global.handle().into(),
+            b"time\0".as_ptr() as *const _,
+            rval.handle().into(),
+        ));
+        assert!(rt
+            .evaluate_script(
+                global.handle(),
+                "time instanceof Date && time.getTime() === 1600000000000
+                    ? new Date(-86400000) : null",
+                "test",
+                1,
+                rval.handle_mut()
+            )
+            .is_ok());
+        assert_eq!(
+            SystemTime::from_jsval(cx, rval.handle(), ()).unwrap(),
+            ConversionResult::Success(UNIX_EPOCH - Duration::from_secs(86400))
+        );
+        assert!(rt
+            .evaluate_script(
+                global.handle(),
+                "new Date(NaN)",
+                "test",
+                1,
+                rval.handle_mut()
+            )
+            .is_ok());
+        match SystemTime::from_jsval(cx, rval.handle(), ()) {
+            Ok(ConversionResult::Failure(e)) => assert_eq!(e, "Date is invalid or out of range"),
+            _ => panic!("an invalid Date is not a SystemTime"),
+        }
+
+        PathBuf::from("/tmp/file.txt").to_jsval(cx, rval.handle_mut());
+        assert_eq!(
+            PathBuf::from_jsval(cx, rval.handle(), ()).unwrap(),
+            ConversionResult::Success(PathBuf::from("/tmp/file.txt"))
+        );
+    }
+}
@@ -0,0 +1,121 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+extern crate futures;
+#[macro_use]
+extern crate mozjs;
+
+use futures::executor::LocalPool;
+use futures::stream::{self, StreamExt};
+use futures::task::{noop_waker_ref, Context, Poll};
+use mozjs::conversions::ConversionBehavior;
+use mozjs::jsapi::JSAutoRealm;
+use mozjs::jsapi::JS_NewGlobalObject;
+use mozjs::jsapi::JS_SetProperty;
+use mozjs::jsapi::OnNewGlobalHookOption;
+use mozjs::jsapi::{RunJobs, UseInternalJobQueues};
+use mozjs::jsval::{ObjectValue, UndefinedValue};
+use mozjs::rust::{JSEngine, RealmOptions, Runtime, SIMPLE_GLOBAL_CLASS};
+use mozjs::stream::{new_async_iterator, AsyncForOfIter};
+
+use std::ptr;
+
+#[test]
+fn stream() {
+    let engine = JSEngine::init().unwrap();
+    let rt = Runtime::new(engine.handle());
+    let cx = rt.cx();
+    let h_option = OnNewGlobalHookOption::FireOnNewGlobalHook;
+    let c_option = RealmOptions::default();
+
+    unsafe {
+        assert!(UseInternalJobQueues(cx));
+        rooted!(in(cx) let global = JS_NewGlobalObject(
+            cx,
+            &SIMPLE_GLOBAL_CLASS,
+            ptr::null_mut(),
+            h_option,
+            &*c_option,
+        ));
+        let _ac = JSAutoRealm::new(cx, global.get());
+
+        rooted!(in(cx) let mut rval = UndefinedValue());
+        assert!(rt
+            .evaluate_script(
+                global.handle(),
+                "(async function* () { for (let i = 1; ; i++) yield i; })()",
+                "test",
+                1,
+                rval.handle_mut()
+            )
+            .is_ok());
+        let mut numbers =
+            AsyncForOfIter::<i32>::new(cx, rval.handle(), ConversionBehavior::Default).unwrap();
+        let mut task_cx = Context::from_waker(noop_waker_ref());
+        let mut values = vec![];
+        while values.len() < 3 {
+            match numbers.poll_next_unpin(&mut task_cx) {
+                Poll::Ready(Some(value)) => values.push(value.unwrap()),
+                Poll::Ready(None) => panic!("the generator ended early"),
+                Poll::Pending => RunJobs(cx),
+            }
+        }
+        assert_eq!(values, vec![1, 2, 3]);
+        drop(numbers);
+
+        assert!(rt
+            .evaluate_script(
+                global.handle(),
+                "[4, Promise.resolve(5), 6]",
+                "test",
+                1,
+                rval.handle_mut()
+            )
+            .is_ok());
+        let mut numbers =
+            AsyncForOfIter::<i32>::new(cx, rval.handle(), ConversionBehavior::Default).unwrap();
+        let mut values = vec![];
+        loop {
+            match numbers.poll_next_unpin(&mut task_cx) {
+                Poll::Ready(Some(value)) => values.push(value.unwrap()),
+                Poll::Ready(None) => break,
+                Poll::Pending => RunJobs(cx),
+            }
+        }
+        assert_eq!(values, vec![4, 5, 6]);
+        drop(numbers);
+
+        let mut pool = LocalPool::new();
+        rooted!(in(cx) let pages = new_async_iterator(
+            cx,
+            stream::iter(vec![10, 20, 30]),
+            pool.spawner(),
+        ));
+        assert!(!pages.is_null());
+        rooted!(in(cx) let pages = ObjectValue(pages.get()));
+        assert!(JS_SetProperty(
+            cx,
+            global.handle().into(),
+            b"pages\0".as_ptr() as *const _,
+            pages.handle().into(),
+        ));
+        assert!(rt
+            .evaluate_script(
+                global.handle(),
+                "var total; (async () => { let sum = 0; for await (let n of pages) sum += n; total = sum; })();",
+                "test",
+                1,
+                rval.handle_mut()
+            )
+            .is_ok());
+        for _ in 0..10 {
+            pool.run_until_stalled();
+            RunJobs(cx);
+        }
+        assert!(rt
+            .evaluate_script(global.handle(), "total", "test", 1, rval.handle_mut())
+            .is_ok());
+        assert_eq!(rval.to_int32(), 60);
+    }
+}
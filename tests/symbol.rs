@@ -0,0 +1,113 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+#[macro_use]
+extern crate mozjs;
+
+use mozjs::conversions::{ConversionResult, FromJSValConvertible, ToJSValConvertible};
+use mozjs::jsapi::CallArgs;
+use mozjs::jsapi::JSAutoRealm;
+use mozjs::jsapi::JSContext;
+use mozjs::jsapi::JS_NewGlobalObject;
+use mozjs::jsapi::JS_NewPlainObject;
+use mozjs::jsapi::JS_SetProperty;
+use mozjs::jsapi::OnNewGlobalHookOption;
+use mozjs::jsapi::Symbol as RawSymbol;
+use mozjs::jsapi::Value;
+use mozjs::jsval::{Int32Value, ObjectValue, UndefinedValue};
+use mozjs::rust::{FunctionSpecs, JSEngine, RealmOptions, Runtime, SIMPLE_GLOBAL_CLASS};
+use mozjs::symbol::{new_symbol, symbol_for, well_known_symbol, Symbol, SymbolCode};
+
+use std::ptr;
+
+unsafe extern "C" fn to_primitive(_cx: *mut JSContext, argc: u32, vp: *mut Value) -> bool {
+    let args = CallArgs::from_vp(vp, argc);
+    args.rval().set(Int32Value(42));
+    true
+}
+
+#[test]
+fn symbol() {
+    let engine = JSEngine::init().unwrap();
+    let rt = Runtime::new(engine.handle());
+    let cx = rt.cx();
+    let h_option = OnNewGlobalHookOption::FireOnNewGlobalHook;
+    let c_option = RealmOptions::default();
+
+    unsafe {
+        rooted!(in(cx) let global = JS_NewGlobalObject(
+            cx,
+            &SIMPLE_GLOBAL_CLASS,
+            ptr::null_mut(),
+            h_option,
+            &*c_option,
+        ));
+        let _ac = JSAutoRealm::new(cx, global.get());
+
+        rooted!(in(cx) let registered = symbol_for(cx, "app.id"));
+        let registered = Symbol::new(registered.handle());
+        assert!(registered.is_registered());
+        assert_eq!(registered.well_known(), None);
+        assert_eq!(registered.description(cx), Some("app.id".to_owned()));
+
+        rooted!(in(cx) let unique = new_symbol(cx, None));
+        let unique = Symbol::new(unique.handle());
+        assert!(!unique.is_registered());
+        assert_eq!(unique.description(cx), None);
+
+        rooted!(in(cx) let iterator = well_known_symbol(cx, SymbolCode::Iterator));
+        let iterator = Symbol::new(iterator.handle());
+        assert_eq!(iterator.well_known(), Some(SymbolCode::Iterator));
+
+        rooted!(in(cx) let mut val = UndefinedValue());
+        registered.to_jsval(cx, val.handle_mut());
+        assert!(JS_SetProperty(
+            cx,
+            global.handle().into(),
+            b"registered\0".as_ptr() as *const _,
+            val.handle().into(),
+        ));
+        rooted!(in(cx) let mut rval = UndefinedValue());
+        assert!(rt
+            .evaluate_script(
+                global.handle(),
+                "registered === Symbol.for('app.id')",
+                "test",
+                1,
+                rval.handle_mut()
+            )
+            .is_ok());
+        assert!(rval.to_boolean());
+
+        assert!(rt
+            .evaluate_script(
+                global.handle(),
+                "Symbol.iterator",
+                "test",
+                1,
+                rval.handle_mut()
+            )
+            .is_ok());
+        match <*mut RawSymbol>::from_jsval(cx, rval.handle(), ()) {
+            Ok(ConversionResult::Success(symbol)) => assert_eq!(symbol, iterator.get()),
+            _ => panic!("converting Symbol.iterator failed"),
+        }
+
+        rooted!(in(cx) let obj = JS_NewPlainObject(cx));
+        let specs =
+            FunctionSpecs::new().symbol_method(SymbolCode::ToPrimitive, Some(to_primitive), 1, 0);
+        assert!(specs.define(cx, obj.handle()).is_ok());
+        rooted!(in(cx) let obj_val = ObjectValue(obj.get()));
+        assert!(JS_SetProperty(
+            cx,
+            global.handle().into(),
+            b"obj\0".as_ptr() as *const _,
+            obj_val.handle().into(),
+        ));
+        assert!(rt
+            .evaluate_script(global.handle(), "+obj", "test", 1, rval.handle_mut())
+            .is_ok());
+        assert_eq!(rval.to_int32(), 42);
+    }
+}
@@ -0,0 +1,122 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+#[macro_use]
+extern crate mozjs;
+
+use mozjs::conversions::{ConversionBehavior, ConversionResult};
+use mozjs::conversions::{FromJSValConvertible, ToJSValConvertible};
+use mozjs::jsapi::JSAutoRealm;
+use mozjs::jsapi::JS_ClearPendingException;
+use mozjs::jsapi::JS_NewGlobalObject;
+use mozjs::jsapi::JS_SetProperty;
+use mozjs::jsapi::OnNewGlobalHookOption;
+use mozjs::jsval::UndefinedValue;
+use mozjs::rust::{JSEngine, RealmOptions, Runtime, SIMPLE_GLOBAL_CLASS};
+
+use std::ptr;
+
+#[test]
+fn tuple_conversion() {
+    let engine = JSEngine::init().unwrap();
+    let rt = Runtime::new(engine.handle());
+    let cx = rt.cx();
+    let h_option = OnNewGlobalHookOption::FireOnNewGlobalHook;
+    let c_option = RealmOptions::default();
+
+    unsafe {
+        rooted!(in(cx) let global = JS_NewGlobalObject(
+            cx,
+            &SIMPLE_GLOBAL_CLASS,
+            ptr::null_mut(),
+            h_option,
+            &*c_option,
+        ));
+        let _ac = JSAutoRealm::new(cx, global.get());
+
+        rooted!(in(cx) let mut rval = UndefinedValue());
+        assert!(rt
+            .evaluate_script(
+                global.handle(),
+                "Object.entries({ a: 1 })[0]",
+                "test",
+                1,
+                rval.handle_mut()
+            )
+            .is_ok());
+        assert_eq!(
+            <(String, i32)>::from_jsval(cx, rval.handle(), ((), ConversionBehavior::Default))
+                .unwrap(),
+            ConversionResult::Success(("a".to_owned(), 1))
+        );
+
+        ("x".to_owned(), 2.5, true).to_jsval(cx, rval.handle_mut());
+        assert!(JS_SetProperty(
+            cx,
+            global.handle().into(),
+            b"tuple\0".as_ptr() as *const _,
+            rval.handle().into(),
+        ));
+        assert!(rt
+            .evaluate_script(
+                global.handle(),
+                "Array.isArray(tuple) && tuple.length === 3 && tuple[1] === 2.5",
+                "test",
+                1,
+                rval.handle_mut()
+            )
+            .is_ok());
+        assert!(rval.to_boolean());
+
+        [1u8, 2, 3].to_jsval(cx, rval.handle_mut());
+        assert_eq!(
+            <[u8; 3]>::from_jsval(cx, rval.handle(), ConversionBehavior::Default).unwrap(),
+            ConversionResult::Success([1, 2, 3])
+        );
+        match <[u8; 2]>::from_jsval(cx, rval.handle(), ConversionBehavior::Default) {
+            Ok(ConversionResult::Failure(e)) => assert_eq!(e, "Expected 2 elements, got more"),
+            _ => panic!("the array was too long"),
+        }
+        match <(u8, u8, u8, u8)>::from_jsval(cx, rval.handle(), Default::default()) {
+            Ok(ConversionResult::Failure(e)) => assert_eq!(e, "Expected 4 elements, got 3"),
+            _ => panic!("the array was too short"),
+        }
+
+        assert!(rt
+            .evaluate_script(
+                global.handle(),
+                "var closed = false;
+                 var pairs = { *[Symbol.iterator]() {
+                     try { yield 1; yield 2; yield 3; } finally { closed = true; }
+                 } };
+                 pairs",
+                "test",
+                1,
+                rval.handle_mut()
+            )
+            .is_ok());
+        match <[i32; 2]>::from_jsval(cx, rval.handle(), ConversionBehavior::Default) {
+            Ok(ConversionResult::Failure(_)) => {}
+            _ => panic!("the iterable was too long"),
+        }
+        assert!(rt
+            .evaluate_script(global.handle(), "closed", "test", 1, rval.handle_mut())
+            .is_ok());
+        assert!(rval.to_boolean());
+
+        assert!(rt
+            .evaluate_script(global.handle(), "[1, 'x']", "test", 1, rval.handle_mut())
+            .is_ok());
+        assert!(<(i32, i32)>::from_jsval(
+            cx,
+            rval.handle(),
+            (
+                ConversionBehavior::Default,
+                ConversionBehavior::EnforceRange
+            )
+        )
+        .is_err());
+        JS_ClearPendingException(cx);
+    }
+}
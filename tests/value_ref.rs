@@ -0,0 +1,67 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+#[macro_use]
+extern crate mozjs;
+
+use mozjs::conversions::jsstr_to_string;
+use mozjs::jsapi::JSAutoRealm;
+use mozjs::jsapi::JS_NewGlobalObject;
+use mozjs::jsapi::OnNewGlobalHookOption;
+use mozjs::jsval::UndefinedValue;
+use mozjs::rust::{JSEngine, RealmOptions, Runtime, SIMPLE_GLOBAL_CLASS};
+use mozjs::value::ValueRef;
+
+use std::ptr;
+
+#[test]
+fn value_ref() {
+    let engine = JSEngine::init().unwrap();
+    let rt = Runtime::new(engine.handle());
+    let cx = rt.cx();
+    let h_option = OnNewGlobalHookOption::FireOnNewGlobalHook;
+    let c_option = RealmOptions::default();
+
+    unsafe {
+        rooted!(in(cx) let global = JS_NewGlobalObject(
+            cx,
+            &SIMPLE_GLOBAL_CLASS,
+            ptr::null_mut(),
+            h_option,
+            &*c_option,
+        ));
+        let _ac = JSAutoRealm::new(cx, global.get());
+
+        let describe = |script: &str| -> String {
+            rooted!(in(cx) let mut rval = UndefinedValue());
+            assert!(rt
+                .evaluate_script(global.handle(), script, "test", 1, rval.handle_mut())
+                .is_ok());
+            match ValueRef::new(rval.handle()) {
+                ValueRef::Undefined => "undefined".to_owned(),
+                ValueRef::Null => "null".to_owned(),
+                ValueRef::Boolean(b) => format!("boolean {}", b),
+                ValueRef::Int32(i) => format!("int32 {}", i),
+                ValueRef::Double(d) => format!("double {}", d),
+                ValueRef::String(s) => format!("string {}", jsstr_to_string(cx, s.get())),
+                ValueRef::Symbol(_) => "symbol".to_owned(),
+                ValueRef::BigInt(_) => "bigint".to_owned(),
+                ValueRef::Object(o) => {
+                    assert!(!o.get().is_null());
+                    "object".to_owned()
+                }
+            }
+        };
+
+        assert_eq!(describe("undefined"), "undefined");
+        assert_eq!(describe("null"), "null");
+        assert_eq!(describe("true"), "boolean true");
+        assert_eq!(describe("41 + 1"), "int32 42");
+        assert_eq!(describe("0.5"), "double 0.5");
+        assert_eq!(describe("'abc'"), "string abc");
+        assert_eq!(describe("Symbol('x')"), "symbol");
+        assert_eq!(describe("10n ** 30n"), "bigint");
+        assert_eq!(describe("({})"), "object");
+    }
+}